@@ -0,0 +1,11 @@
+CREATE TABLE `accounting_serverstateimportrun` (
+    `id` int(10) unsigned NOT NULL AUTO_INCREMENT,
+    `begin` datetime(6) NOT NULL,
+    `end` datetime(6) NOT NULL,
+    `new_state_count` int(10) unsigned NOT NULL,
+    `end_state_count` int(10) unsigned NOT NULL,
+    `new_flavor_count` int(10) unsigned DEFAULT NULL,
+    `error` text DEFAULT NULL,
+    PRIMARY KEY (`id`),
+    KEY `accounting_serverstateimportrun_begin_idx` (`begin`)
+) ENGINE=InnoDB AUTO_INCREMENT=1 DEFAULT CHARSET=utf8
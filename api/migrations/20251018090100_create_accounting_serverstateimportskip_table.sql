@@ -0,0 +1,10 @@
+CREATE TABLE `accounting_serverstateimportskip` (
+    `id` int(10) unsigned NOT NULL AUTO_INCREMENT,
+    `instance_id` varchar(36) NOT NULL,
+    `instance_name` varchar(255) NOT NULL,
+    `reason` varchar(255) NOT NULL,
+    `run_id` int(10) unsigned NOT NULL,
+    PRIMARY KEY (`id`),
+    KEY `accounting_serverstateimportskip_run_id_fk` (`run_id`),
+    CONSTRAINT `accounting_serverstateimportskip_run_id_fk` FOREIGN KEY (`run_id`) REFERENCES `accounting_serverstateimportrun` (`id`)
+) ENGINE=InnoDB AUTO_INCREMENT=1 DEFAULT CHARSET=utf8
@@ -0,0 +1,17 @@
+CREATE TABLE `accounting_serverstatearchive` (
+    -- keeps the ID of the archived accounting_state
+    `id` int(11) NOT NULL,
+    `begin` datetime(6) NOT NULL,
+    `end` datetime(6) NOT NULL,
+    `instance_id` varchar(36) NOT NULL,
+    `instance_name` varchar(255) NOT NULL,
+    `status` varchar(18) NOT NULL,
+    `flavor_id` bigint(20) NOT NULL,
+    `user_id` int(11) NOT NULL,
+    PRIMARY KEY (`id`),
+    KEY `accounting_serverstatearchive_instance_id_idx` (`instance_id`),
+    KEY `accounting_serverstatearchive_user_id_end_idx` (`user_id`, `end`),
+    KEY `accounting_serverstatearchive_flavor_id_fk` (`flavor_id`),
+    CONSTRAINT `accounting_serverstatearchive_flavor_id_fk` FOREIGN KEY (`flavor_id`) REFERENCES `resources_flavor` (`id`),
+    CONSTRAINT `accounting_serverstatearchive_user_id_fk` FOREIGN KEY (`user_id`) REFERENCES `user_user` (`id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8
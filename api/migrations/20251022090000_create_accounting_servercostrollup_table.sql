@@ -0,0 +1,20 @@
+CREATE TABLE `accounting_servercostrollup` (
+    `id` int(10) unsigned NOT NULL AUTO_INCREMENT,
+    -- midnight UTC of the rolled up day
+    `day` datetime(6) NOT NULL,
+    `instance_id` varchar(36) NOT NULL,
+    `user_id` int(11) NOT NULL,
+    `project_id` int(11) NOT NULL,
+    `flavor_id` bigint(20) NOT NULL,
+    `user_class` smallint(5) unsigned NOT NULL,
+    `cost` double NOT NULL,
+    PRIMARY KEY (`id`),
+    KEY `accounting_servercostrollup_day_idx` (`day`),
+    KEY `accounting_servercostrollup_instance_id_day_idx` (`instance_id`, `day`),
+    KEY `accounting_servercostrollup_user_id_day_idx` (`user_id`, `day`),
+    KEY `accounting_servercostrollup_project_id_day_idx` (`project_id`, `day`),
+    KEY `accounting_servercostrollup_flavor_id_fk` (`flavor_id`),
+    CONSTRAINT `accounting_servercostrollup_user_id_fk` FOREIGN KEY (`user_id`) REFERENCES `user_user` (`id`),
+    CONSTRAINT `accounting_servercostrollup_project_id_fk` FOREIGN KEY (`project_id`) REFERENCES `user_project` (`id`),
+    CONSTRAINT `accounting_servercostrollup_flavor_id_fk` FOREIGN KEY (`flavor_id`) REFERENCES `resources_flavor` (`id`)
+) ENGINE=InnoDB AUTO_INCREMENT=1 DEFAULT CHARSET=utf8
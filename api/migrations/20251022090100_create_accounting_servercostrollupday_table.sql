@@ -0,0 +1,9 @@
+CREATE TABLE `accounting_servercostrollupday` (
+    -- days listed here are fully rolled up, even when they had no cost
+    `day` datetime(6) NOT NULL,
+    -- rolled up costs depend on the billing calendar, so days rolled up with
+    -- another calendar are ignored until they are rolled up again
+    `calendar` varchar(64) NOT NULL,
+    `created` datetime(6) NOT NULL,
+    PRIMARY KEY (`day`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8
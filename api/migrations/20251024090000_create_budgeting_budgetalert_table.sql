@@ -0,0 +1,18 @@
+CREATE TABLE `budgeting_budgetalert` (
+    `id` int(10) unsigned NOT NULL AUTO_INCREMENT,
+    -- exactly one of the two budgets is set
+    `user_budget_id` int(11) DEFAULT NULL,
+    `project_budget_id` int(11) DEFAULT NULL,
+    -- percentage of the budget amount
+    `threshold` int(10) unsigned NOT NULL,
+    -- alerts are recorded per notification channel, so that a channel that
+    -- failed still receives them later
+    `channel` varchar(20) NOT NULL,
+    `cost` double NOT NULL,
+    `created` datetime(6) NOT NULL,
+    PRIMARY KEY (`id`),
+    UNIQUE KEY `budgeting_budgetalert_user_budget_id_threshold_channel_uniq` (`user_budget_id`, `threshold`, `channel`),
+    UNIQUE KEY `budgeting_budgetalert_project_budget_id_threshold_channel_uniq` (`project_budget_id`, `threshold`, `channel`),
+    CONSTRAINT `budgeting_budgetalert_user_budget_id_fk` FOREIGN KEY (`user_budget_id`) REFERENCES `budgeting_userbudget` (`id`) ON DELETE CASCADE,
+    CONSTRAINT `budgeting_budgetalert_project_budget_id_fk` FOREIGN KEY (`project_budget_id`) REFERENCES `budgeting_projectbudget` (`id`) ON DELETE CASCADE
+) ENGINE=InnoDB AUTO_INCREMENT=1 DEFAULT CHARSET=utf8
@@ -0,0 +1,8 @@
+CREATE TABLE `budgeting_userbudgetover` (
+    `user_budget_id` int(11) NOT NULL,
+    -- first enforcement run that found the combined budget check over,
+    -- the row is removed as soon as a run finds it no longer over
+    `since` datetime(6) NOT NULL,
+    PRIMARY KEY (`user_budget_id`),
+    CONSTRAINT `budgeting_userbudgetover_user_budget_id_fk` FOREIGN KEY (`user_budget_id`) REFERENCES `budgeting_userbudget` (`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8
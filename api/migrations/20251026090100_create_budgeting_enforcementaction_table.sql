@@ -0,0 +1,14 @@
+CREATE TABLE `budgeting_enforcementaction` (
+    `id` int(10) unsigned NOT NULL AUTO_INCREMENT,
+    `user_id` int(11) NOT NULL,
+    `instance_id` varchar(36) NOT NULL,
+    `instance_name` varchar(255) NOT NULL,
+    -- lock, shelve or stop
+    `action` varchar(10) NOT NULL,
+    -- set when Nova rejected the action
+    `error` text DEFAULT NULL,
+    `created` datetime(6) NOT NULL,
+    PRIMARY KEY (`id`),
+    KEY `budgeting_enforcementaction_instance_id_created_idx` (`instance_id`, `created`),
+    CONSTRAINT `budgeting_enforcementaction_user_id_fk` FOREIGN KEY (`user_id`) REFERENCES `user_user` (`id`)
+) ENGINE=InnoDB AUTO_INCREMENT=1 DEFAULT CHARSET=utf8
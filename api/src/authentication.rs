@@ -7,9 +7,13 @@ use actix_web::{
 };
 use avina_wire::user::{Project, User};
 use sqlx::MySqlPool;
+use tracing::Instrument;
 
 use crate::{
-    error::{bad_request_error, internal_server_error, unauthorized_error},
+    error::{
+        bad_request_error, forbidden_error, internal_server_error,
+        not_found_error, unauthorized_error,
+    },
     openstack::{OpenStack, ProjectMinimal as OpenstackProjectMinimal},
 };
 
@@ -37,6 +41,71 @@ pub async fn require_valid_token(
     next.call(req).await
 }
 
+struct UserAndProjectRow {
+    user_id: i32,
+    user_name: String,
+    user_openstack_id: String,
+    user_role: u32,
+    user_is_staff: i8,
+    user_is_active: i8,
+    project_id: i32,
+    project_name: String,
+    project_openstack_id: String,
+    project_user_class: u32,
+}
+
+impl UserAndProjectRow {
+    fn into_user_and_project(self) -> (User, Project) {
+        let user = User {
+            id: self.user_id as u32,
+            name: self.user_name,
+            openstack_id: self.user_openstack_id,
+            project: self.project_id as u32,
+            project_name: self.project_name.clone(),
+            role: self.user_role,
+            is_staff: self.user_is_staff != 0,
+            is_active: self.user_is_active != 0,
+        };
+        let project = Project {
+            id: self.project_id as u32,
+            name: self.project_name,
+            openstack_id: self.project_openstack_id,
+            user_class: self.project_user_class,
+        };
+        (user, project)
+    }
+}
+
+async fn select_impersonated_user_and_project(
+    db_pool: &MySqlPool,
+    user_id: u32,
+) -> Result<Option<(User, Project)>, sqlx::Error> {
+    let row = sqlx::query_as!(
+        UserAndProjectRow,
+        r#"
+        SELECT
+            user.id AS user_id,
+            user.name AS user_name,
+            user.openstack_id AS user_openstack_id,
+            user.role AS user_role,
+            user.is_staff AS user_is_staff,
+            user.is_active AS user_is_active,
+            project.id AS project_id,
+            project.name AS project_name,
+            project.openstack_id AS project_openstack_id,
+            project.user_class AS project_user_class
+        FROM user_user AS user, user_project AS project
+        WHERE
+            user.project_id = project.id AND
+            user.id = ?
+        "#,
+        user_id
+    )
+    .fetch_optional(db_pool)
+    .await?;
+    Ok(row.map(UserAndProjectRow::into_user_and_project))
+}
+
 pub async fn extract_user_and_project(
     req: ServiceRequest,
     next: Next<impl MessageBody>,
@@ -55,21 +124,8 @@ pub async fn extract_user_and_project(
         ));
     };
 
-    struct Row {
-        user_id: i32,
-        user_name: String,
-        user_openstack_id: String,
-        user_role: u32,
-        user_is_staff: i8,
-        user_is_active: i8,
-        project_id: i32,
-        project_name: String,
-        project_openstack_id: String,
-        project_user_class: u32,
-    }
-
     let Ok(row) = sqlx::query_as!(
-        Row,
+        UserAndProjectRow,
         r#"
         SELECT
             user.id AS user_id,
@@ -97,26 +153,60 @@ pub async fn extract_user_and_project(
             "Failed to retrieve user and project from database",
         ));
     };
+    let (actor, actor_project) = row.into_user_and_project();
 
-    let user = User {
-        id: row.user_id as u32,
-        name: row.user_name,
-        openstack_id: row.user_openstack_id,
-        project: row.project_id as u32,
-        project_name: row.project_name.clone(),
-        role: row.user_role,
-        is_staff: row.user_is_staff != 0,
-        is_active: row.user_is_active != 0,
+    let Some(impersonate) = req.headers().get("X-Impersonate") else {
+        req.extensions_mut().insert(actor);
+        req.extensions_mut().insert(actor_project);
+        return next.call(req).await;
     };
-    let project = Project {
-        id: row.project_id as u32,
-        name: row.project_name,
-        openstack_id: row.project_openstack_id,
-        user_class: row.project_user_class,
+    let Some(impersonate) = impersonate
+        .to_str()
+        .ok()
+        .and_then(|i| i.trim().parse::<u32>().ok())
+    else {
+        return Err(bad_request_error(
+            "Impersonate header is not a valid user ID",
+        ));
+    };
+    if !actor.is_staff {
+        return Err(forbidden_error(
+            "Admin privileges required for impersonation",
+        ));
+    }
+    let (user, project) = match select_impersonated_user_and_project(
+        db_pool.get_ref(),
+        impersonate,
+    )
+    .await
+    {
+        Ok(Some(user_and_project)) => user_and_project,
+        Ok(None) => {
+            return Err(not_found_error("Impersonated user not found"));
+        }
+        Err(_) => {
+            return Err(internal_server_error(
+                "Failed to retrieve impersonated user from database",
+            ));
+        }
     };
 
+    let span = tracing::info_span!(
+        "impersonation",
+        actor_id = actor.id,
+        actor_name = %actor.name,
+        user_id = user.id,
+        user_name = %user.name,
+    );
+    tracing::info!(
+        parent: &span,
+        "User {} impersonates user {}",
+        actor.name,
+        user.name
+    );
+
     req.extensions_mut().insert(user);
     req.extensions_mut().insert(project);
 
-    next.call(req).await
+    next.call(req).instrument(span).await
 }
@@ -0,0 +1,320 @@
+use avina_wire::accounting::TimeGrouping;
+use chrono::{
+    DateTime, Datelike, FixedOffset, Months, NaiveDate, NaiveTime, TimeDelta,
+    TimeZone, Utc,
+};
+
+use crate::configuration::{BillingSettings, YearLength};
+
+// begin and end of a period of the billing calendar
+pub type Bucket = (DateTime<Utc>, DateTime<Utc>);
+
+impl Default for BillingSettings {
+    fn default() -> Self {
+        Self {
+            timezone: chrono_tz::Europe::Berlin,
+            year_length: YearLength::Calendar,
+        }
+    }
+}
+
+impl BillingSettings {
+    pub fn start_of_the_year(&self, year: u32) -> DateTime<Utc> {
+        self.start_of_the_day(
+            NaiveDate::from_ymd_opt(year as i32, 1, 1)
+                .unwrap_or(NaiveDate::MAX),
+        )
+    }
+
+    pub fn start_of_the_day(&self, date: NaiveDate) -> DateTime<Utc> {
+        let midnight = date.and_time(NaiveTime::MIN);
+        // midnight inside a daylight saving gap starts the day at the end of
+        // the gap, which is one hour later for usual daylight saving shifts
+        self.timezone
+            .from_local_datetime(&midnight)
+            .earliest()
+            .or_else(|| {
+                self.timezone
+                    .from_local_datetime(&(midnight + TimeDelta::hours(1)))
+                    .earliest()
+            })
+            .map(|time| time.to_utc())
+            .unwrap_or_else(|| Utc.from_utc_datetime(&midnight))
+    }
+
+    // identifies the calendar costs were calculated with, rolled up costs
+    // of another calendar are outdated
+    pub fn key(&self) -> String {
+        let year_length = match self.year_length {
+            YearLength::Calendar => "calendar",
+            YearLength::Fixed => "fixed",
+        };
+        format!("{} {}", self.timezone.name(), year_length)
+    }
+
+    pub fn local(&self, time: DateTime<Utc>) -> DateTime<FixedOffset> {
+        time.with_timezone(&self.timezone).fixed_offset()
+    }
+
+    pub fn year_of(&self, time: DateTime<Utc>) -> u32 {
+        time.with_timezone(&self.timezone).year() as u32
+    }
+
+    pub fn start_of_the_current_year(&self) -> DateTime<Utc> {
+        self.start_of_the_year(self.year_of(Utc::now()))
+    }
+
+    pub fn seconds_in_the_year(&self, year: u32) -> f64 {
+        match self.year_length {
+            YearLength::Calendar => (self.start_of_the_year(year + 1)
+                - self.start_of_the_year(year))
+            .num_seconds() as f64,
+            YearLength::Fixed => (365 * 24 * 60 * 60) as f64,
+        }
+    }
+
+    // starts of the billing years strictly between begin and end
+    pub fn starts_of_the_years_between(
+        &self,
+        begin: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Vec<DateTime<Utc>> {
+        if end <= begin {
+            return vec![];
+        }
+        (self.year_of(begin) + 1..=self.year_of(end))
+            .map(|year| self.start_of_the_year(year))
+            .filter(|start| *start > begin && *start < end)
+            .collect()
+    }
+
+    // splits [begin, end) into consecutive buckets at the starts of the
+    // years, months or days of the calendar, the first and last bucket are
+    // cut to begin and end, returns none for more than max buckets
+    pub fn buckets(
+        &self,
+        begin: DateTime<Utc>,
+        end: DateTime<Utc>,
+        by: &TimeGrouping,
+        max: usize,
+    ) -> Option<Vec<Bucket>> {
+        let local_begin = begin.with_timezone(&self.timezone).date_naive();
+        let mut date = match by {
+            TimeGrouping::Year => local_begin.with_ordinal(1),
+            TimeGrouping::Month => local_begin.with_day(1),
+            TimeGrouping::Day => Some(local_begin),
+        };
+        let mut buckets = vec![];
+        let mut bucket_begin = begin;
+        while let Some(current) = date
+            && bucket_begin < end
+        {
+            let next = match by {
+                TimeGrouping::Year => {
+                    current.checked_add_months(Months::new(12))
+                }
+                TimeGrouping::Month => {
+                    current.checked_add_months(Months::new(1))
+                }
+                TimeGrouping::Day => current.succ_opt(),
+            };
+            let bucket_end = next
+                .map(|next| self.start_of_the_day(next).min(end))
+                .unwrap_or(end);
+            if buckets.len() == max {
+                return None;
+            }
+            buckets.push((bucket_begin, bucket_end));
+            bucket_begin = bucket_end;
+            date = next;
+        }
+        Some(buckets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono_tz::Tz;
+
+    use super::*;
+
+    fn calendar(timezone: Tz, year_length: YearLength) -> BillingSettings {
+        BillingSettings {
+            timezone,
+            year_length,
+        }
+    }
+
+    #[test]
+    fn year_starts_at_local_midnight() {
+        let berlin = calendar(chrono_tz::Europe::Berlin, YearLength::Calendar);
+        assert_eq!(
+            berlin.start_of_the_year(2024),
+            Utc.with_ymd_and_hms(2023, 12, 31, 23, 0, 0).unwrap()
+        );
+        let utc = calendar(chrono_tz::UTC, YearLength::Calendar);
+        assert_eq!(
+            utc.start_of_the_year(2024),
+            Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn first_hour_of_the_year_belongs_to_the_new_year() {
+        let berlin = calendar(chrono_tz::Europe::Berlin, YearLength::Calendar);
+        let new_year = berlin.start_of_the_year(2025);
+        assert_eq!(berlin.year_of(new_year), 2025);
+        assert_eq!(berlin.year_of(new_year + TimeDelta::minutes(30)), 2025);
+        assert_eq!(berlin.year_of(new_year - TimeDelta::seconds(1)), 2024);
+    }
+
+    #[test]
+    fn calendar_year_length_handles_leap_years() {
+        let berlin = calendar(chrono_tz::Europe::Berlin, YearLength::Calendar);
+        assert_eq!(berlin.seconds_in_the_year(2023), 365. * 24. * 3600.);
+        assert_eq!(berlin.seconds_in_the_year(2024), 366. * 24. * 3600.);
+        assert_eq!(berlin.seconds_in_the_year(2100), 365. * 24. * 3600.);
+        assert_eq!(berlin.seconds_in_the_year(2000), 366. * 24. * 3600.);
+    }
+
+    #[test]
+    fn fixed_year_length_ignores_leap_years() {
+        let berlin = calendar(chrono_tz::Europe::Berlin, YearLength::Fixed);
+        assert_eq!(berlin.seconds_in_the_year(2024), 365. * 24. * 3600.);
+    }
+
+    #[test]
+    fn daylight_saving_time_does_not_change_the_year_length() {
+        // new year falls into summer time in the southern hemisphere
+        let sydney =
+            calendar(chrono_tz::Australia::Sydney, YearLength::Calendar);
+        assert_eq!(
+            sydney.start_of_the_year(2024),
+            Utc.with_ymd_and_hms(2023, 12, 31, 13, 0, 0).unwrap()
+        );
+        assert_eq!(sydney.seconds_in_the_year(2024), 366. * 24. * 3600.);
+        assert_eq!(sydney.seconds_in_the_year(2023), 365. * 24. * 3600.);
+    }
+
+    #[test]
+    fn year_of_is_stable_across_daylight_saving_transitions() {
+        let berlin = calendar(chrono_tz::Europe::Berlin, YearLength::Calendar);
+        // clocks go forward on 2024-03-31 at 01:00 UTC and back on
+        // 2024-10-27 at 01:00 UTC
+        for time in [
+            Utc.with_ymd_and_hms(2024, 3, 31, 0, 59, 59).unwrap(),
+            Utc.with_ymd_and_hms(2024, 3, 31, 1, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2024, 10, 27, 0, 59, 59).unwrap(),
+            Utc.with_ymd_and_hms(2024, 10, 27, 1, 0, 0).unwrap(),
+        ] {
+            assert_eq!(berlin.year_of(time), 2024);
+        }
+    }
+
+    #[test]
+    fn key_differs_between_calendars() {
+        let berlin = calendar(chrono_tz::Europe::Berlin, YearLength::Calendar);
+        assert_eq!(berlin.key(), "Europe/Berlin calendar");
+        assert_ne!(
+            berlin.key(),
+            calendar(chrono_tz::Europe::Berlin, YearLength::Fixed).key()
+        );
+        assert_ne!(
+            berlin.key(),
+            calendar(chrono_tz::UTC, YearLength::Calendar).key()
+        );
+    }
+
+    #[test]
+    fn starts_of_the_years_between_excludes_bounds() {
+        let berlin = calendar(chrono_tz::Europe::Berlin, YearLength::Calendar);
+        let begin = berlin.start_of_the_year(2023);
+        let end = berlin.start_of_the_year(2026);
+        assert_eq!(
+            berlin.starts_of_the_years_between(begin, end),
+            vec![
+                berlin.start_of_the_year(2024),
+                berlin.start_of_the_year(2025)
+            ]
+        );
+        assert!(berlin.starts_of_the_years_between(end, begin).is_empty());
+    }
+
+    #[test]
+    fn buckets_split_at_local_month_starts() {
+        let berlin = calendar(chrono_tz::Europe::Berlin, YearLength::Calendar);
+        let begin = Utc.with_ymd_and_hms(2023, 11, 15, 12, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2024, 2, 10, 0, 0, 0).unwrap();
+        assert_eq!(
+            berlin
+                .buckets(begin, end, &TimeGrouping::Month, usize::MAX)
+                .unwrap(),
+            vec![
+                (begin, Utc.with_ymd_and_hms(2023, 11, 30, 23, 0, 0).unwrap()),
+                (
+                    Utc.with_ymd_and_hms(2023, 11, 30, 23, 0, 0).unwrap(),
+                    Utc.with_ymd_and_hms(2023, 12, 31, 23, 0, 0).unwrap()
+                ),
+                (
+                    Utc.with_ymd_and_hms(2023, 12, 31, 23, 0, 0).unwrap(),
+                    Utc.with_ymd_and_hms(2024, 1, 31, 23, 0, 0).unwrap()
+                ),
+                (Utc.with_ymd_and_hms(2024, 1, 31, 23, 0, 0).unwrap(), end),
+            ]
+        );
+    }
+
+    #[test]
+    fn buckets_split_at_local_year_starts() {
+        let berlin = calendar(chrono_tz::Europe::Berlin, YearLength::Calendar);
+        let begin = Utc.with_ymd_and_hms(2023, 7, 1, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2025, 6, 30, 0, 0, 0).unwrap();
+        assert_eq!(
+            berlin
+                .buckets(begin, end, &TimeGrouping::Year, usize::MAX)
+                .unwrap(),
+            vec![
+                (begin, berlin.start_of_the_year(2024)),
+                (
+                    berlin.start_of_the_year(2024),
+                    berlin.start_of_the_year(2025)
+                ),
+                (berlin.start_of_the_year(2025), end),
+            ]
+        );
+    }
+
+    #[test]
+    fn day_buckets_follow_daylight_saving_transitions() {
+        let berlin = calendar(chrono_tz::Europe::Berlin, YearLength::Calendar);
+        let begin = Utc.with_ymd_and_hms(2024, 3, 30, 23, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2024, 4, 1, 22, 0, 0).unwrap();
+        let buckets = berlin
+            .buckets(begin, end, &TimeGrouping::Day, usize::MAX)
+            .unwrap();
+        assert_eq!(buckets.len(), 2);
+        // the 31st of March has only 23 hours in Europe/Berlin
+        assert_eq!(buckets[0].1 - buckets[0].0, TimeDelta::hours(23));
+        assert_eq!(buckets[1].1 - buckets[1].0, TimeDelta::hours(24));
+        assert!(
+            berlin
+                .buckets(end, begin, &TimeGrouping::Day, usize::MAX)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn buckets_beyond_max_are_refused() {
+        let berlin = calendar(chrono_tz::Europe::Berlin, YearLength::Calendar);
+        let begin = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2024, 1, 4, 0, 0, 0).unwrap();
+        assert_eq!(
+            berlin
+                .buckets(begin, end, &TimeGrouping::Day, 4)
+                .map(|buckets| buckets.len()),
+            Some(4)
+        );
+        assert!(berlin.buckets(begin, end, &TimeGrouping::Day, 3).is_none());
+    }
+}
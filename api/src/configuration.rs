@@ -1,3 +1,5 @@
+use avina_wire::budgeting::EnforcementAction;
+use chrono_tz::Tz;
 use secrecy::{ExposeSecret, SecretString};
 use serde::Deserialize;
 use serde_aux::field_attributes::deserialize_number_from_string;
@@ -8,6 +10,11 @@ pub struct Settings {
     pub database: DatabaseSettings,
     pub application: ApplicationSettings,
     pub openstack: OpenStackSettings,
+    pub pricing: PricingSettings,
+    pub scheduler: SchedulerSettings,
+    pub billing: BillingSettings,
+    pub alerts: AlertSettings,
+    pub enforcement: EnforcementSettings,
 }
 
 #[derive(Clone, serde::Deserialize)]
@@ -51,6 +58,108 @@ pub struct OpenStackSettings {
     pub domain_id: String,
     pub keystone_endpoint: String,
     pub nova_endpoint: String,
+    // number of servers requested per page from Nova
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub server_page_size: u32,
+}
+
+#[derive(Clone, serde::Deserialize)]
+pub struct PricingSettings {
+    // yearly unit price per flavor weight
+    pub unit_price_per_weight: f64,
+    // factor applied to the unit price, indexed by user class minus one
+    pub user_class_factors: Vec<f64>,
+}
+
+#[derive(Clone, serde::Deserialize)]
+pub struct SchedulerSettings {
+    // periodically run the flavor and server state import
+    pub enabled: bool,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub interval_seconds: u64,
+    // random delay added to each interval
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub jitter_seconds: u64,
+    // every n-th scheduled import lists all servers, the ones in between
+    // only list the servers changed since the last successful import
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub full_import_every: u32,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct BillingSettings {
+    // timezone in which billing years begin
+    pub timezone: Tz,
+    pub year_length: YearLength,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum YearLength {
+    // length of the billing year, 366 days in leap years
+    Calendar,
+    // always 365 days
+    Fixed,
+}
+
+#[derive(Clone, serde::Deserialize)]
+pub struct AlertSettings {
+    // percentages of the budget amount, each alerted once per budget
+    pub thresholds: Vec<u32>,
+    pub smtp: Option<SmtpSettings>,
+    pub webhook: Option<WebhookSettings>,
+}
+
+#[derive(Clone, serde::Deserialize)]
+pub struct SmtpSettings {
+    pub host: String,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub port: u16,
+    pub tls: SmtpTls,
+    pub username: Option<String>,
+    pub password: Option<SecretString>,
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+#[derive(Clone, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpTls {
+    // plain connection, only meant for a relay on the same host
+    None,
+    Starttls,
+    Tls,
+}
+
+#[derive(Clone, serde::Deserialize)]
+pub struct WebhookSettings {
+    // receives each alert as JSON in a POST request
+    pub url: String,
+}
+
+#[derive(Clone, serde::Deserialize)]
+pub struct EnforcementSettings {
+    // run after each server state import
+    pub enabled: bool,
+    pub action: EnforcementAction,
+    // how long a user has to be over before their servers are acted on
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub grace_period_hours: u64,
+    // names of users and projects whose servers are never acted on
+    pub allow_list: Vec<String>,
+    // only report the actions instead of taking them
+    pub dry_run: bool,
+}
+
+impl PricingSettings {
+    pub fn unit_price(&self, weight: u32, user_class: u32) -> f64 {
+        let factor = (user_class as usize)
+            .checked_sub(1)
+            .and_then(|i| self.user_class_factors.get(i))
+            .copied()
+            .unwrap_or(1.0);
+        weight as f64 * self.unit_price_per_weight * factor
+    }
 }
 
 impl DatabaseSettings {
@@ -1 +1,4 @@
+pub mod server_cost_rollup;
 pub mod server_state;
+pub mod server_state_archive;
+pub mod server_state_import_run;
@@ -0,0 +1,271 @@
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, FromRow, MySql, Transaction};
+
+use crate::error::UnexpectedOnlyError;
+
+pub struct NewServerCostRollup {
+    pub instance_id: String,
+    pub user_id: u32,
+    pub project_id: u32,
+    pub flavor_id: u32,
+    pub user_class: u32,
+    pub cost: f64,
+}
+
+#[derive(FromRow)]
+pub struct ServerCostRollupRow {
+    pub instance_id: String,
+    pub username: String,
+    pub project_name: String,
+    pub flavor_name: String,
+    pub cost: f64,
+}
+
+#[derive(Debug)]
+pub enum ServerCostRollupFilter {
+    Server(String),
+    User(u64),
+    Project(u64),
+    All,
+}
+
+impl ServerCostRollupFilter {
+    fn into_ids(self) -> (Option<String>, Option<u64>, Option<u64>) {
+        match self {
+            ServerCostRollupFilter::Server(server_id) => {
+                (Some(server_id), None, None)
+            }
+            ServerCostRollupFilter::User(user_id) => {
+                (None, Some(user_id), None)
+            }
+            ServerCostRollupFilter::Project(project_id) => {
+                (None, None, Some(project_id))
+            }
+            ServerCostRollupFilter::All => (None, None, None),
+        }
+    }
+}
+
+#[tracing::instrument(
+    name = "select_server_cost_rollup_days_from_db",
+    skip(transaction)
+)]
+pub async fn select_server_cost_rollup_days_from_db(
+    transaction: &mut Transaction<'_, MySql>,
+    calendar: &str,
+    since: Option<DateTime<Utc>>,
+) -> Result<Vec<DateTime<Utc>>, UnexpectedOnlyError> {
+    #[derive(FromRow)]
+    struct Row {
+        day: DateTime<Utc>,
+    }
+    let query = sqlx::query!(
+        r#"
+        SELECT day
+        FROM accounting_servercostrollupday
+        WHERE calendar = ? AND (? IS NULL OR day >= ?)
+        ORDER BY day
+        "#,
+        calendar,
+        since,
+        since
+    );
+    let days = transaction
+        .fetch_all(query)
+        .await
+        .context("Failed to execute select query")?
+        .into_iter()
+        .map(|r| Row::from_row(&r).map(|r| r.day))
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to convert row to server cost rollup day")?;
+    Ok(days)
+}
+
+#[tracing::instrument(
+    name = "select_server_cost_rollup_days_by_filter_from_db",
+    skip(transaction)
+)]
+pub async fn select_server_cost_rollup_days_by_filter_from_db(
+    transaction: &mut Transaction<'_, MySql>,
+    calendar: &str,
+    filter: ServerCostRollupFilter,
+) -> Result<Vec<DateTime<Utc>>, UnexpectedOnlyError> {
+    #[derive(FromRow)]
+    struct Row {
+        day: DateTime<Utc>,
+    }
+    let (server_id, user_id, project_id) = filter.into_ids();
+    let query = sqlx::query!(
+        r#"
+        SELECT d.day as day
+        FROM
+            accounting_servercostrollup as r,
+            accounting_servercostrollupday as d
+        WHERE
+            r.day = d.day AND
+            d.calendar = ? AND
+            (? IS NULL OR r.instance_id = ?) AND
+            (? IS NULL OR r.user_id = ?) AND
+            (? IS NULL OR r.project_id = ?)
+        GROUP BY d.day
+        ORDER BY d.day
+        "#,
+        calendar,
+        server_id,
+        server_id,
+        user_id,
+        user_id,
+        project_id,
+        project_id
+    );
+    let days = transaction
+        .fetch_all(query)
+        .await
+        .context("Failed to execute select query")?
+        .into_iter()
+        .map(|r| Row::from_row(&r).map(|r| r.day))
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to convert row to server cost rollup day")?;
+    Ok(days)
+}
+
+#[tracing::instrument(
+    name = "delete_server_cost_rollup_day_from_db",
+    skip(transaction)
+)]
+pub async fn delete_server_cost_rollup_day_from_db(
+    transaction: &mut Transaction<'_, MySql>,
+    day: DateTime<Utc>,
+) -> Result<(), UnexpectedOnlyError> {
+    let query1 = sqlx::query!(
+        r#"
+        DELETE FROM accounting_servercostrollup
+        WHERE day = ?
+        "#,
+        day
+    );
+    transaction
+        .execute(query1)
+        .await
+        .context("Failed to execute delete query")?;
+    let query2 = sqlx::query!(
+        r#"
+        DELETE FROM accounting_servercostrollupday
+        WHERE day = ?
+        "#,
+        day
+    );
+    transaction
+        .execute(query2)
+        .await
+        .context("Failed to execute delete query")?;
+    Ok(())
+}
+
+#[tracing::instrument(
+    name = "insert_server_cost_rollup_day_into_db",
+    skip(rollups, transaction)
+)]
+pub async fn insert_server_cost_rollup_day_into_db(
+    transaction: &mut Transaction<'_, MySql>,
+    calendar: &str,
+    day: DateTime<Utc>,
+    rollups: &[NewServerCostRollup],
+) -> Result<(), UnexpectedOnlyError> {
+    for rollup in rollups {
+        let query = sqlx::query!(
+            r#"
+            INSERT INTO accounting_servercostrollup (
+                day, instance_id, user_id, project_id, flavor_id, user_class,
+                cost
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+            day,
+            rollup.instance_id,
+            rollup.user_id,
+            rollup.project_id,
+            rollup.flavor_id,
+            rollup.user_class,
+            rollup.cost
+        );
+        transaction
+            .execute(query)
+            .await
+            .context("Failed to execute insert query")?;
+    }
+    let query = sqlx::query!(
+        r#"
+        INSERT INTO accounting_servercostrollupday (day, created, calendar)
+        VALUES (?, ?, ?)
+        "#,
+        day,
+        Utc::now(),
+        calendar
+    );
+    transaction
+        .execute(query)
+        .await
+        .context("Failed to execute insert query")?;
+    Ok(())
+}
+
+#[tracing::instrument(
+    name = "select_server_cost_rollups_from_db",
+    skip(transaction)
+)]
+pub async fn select_server_cost_rollups_from_db(
+    transaction: &mut Transaction<'_, MySql>,
+    calendar: &str,
+    begin: DateTime<Utc>,
+    end: DateTime<Utc>,
+    filter: ServerCostRollupFilter,
+) -> Result<Vec<ServerCostRollupRow>, UnexpectedOnlyError> {
+    let (server_id, user_id, project_id) = filter.into_ids();
+    let query = sqlx::query!(
+        r#"
+        SELECT
+            r.instance_id as instance_id,
+            u.name as username,
+            p.name as project_name,
+            f.name as flavor_name,
+            r.cost as cost
+        FROM
+            accounting_servercostrollup as r,
+            accounting_servercostrollupday as d,
+            user_user as u,
+            user_project as p,
+            resources_flavor as f
+        WHERE
+            r.day = d.day AND
+            r.user_id = u.id AND
+            r.project_id = p.id AND
+            r.flavor_id = f.id AND
+            d.calendar = ? AND
+            r.day >= ? AND
+            r.day < ? AND
+            (? IS NULL OR r.instance_id = ?) AND
+            (? IS NULL OR r.user_id = ?) AND
+            (? IS NULL OR r.project_id = ?)
+        "#,
+        calendar,
+        begin,
+        end,
+        server_id,
+        server_id,
+        user_id,
+        user_id,
+        project_id,
+        project_id
+    );
+    let rows = transaction
+        .fetch_all(query)
+        .await
+        .context("Failed to execute select query")?
+        .into_iter()
+        .map(|r| ServerCostRollupRow::from_row(&r))
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to convert row to server cost rollup")?;
+    Ok(rows)
+}
@@ -1,8 +1,15 @@
-use anyhow::Context;
+use anyhow::{Context, anyhow};
 use avina_wire::accounting::{ServerState, ServerStateCreateData};
 use chrono::{DateTime, Utc};
 use sqlx::{Executor, FromRow, MySql, Transaction};
 
+use super::server_state_archive::{
+    select_maybe_archived_server_state_user_from_db,
+    select_maybe_archived_user_class_by_server_from_db,
+    select_ordered_archived_server_states_by_begin_and_end_from_db,
+    select_ordered_archived_server_states_by_server_begin_and_end_from_db,
+    select_ordered_archived_server_states_by_user_begin_and_end_from_db,
+};
 use crate::error::{
     MinimalApiError, NotFoundOrUnexpectedApiError, UnexpectedOnlyError,
 };
@@ -374,7 +381,89 @@ pub async fn select_user_class_by_server_from_db(
         })
         .map_or(Ok(None), |r| r.map(Some))?
         .map(|r| r.user_class);
-    Ok(user_class)
+    if user_class.is_some() {
+        return Ok(user_class);
+    }
+    select_maybe_archived_user_class_by_server_from_db(transaction, server_id)
+        .await
+}
+
+#[tracing::instrument(
+    name = "select_user_id_by_server_from_db",
+    skip(transaction)
+)]
+pub async fn select_user_id_by_server_from_db(
+    transaction: &mut Transaction<'_, MySql>,
+    server_id: String,
+) -> Result<u32, UnexpectedOnlyError> {
+    #[derive(FromRow)]
+    struct Row {
+        #[sqlx(try_from = "i32")]
+        user_id: u32,
+    }
+    let query = sqlx::query!(
+        r#"
+        SELECT user_id
+        FROM accounting_serverstate
+        WHERE instance_id = ?
+        LIMIT 1
+        "#,
+        server_id
+    );
+    let row = transaction
+        .fetch_optional(query)
+        .await
+        .context("Failed to execute select query")?;
+    if let Some(row) = row {
+        return Ok(Row::from_row(&row)
+            .context("Failed to parse server state row")?
+            .user_id);
+    }
+    // the states of the server might all be archived already
+    select_maybe_archived_server_state_user_from_db(transaction, server_id)
+        .await?
+        .ok_or_else(|| anyhow!("No server state found for server").into())
+}
+
+#[tracing::instrument(
+    name = "select_maybe_last_server_state_end_by_server_from_db",
+    skip(transaction)
+)]
+pub async fn select_maybe_last_server_state_end_by_server_from_db(
+    transaction: &mut Transaction<'_, MySql>,
+    server_id: String,
+) -> Result<Option<DateTime<Utc>>, UnexpectedOnlyError> {
+    #[derive(FromRow)]
+    struct Row {
+        end: Option<DateTime<Utc>>,
+    }
+    let query = sqlx::query!(
+        r#"
+        SELECT s.end as end
+        FROM
+            accounting_state as s,
+            accounting_serverstate as ss
+        WHERE
+            ss.state_ptr_id = s.id AND
+            s.end IS NOT NULL AND
+            ss.instance_id = ?
+        ORDER BY s.end DESC
+        LIMIT 1
+        "#,
+        server_id
+    );
+    let row = transaction
+        .fetch_optional(query)
+        .await
+        .context("Failed to execute select query")?;
+    Ok(match row {
+        Some(row) => {
+            Row::from_row(&row)
+                .context("Failed to parse server state row")?
+                .end
+        }
+        None => None,
+    })
 }
 
 #[tracing::instrument(
@@ -585,6 +674,31 @@ pub async fn insert_server_state_into_db(
     Ok(id)
 }
 
+#[tracing::instrument(
+    name = "update_server_state_end_in_db",
+    skip(transaction)
+)]
+pub async fn update_server_state_end_in_db(
+    transaction: &mut Transaction<'_, MySql>,
+    server_state_id: u64,
+    end: Option<DateTime<Utc>>,
+) -> Result<(), UnexpectedOnlyError> {
+    let query = sqlx::query!(
+        r#"
+        UPDATE accounting_state
+        SET end = ?
+        WHERE id = ?
+        "#,
+        end,
+        server_state_id,
+    );
+    transaction
+        .execute(query)
+        .await
+        .context("Failed to execute update query")?;
+    Ok(())
+}
+
 #[tracing::instrument(
     name = "select_ordered_server_states_by_server_begin_and_end_from_db",
     skip(transaction)
@@ -745,6 +859,18 @@ pub async fn select_ordered_server_states_by_server_begin_and_end_from_db(
             username: r.username,
         })
         .collect::<Vec<_>>();
+    // archived states are older, but merge by ID to keep the usual order
+    let mut rows = rows;
+    rows.extend(
+        select_ordered_archived_server_states_by_server_begin_and_end_from_db(
+            transaction,
+            server_id,
+            begin,
+            end,
+        )
+        .await?,
+    );
+    rows.sort_by_key(|s| s.id);
     Ok(rows)
 }
 
@@ -908,6 +1034,93 @@ pub async fn select_ordered_server_states_by_user_begin_and_end_from_db(
             username: r.username,
         })
         .collect::<Vec<_>>();
+    // archived states are older, but merge by ID to keep the usual order
+    let mut rows = rows;
+    rows.extend(
+        select_ordered_archived_server_states_by_user_begin_and_end_from_db(
+            transaction,
+            user_id,
+            begin,
+            end,
+        )
+        .await?,
+    );
+    rows.sort_by_key(|s| s.id);
+    Ok(rows)
+}
+
+#[tracing::instrument(
+    name = "select_ordered_server_states_by_begin_and_end_from_db",
+    skip(transaction)
+)]
+pub async fn select_ordered_server_states_by_begin_and_end_from_db(
+    transaction: &mut Transaction<'_, MySql>,
+    begin: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+) -> Result<Vec<ServerState>, UnexpectedOnlyError> {
+    let query = sqlx::query!(
+        r#"
+        SELECT
+            s.id as id,
+            s.begin as begin,
+            s.end as end,
+            ss.instance_id as instance_id,
+            ss.instance_name as instance_name,
+            f.id as flavor,
+            f.name as flavor_name,
+            ss.status as status,
+            u.id as user,
+            u.name as username
+        FROM
+            accounting_state as s,
+            accounting_serverstate as ss,
+            resources_flavor as f,
+            user_user as u
+        WHERE
+            ss.flavor_id = f.id AND
+            ss.user_id = u.id AND
+            ss.state_ptr_id = s.id AND
+            (? IS NULL OR s.end > ? OR s.end IS NULL) AND
+            (? IS NULL OR s.begin < ?)
+        ORDER BY s.id
+        "#,
+        begin,
+        begin,
+        end,
+        end
+    );
+    let mut rows = transaction
+        .fetch_all(query)
+        .await
+        .context("Failed to execute select query")?
+        .into_iter()
+        .map(|r| ServerStateRow::from_row(&r))
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to convert row to server state")?
+        .into_iter()
+        .map(|r| ServerState {
+            id: r.id,
+            begin: r.begin.fixed_offset(),
+            end: r.end.map(|end| end.fixed_offset()),
+            instance_id: r.instance_id,
+            instance_name: r.instance_name,
+            flavor: r.flavor,
+            flavor_name: r.flavor_name,
+            status: r.status,
+            user: r.user,
+            username: r.username,
+        })
+        .collect::<Vec<_>>();
+    // archived states are older, but merge by ID to keep the usual order
+    rows.extend(
+        select_ordered_archived_server_states_by_begin_and_end_from_db(
+            transaction,
+            begin,
+            end,
+        )
+        .await?,
+    );
+    rows.sort_by_key(|s| s.id);
     Ok(rows)
 }
 
@@ -967,3 +1180,123 @@ pub async fn select_unfinished_server_states_from_db(
         .collect::<Vec<_>>();
     Ok(rows)
 }
+
+#[tracing::instrument(
+    name = "select_unfinished_server_states_by_user_from_db",
+    skip(transaction)
+)]
+pub async fn select_unfinished_server_states_by_user_from_db(
+    transaction: &mut Transaction<'_, MySql>,
+    user_id: u64,
+) -> Result<Vec<ServerState>, UnexpectedOnlyError> {
+    let query = sqlx::query!(
+        r#"
+        SELECT
+            s.id as id,
+            s.begin as begin,
+            s.end as end,
+            ss.instance_id as instance_id,
+            ss.instance_name as instance_name,
+            f.id as flavor,
+            f.name as flavor_name,
+            ss.status as status,
+            u.id as user,
+            u.name as username
+        FROM
+            accounting_state as s,
+            accounting_serverstate as ss,
+            resources_flavor as f,
+            user_user as u
+        WHERE
+            ss.flavor_id = f.id AND
+            ss.user_id = u.id AND
+            ss.state_ptr_id = s.id AND
+            s.end is NULL AND
+            u.id = ?
+        "#,
+        user_id
+    );
+    let rows = transaction
+        .fetch_all(query)
+        .await
+        .context("Failed to execute select query")?
+        .into_iter()
+        .map(|r| ServerStateRow::from_row(&r))
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to convert row to server state")?
+        .into_iter()
+        .map(|r| ServerState {
+            id: r.id,
+            begin: r.begin.fixed_offset(),
+            end: r.end.map(|end| end.fixed_offset()),
+            instance_id: r.instance_id,
+            instance_name: r.instance_name,
+            flavor: r.flavor,
+            flavor_name: r.flavor_name,
+            status: r.status,
+            user: r.user,
+            username: r.username,
+        })
+        .collect::<Vec<_>>();
+    Ok(rows)
+}
+
+#[tracing::instrument(
+    name = "select_unfinished_server_states_by_project_from_db",
+    skip(transaction)
+)]
+pub async fn select_unfinished_server_states_by_project_from_db(
+    transaction: &mut Transaction<'_, MySql>,
+    project_id: u64,
+) -> Result<Vec<ServerState>, UnexpectedOnlyError> {
+    let query = sqlx::query!(
+        r#"
+        SELECT
+            s.id as id,
+            s.begin as begin,
+            s.end as end,
+            ss.instance_id as instance_id,
+            ss.instance_name as instance_name,
+            f.id as flavor,
+            f.name as flavor_name,
+            ss.status as status,
+            u.id as user,
+            u.name as username
+        FROM
+            accounting_state as s,
+            accounting_serverstate as ss,
+            resources_flavor as f,
+            user_user as u
+        WHERE
+            ss.flavor_id = f.id AND
+            ss.user_id = u.id AND
+            ss.state_ptr_id = s.id AND
+            s.end is NULL AND
+            u.project_id = ?
+        "#,
+        project_id
+    );
+    let rows = transaction
+        .fetch_all(query)
+        .await
+        .context("Failed to execute select query")?
+        .into_iter()
+        .map(|r| ServerStateRow::from_row(&r))
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to convert row to server state")?
+        .into_iter()
+        .map(|r| ServerState {
+            id: r.id,
+            begin: r.begin.fixed_offset(),
+            end: r.end.map(|end| end.fixed_offset()),
+            instance_id: r.instance_id,
+            instance_name: r.instance_name,
+            flavor: r.flavor,
+            flavor_name: r.flavor_name,
+            status: r.status,
+            user: r.user,
+            username: r.username,
+        })
+        .collect::<Vec<_>>();
+    Ok(rows)
+}
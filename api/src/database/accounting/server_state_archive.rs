@@ -0,0 +1,317 @@
+use anyhow::Context;
+use avina_wire::accounting::ServerState;
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, FromRow, MySql, Transaction};
+
+use super::server_state::ServerStateRow;
+use crate::error::UnexpectedOnlyError;
+
+fn server_state_from_row(row: ServerStateRow) -> ServerState {
+    ServerState {
+        id: row.id,
+        begin: row.begin.fixed_offset(),
+        end: row.end.map(|end| end.fixed_offset()),
+        instance_id: row.instance_id,
+        instance_name: row.instance_name,
+        flavor: row.flavor,
+        flavor_name: row.flavor_name,
+        status: row.status,
+        user: row.user,
+        username: row.username,
+    }
+}
+
+#[tracing::instrument(name = "archive_server_states_in_db", skip(transaction))]
+pub async fn archive_server_states_in_db(
+    transaction: &mut Transaction<'_, MySql>,
+    before: DateTime<Utc>,
+) -> Result<u64, UnexpectedOnlyError> {
+    let query1 = sqlx::query!(
+        r#"
+        INSERT INTO accounting_serverstatearchive (
+            id, begin, end, instance_id, instance_name, status, flavor_id,
+            user_id
+        )
+        SELECT
+            s.id, s.begin, s.end, ss.instance_id, ss.instance_name,
+            ss.status, ss.flavor_id, ss.user_id
+        FROM
+            accounting_state as s,
+            accounting_serverstate as ss
+        WHERE
+            ss.state_ptr_id = s.id AND
+            s.end IS NOT NULL AND
+            s.end < ?
+        "#,
+        before
+    );
+    let result = transaction
+        .execute(query1)
+        .await
+        .context("Failed to execute insert query")?;
+    let query2 = sqlx::query!(
+        r#"
+        DELETE ss
+        FROM
+            accounting_serverstate as ss,
+            accounting_serverstatearchive as a
+        WHERE ss.state_ptr_id = a.id
+        "#,
+    );
+    transaction
+        .execute(query2)
+        .await
+        .context("Failed to execute delete query")?;
+    let query3 = sqlx::query!(
+        r#"
+        DELETE s
+        FROM
+            accounting_state as s,
+            accounting_serverstatearchive as a
+        WHERE s.id = a.id
+        "#,
+    );
+    transaction
+        .execute(query3)
+        .await
+        .context("Failed to execute delete query")?;
+    Ok(result.rows_affected())
+}
+
+#[tracing::instrument(
+    name = "select_ordered_archived_server_states_by_begin_and_end_from_db",
+    skip(transaction)
+)]
+pub async fn select_ordered_archived_server_states_by_begin_and_end_from_db(
+    transaction: &mut Transaction<'_, MySql>,
+    begin: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+) -> Result<Vec<ServerState>, UnexpectedOnlyError> {
+    let query = sqlx::query!(
+        r#"
+        SELECT
+            a.id as id,
+            a.begin as begin,
+            a.end as end,
+            a.instance_id as instance_id,
+            a.instance_name as instance_name,
+            f.id as flavor,
+            f.name as flavor_name,
+            a.status as status,
+            u.id as user,
+            u.name as username
+        FROM
+            accounting_serverstatearchive as a,
+            resources_flavor as f,
+            user_user as u
+        WHERE
+            a.flavor_id = f.id AND
+            a.user_id = u.id AND
+            (? IS NULL OR a.end > ?) AND
+            (? IS NULL OR a.begin < ?)
+        ORDER BY a.id
+        "#,
+        begin,
+        begin,
+        end,
+        end
+    );
+    let rows = transaction
+        .fetch_all(query)
+        .await
+        .context("Failed to execute select query")?
+        .into_iter()
+        .map(|r| ServerStateRow::from_row(&r))
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to convert row to server state")?
+        .into_iter()
+        .map(server_state_from_row)
+        .collect::<Vec<_>>();
+    Ok(rows)
+}
+
+#[tracing::instrument(
+    name = "select_ordered_archived_server_states_by_server_begin_and_end_from_db",
+    skip(transaction)
+)]
+pub async fn select_ordered_archived_server_states_by_server_begin_and_end_from_db(
+    transaction: &mut Transaction<'_, MySql>,
+    server_id: String,
+    begin: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+) -> Result<Vec<ServerState>, UnexpectedOnlyError> {
+    let query = sqlx::query!(
+        r#"
+        SELECT
+            a.id as id,
+            a.begin as begin,
+            a.end as end,
+            a.instance_id as instance_id,
+            a.instance_name as instance_name,
+            f.id as flavor,
+            f.name as flavor_name,
+            a.status as status,
+            u.id as user,
+            u.name as username
+        FROM
+            accounting_serverstatearchive as a,
+            resources_flavor as f,
+            user_user as u
+        WHERE
+            a.flavor_id = f.id AND
+            a.user_id = u.id AND
+            a.instance_id = ? AND
+            (? IS NULL OR a.end > ?) AND
+            (? IS NULL OR a.begin < ?)
+        ORDER BY a.id
+        "#,
+        server_id,
+        begin,
+        begin,
+        end,
+        end
+    );
+    let rows = transaction
+        .fetch_all(query)
+        .await
+        .context("Failed to execute select query")?
+        .into_iter()
+        .map(|r| ServerStateRow::from_row(&r))
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to convert row to server state")?
+        .into_iter()
+        .map(server_state_from_row)
+        .collect::<Vec<_>>();
+    Ok(rows)
+}
+
+#[tracing::instrument(
+    name = "select_ordered_archived_server_states_by_user_begin_and_end_from_db",
+    skip(transaction)
+)]
+pub async fn select_ordered_archived_server_states_by_user_begin_and_end_from_db(
+    transaction: &mut Transaction<'_, MySql>,
+    user_id: u64,
+    begin: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+) -> Result<Vec<ServerState>, UnexpectedOnlyError> {
+    let query = sqlx::query!(
+        r#"
+        SELECT
+            a.id as id,
+            a.begin as begin,
+            a.end as end,
+            a.instance_id as instance_id,
+            a.instance_name as instance_name,
+            f.id as flavor,
+            f.name as flavor_name,
+            a.status as status,
+            u.id as user,
+            u.name as username
+        FROM
+            accounting_serverstatearchive as a,
+            resources_flavor as f,
+            user_user as u
+        WHERE
+            a.flavor_id = f.id AND
+            a.user_id = u.id AND
+            a.user_id = ? AND
+            (? IS NULL OR a.end > ?) AND
+            (? IS NULL OR a.begin < ?)
+        ORDER BY a.id
+        "#,
+        user_id,
+        begin,
+        begin,
+        end,
+        end
+    );
+    let rows = transaction
+        .fetch_all(query)
+        .await
+        .context("Failed to execute select query")?
+        .into_iter()
+        .map(|r| ServerStateRow::from_row(&r))
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to convert row to server state")?
+        .into_iter()
+        .map(server_state_from_row)
+        .collect::<Vec<_>>();
+    Ok(rows)
+}
+
+#[tracing::instrument(
+    name = "select_maybe_archived_server_state_user_from_db",
+    skip(transaction)
+)]
+pub async fn select_maybe_archived_server_state_user_from_db(
+    transaction: &mut Transaction<'_, MySql>,
+    server_id: String,
+) -> Result<Option<u32>, UnexpectedOnlyError> {
+    #[derive(FromRow)]
+    struct Row {
+        #[sqlx(try_from = "i32")]
+        user_id: u32,
+    }
+    let query = sqlx::query!(
+        r#"
+        SELECT user_id
+        FROM accounting_serverstatearchive
+        WHERE instance_id = ?
+        LIMIT 1
+        "#,
+        server_id
+    );
+    let row = transaction
+        .fetch_optional(query)
+        .await
+        .context("Failed to execute select query")?;
+    Ok(match row {
+        Some(row) => Some(
+            Row::from_row(&row)
+                .context("Failed to parse archived server state row")?
+                .user_id,
+        ),
+        None => None,
+    })
+}
+
+#[tracing::instrument(
+    name = "select_maybe_archived_user_class_by_server_from_db",
+    skip(transaction)
+)]
+pub async fn select_maybe_archived_user_class_by_server_from_db(
+    transaction: &mut Transaction<'_, MySql>,
+    server_id: String,
+) -> Result<Option<u64>, UnexpectedOnlyError> {
+    #[derive(FromRow)]
+    struct Row {
+        user_class: u64,
+    }
+    let query = sqlx::query!(
+        r#"
+        SELECT
+            p.user_class as user_class
+        FROM
+            accounting_serverstatearchive as a,
+            user_user as u,
+            user_project as p
+        WHERE
+            a.user_id = u.id AND
+            u.project_id = p.id AND
+            a.instance_id = ?
+        LIMIT 1
+        "#,
+        server_id
+    );
+    let user_class = transaction
+        .fetch_optional(query)
+        .await
+        .context("Failed to execute select query")?
+        .map(|r| {
+            Row::from_row(&r).context("Failed to convert row to user class")
+        })
+        .map_or(Ok(None), |r| r.map(Some))?
+        .map(|r| r.user_class);
+    Ok(user_class)
+}
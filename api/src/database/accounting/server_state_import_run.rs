@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use avina_wire::accounting::{ServerStateImportRun, ServerStateImportSkip};
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, FromRow, MySql, Transaction};
+
+use crate::error::{NotFoundOrUnexpectedApiError, UnexpectedOnlyError};
+
+pub struct NewServerStateImportRun {
+    pub begin: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub new_state_count: u32,
+    pub end_state_count: u32,
+    pub new_flavor_count: Option<u32>,
+    pub error: Option<String>,
+    pub skipped: Vec<ServerStateImportSkip>,
+}
+
+#[derive(FromRow)]
+struct ServerStateImportRunRow {
+    id: u32,
+    begin: DateTime<Utc>,
+    end: DateTime<Utc>,
+    new_state_count: u32,
+    end_state_count: u32,
+    new_flavor_count: Option<u32>,
+    error: Option<String>,
+}
+
+#[derive(FromRow)]
+struct ServerStateImportSkipRow {
+    run_id: u32,
+    instance_id: String,
+    instance_name: String,
+    reason: String,
+}
+
+fn server_state_import_run_from_row(
+    row: ServerStateImportRunRow,
+    skipped: Vec<ServerStateImportSkip>,
+) -> ServerStateImportRun {
+    ServerStateImportRun {
+        id: row.id,
+        begin: row.begin.fixed_offset(),
+        end: row.end.fixed_offset(),
+        duration: (row.end - row.begin).num_milliseconds() as f64 / 1000.0,
+        new_state_count: row.new_state_count,
+        end_state_count: row.end_state_count,
+        new_flavor_count: row.new_flavor_count,
+        error: row.error,
+        skipped,
+    }
+}
+
+#[tracing::instrument(
+    name = "insert_server_state_import_run_into_db",
+    skip(transaction, new_import_run)
+)]
+pub async fn insert_server_state_import_run_into_db(
+    transaction: &mut Transaction<'_, MySql>,
+    new_import_run: &NewServerStateImportRun,
+) -> Result<u64, UnexpectedOnlyError> {
+    let query = sqlx::query!(
+        r#"
+        INSERT INTO accounting_serverstateimportrun (
+            begin, end, new_state_count, end_state_count, new_flavor_count,
+            error
+        )
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+        new_import_run.begin,
+        new_import_run.end,
+        new_import_run.new_state_count,
+        new_import_run.end_state_count,
+        new_import_run.new_flavor_count,
+        new_import_run.error,
+    );
+    let id = transaction
+        .execute(query)
+        .await
+        .context("Failed to execute insert query")?
+        .last_insert_id();
+    for skip in new_import_run.skipped.iter() {
+        let query = sqlx::query!(
+            r#"
+            INSERT INTO accounting_serverstateimportskip (
+                instance_id, instance_name, reason, run_id
+            )
+            VALUES (?, ?, ?, ?)
+            "#,
+            skip.instance_id,
+            skip.instance_name,
+            skip.reason,
+            id,
+        );
+        transaction
+            .execute(query)
+            .await
+            .context("Failed to execute insert query")?;
+    }
+    Ok(id)
+}
+
+#[tracing::instrument(
+    name = "select_server_state_import_runs_from_db",
+    skip(transaction)
+)]
+pub async fn select_server_state_import_runs_from_db(
+    transaction: &mut Transaction<'_, MySql>,
+    since: DateTime<Utc>,
+    limit: u32,
+) -> Result<Vec<ServerStateImportRun>, UnexpectedOnlyError> {
+    let query = sqlx::query!(
+        r#"
+        SELECT
+            id,
+            begin,
+            end,
+            new_state_count,
+            end_state_count,
+            new_flavor_count,
+            error
+        FROM accounting_serverstateimportrun
+        WHERE begin >= ?
+        ORDER BY begin DESC
+        LIMIT ?
+        "#,
+        since,
+        limit,
+    );
+    let rows = transaction
+        .fetch_all(query)
+        .await
+        .context("Failed to execute select query")?
+        .into_iter()
+        .map(|r| ServerStateImportRunRow::from_row(&r))
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to convert row to server state import run")?;
+    // only load the skips in the id range of the selected runs
+    let (Some(min_id), Some(max_id)) = (
+        rows.iter().map(|r| r.id).min(),
+        rows.iter().map(|r| r.id).max(),
+    ) else {
+        return Ok(Vec::new());
+    };
+    let query = sqlx::query!(
+        r#"
+        SELECT
+            run_id,
+            instance_id,
+            instance_name,
+            reason
+        FROM accounting_serverstateimportskip
+        WHERE run_id >= ? AND run_id <= ?
+        ORDER BY id
+        "#,
+        min_id,
+        max_id,
+    );
+    let mut skipped = HashMap::<u32, Vec<ServerStateImportSkip>>::new();
+    for row in transaction
+        .fetch_all(query)
+        .await
+        .context("Failed to execute select query")?
+    {
+        let row = ServerStateImportSkipRow::from_row(&row)
+            .context("Failed to convert row to server state import skip")?;
+        skipped
+            .entry(row.run_id)
+            .or_default()
+            .push(ServerStateImportSkip {
+                instance_id: row.instance_id,
+                instance_name: row.instance_name,
+                reason: row.reason,
+            });
+    }
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let skipped = skipped.remove(&row.id).unwrap_or_default();
+            server_state_import_run_from_row(row, skipped)
+        })
+        .collect())
+}
+
+#[tracing::instrument(
+    name = "select_maybe_server_state_import_run_from_db",
+    skip(transaction)
+)]
+pub async fn select_maybe_server_state_import_run_from_db(
+    transaction: &mut Transaction<'_, MySql>,
+    import_run_id: u64,
+) -> Result<Option<ServerStateImportRun>, UnexpectedOnlyError> {
+    let query = sqlx::query!(
+        r#"
+        SELECT
+            id,
+            begin,
+            end,
+            new_state_count,
+            end_state_count,
+            new_flavor_count,
+            error
+        FROM accounting_serverstateimportrun
+        WHERE id = ?
+        "#,
+        import_run_id
+    );
+    let Some(row) = transaction
+        .fetch_optional(query)
+        .await
+        .context("Failed to execute select query")?
+    else {
+        return Ok(None);
+    };
+    let row = ServerStateImportRunRow::from_row(&row)
+        .context("Failed to convert row to server state import run")?;
+    let query = sqlx::query!(
+        r#"
+        SELECT
+            run_id,
+            instance_id,
+            instance_name,
+            reason
+        FROM accounting_serverstateimportskip
+        WHERE run_id = ?
+        ORDER BY id
+        "#,
+        import_run_id
+    );
+    let skipped = transaction
+        .fetch_all(query)
+        .await
+        .context("Failed to execute select query")?
+        .into_iter()
+        .map(|r| ServerStateImportSkipRow::from_row(&r))
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to convert row to server state import skip")?
+        .into_iter()
+        .map(|r| ServerStateImportSkip {
+            instance_id: r.instance_id,
+            instance_name: r.instance_name,
+            reason: r.reason,
+        })
+        .collect();
+    Ok(Some(server_state_import_run_from_row(row, skipped)))
+}
+
+#[tracing::instrument(
+    name = "select_server_state_import_run_from_db",
+    skip(transaction)
+)]
+pub async fn select_server_state_import_run_from_db(
+    transaction: &mut Transaction<'_, MySql>,
+    import_run_id: u64,
+) -> Result<ServerStateImportRun, NotFoundOrUnexpectedApiError> {
+    select_maybe_server_state_import_run_from_db(transaction, import_run_id)
+        .await?
+        .ok_or(NotFoundOrUnexpectedApiError::NotFoundError)
+}
+
+// begin of the last import run without an error, incremental imports only
+// need the servers changed since then
+#[tracing::instrument(
+    name = "select_maybe_last_successful_import_begin_from_db",
+    skip(transaction)
+)]
+pub async fn select_maybe_last_successful_import_begin_from_db(
+    transaction: &mut Transaction<'_, MySql>,
+) -> Result<Option<DateTime<Utc>>, UnexpectedOnlyError> {
+    #[derive(FromRow)]
+    struct Row {
+        begin: DateTime<Utc>,
+    }
+    let query = sqlx::query!(
+        r#"
+        SELECT begin
+        FROM accounting_serverstateimportrun
+        WHERE error IS NULL
+        ORDER BY begin DESC
+        LIMIT 1
+        "#,
+    );
+    let row = transaction
+        .fetch_optional(query)
+        .await
+        .context("Failed to execute select query")?;
+    Ok(match row {
+        Some(row) => Some(
+            Row::from_row(&row)
+                .context("Failed to convert row to server state import run")?
+                .begin,
+        ),
+        None => None,
+    })
+}
@@ -0,0 +1,102 @@
+use std::collections::HashSet;
+
+use anyhow::Context;
+use avina_wire::budgeting::BudgetAlertKind;
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, FromRow, MySql, Transaction};
+
+use crate::error::UnexpectedOnlyError;
+
+pub struct NewBudgetAlert {
+    pub kind: BudgetAlertKind,
+    pub budget_id: u32,
+    pub threshold: u32,
+    pub channel: String,
+    pub cost: f64,
+    pub created: DateTime<Utc>,
+}
+
+#[derive(FromRow)]
+struct BudgetAlertThresholdRow {
+    user_budget_id: Option<i32>,
+    project_budget_id: Option<i32>,
+    threshold: u32,
+    channel: String,
+}
+
+// (kind, budget ID, threshold, channel) of every alert sent so far
+#[tracing::instrument(
+    name = "select_budget_alert_thresholds_from_db",
+    skip(transaction)
+)]
+pub async fn select_budget_alert_thresholds_from_db(
+    transaction: &mut Transaction<'_, MySql>,
+) -> Result<HashSet<(BudgetAlertKind, u32, u32, String)>, UnexpectedOnlyError> {
+    let query = sqlx::query!(
+        r#"
+        SELECT user_budget_id, project_budget_id, threshold, channel
+        FROM budgeting_budgetalert
+        "#,
+    );
+    let rows = transaction
+        .fetch_all(query)
+        .await
+        .context("Failed to execute select query")?
+        .into_iter()
+        .map(|r| BudgetAlertThresholdRow::from_row(&r))
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to convert row to budget alert threshold")?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|row| match (row.user_budget_id, row.project_budget_id) {
+            (Some(id), _) => Some((
+                BudgetAlertKind::User,
+                id as u32,
+                row.threshold,
+                row.channel,
+            )),
+            (None, Some(id)) => Some((
+                BudgetAlertKind::Project,
+                id as u32,
+                row.threshold,
+                row.channel,
+            )),
+            (None, None) => None,
+        })
+        .collect())
+}
+
+#[tracing::instrument(
+    name = "insert_budget_alert_into_db",
+    skip(transaction, new_budget_alert)
+)]
+pub async fn insert_budget_alert_into_db(
+    transaction: &mut Transaction<'_, MySql>,
+    new_budget_alert: &NewBudgetAlert,
+) -> Result<u64, UnexpectedOnlyError> {
+    let (user_budget_id, project_budget_id) = match new_budget_alert.kind {
+        BudgetAlertKind::User => (Some(new_budget_alert.budget_id), None),
+        BudgetAlertKind::Project => (None, Some(new_budget_alert.budget_id)),
+    };
+    let query = sqlx::query!(
+        r#"
+        INSERT INTO budgeting_budgetalert (
+            user_budget_id, project_budget_id, threshold, channel, cost,
+            created
+        )
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+        user_budget_id,
+        project_budget_id,
+        new_budget_alert.threshold,
+        new_budget_alert.channel,
+        new_budget_alert.cost,
+        new_budget_alert.created,
+    );
+    let id = transaction
+        .execute(query)
+        .await
+        .context("Failed to execute insert query")?
+        .last_insert_id();
+    Ok(id)
+}
@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, anyhow};
+use avina_wire::budgeting::{BudgetEnforcementAction, EnforcementAction};
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, FromRow, MySql, Transaction};
+
+use crate::error::UnexpectedOnlyError;
+
+pub struct NewEnforcementAction {
+    pub user_id: u32,
+    pub instance_id: String,
+    pub instance_name: String,
+    pub action: EnforcementAction,
+    pub error: Option<String>,
+    pub created: DateTime<Utc>,
+}
+
+#[derive(FromRow)]
+struct UserBudgetOverRow {
+    user_budget_id: i32,
+    since: DateTime<Utc>,
+}
+
+#[derive(FromRow)]
+struct EnforcementActionRow {
+    user_id: i32,
+    user_name: String,
+    instance_id: String,
+    instance_name: String,
+    action: String,
+    error: Option<String>,
+    created: DateTime<Utc>,
+}
+
+fn enforcement_action_from_str(
+    action: &str,
+) -> Result<EnforcementAction, UnexpectedOnlyError> {
+    match action {
+        "lock" => Ok(EnforcementAction::Lock),
+        "shelve" => Ok(EnforcementAction::Shelve),
+        "stop" => Ok(EnforcementAction::Stop),
+        other => Err(anyhow!("Unknown enforcement action {other}").into()),
+    }
+}
+
+fn enforcement_action_from_row(
+    row: EnforcementActionRow,
+) -> Result<BudgetEnforcementAction, UnexpectedOnlyError> {
+    Ok(BudgetEnforcementAction {
+        user_id: row.user_id as u32,
+        user_name: row.user_name,
+        server_id: row.instance_id,
+        server_name: row.instance_name,
+        action: enforcement_action_from_str(&row.action)?,
+        dry_run: false,
+        error: row.error,
+        created: row.created.fixed_offset(),
+    })
+}
+
+// user budget ID mapped to the time since when it has been over
+#[tracing::instrument(
+    name = "select_user_budget_overs_from_db",
+    skip(transaction)
+)]
+pub async fn select_user_budget_overs_from_db(
+    transaction: &mut Transaction<'_, MySql>,
+) -> Result<HashMap<u32, DateTime<Utc>>, UnexpectedOnlyError> {
+    let query = sqlx::query!(
+        r#"
+        SELECT user_budget_id, since
+        FROM budgeting_userbudgetover
+        "#,
+    );
+    let rows = transaction
+        .fetch_all(query)
+        .await
+        .context("Failed to execute select query")?
+        .into_iter()
+        .map(|r| UserBudgetOverRow::from_row(&r))
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to convert row to user budget over")?;
+    Ok(rows
+        .into_iter()
+        .map(|row| (row.user_budget_id as u32, row.since))
+        .collect())
+}
+
+#[tracing::instrument(
+    name = "insert_user_budget_over_into_db",
+    skip(transaction)
+)]
+pub async fn insert_user_budget_over_into_db(
+    transaction: &mut Transaction<'_, MySql>,
+    user_budget_id: u32,
+    since: DateTime<Utc>,
+) -> Result<(), UnexpectedOnlyError> {
+    let query = sqlx::query!(
+        r#"
+        INSERT INTO budgeting_userbudgetover (user_budget_id, since)
+        VALUES (?, ?)
+        "#,
+        user_budget_id,
+        since,
+    );
+    transaction
+        .execute(query)
+        .await
+        .context("Failed to execute insert query")?;
+    Ok(())
+}
+
+#[tracing::instrument(
+    name = "delete_user_budget_over_from_db",
+    skip(transaction)
+)]
+pub async fn delete_user_budget_over_from_db(
+    transaction: &mut Transaction<'_, MySql>,
+    user_budget_id: u32,
+) -> Result<(), UnexpectedOnlyError> {
+    let query = sqlx::query!(
+        r#"
+        DELETE FROM budgeting_userbudgetover
+        WHERE user_budget_id = ?
+        "#,
+        user_budget_id,
+    );
+    transaction
+        .execute(query)
+        .await
+        .context("Failed to execute delete query")?;
+    Ok(())
+}
+
+#[tracing::instrument(
+    name = "select_enforcement_actions_from_db",
+    skip(transaction)
+)]
+pub async fn select_enforcement_actions_from_db(
+    transaction: &mut Transaction<'_, MySql>,
+) -> Result<Vec<BudgetEnforcementAction>, UnexpectedOnlyError> {
+    let query = sqlx::query!(
+        r#"
+        SELECT
+            a.user_id as user_id,
+            u.name as user_name,
+            a.instance_id as instance_id,
+            a.instance_name as instance_name,
+            a.action as action,
+            a.error as error,
+            a.created as created
+        FROM budgeting_enforcementaction as a, user_user as u
+        WHERE a.user_id = u.id
+        ORDER BY a.id
+        "#,
+    );
+    transaction
+        .fetch_all(query)
+        .await
+        .context("Failed to execute select query")?
+        .into_iter()
+        .map(|r| EnforcementActionRow::from_row(&r))
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to convert row to enforcement action")?
+        .into_iter()
+        .map(enforcement_action_from_row)
+        .collect()
+}
+
+#[tracing::instrument(
+    name = "select_enforcement_actions_by_user_since_from_db",
+    skip(transaction)
+)]
+pub async fn select_enforcement_actions_by_user_since_from_db(
+    transaction: &mut Transaction<'_, MySql>,
+    user_id: u32,
+    since: DateTime<Utc>,
+) -> Result<Vec<BudgetEnforcementAction>, UnexpectedOnlyError> {
+    let query = sqlx::query!(
+        r#"
+        SELECT
+            a.user_id as user_id,
+            u.name as user_name,
+            a.instance_id as instance_id,
+            a.instance_name as instance_name,
+            a.action as action,
+            a.error as error,
+            a.created as created
+        FROM budgeting_enforcementaction as a, user_user as u
+        WHERE
+            a.user_id = u.id AND
+            a.user_id = ? AND
+            a.created >= ?
+        ORDER BY a.id
+        "#,
+        user_id,
+        since,
+    );
+    transaction
+        .fetch_all(query)
+        .await
+        .context("Failed to execute select query")?
+        .into_iter()
+        .map(|r| EnforcementActionRow::from_row(&r))
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to convert row to enforcement action")?
+        .into_iter()
+        .map(enforcement_action_from_row)
+        .collect()
+}
+
+#[tracing::instrument(
+    name = "insert_enforcement_action_into_db",
+    skip(transaction, new_enforcement_action)
+)]
+pub async fn insert_enforcement_action_into_db(
+    transaction: &mut Transaction<'_, MySql>,
+    new_enforcement_action: &NewEnforcementAction,
+) -> Result<u64, UnexpectedOnlyError> {
+    let action = new_enforcement_action.action.to_string();
+    let query = sqlx::query!(
+        r#"
+        INSERT INTO budgeting_enforcementaction (
+            user_id, instance_id, instance_name, action, error, created
+        )
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+        new_enforcement_action.user_id,
+        new_enforcement_action.instance_id,
+        new_enforcement_action.instance_name,
+        action,
+        new_enforcement_action.error,
+        new_enforcement_action.created,
+    );
+    let id = transaction
+        .execute(query)
+        .await
+        .context("Failed to execute insert query")?
+        .last_insert_id();
+    Ok(id)
+}
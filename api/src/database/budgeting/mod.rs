@@ -1,2 +1,4 @@
+pub mod budget_alert;
+pub mod budget_enforcement;
 pub mod project_budget;
 pub mod user_budget;
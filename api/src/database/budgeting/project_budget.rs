@@ -1,6 +1,5 @@
 use anyhow::Context;
 use avina_wire::budgeting::{ProjectBudget, ProjectBudgetCreateData};
-use chrono::{Datelike, Utc};
 use sqlx::{Executor, FromRow, MySql, Transaction};
 
 use crate::error::{
@@ -230,7 +229,7 @@ impl TryFrom<ProjectBudgetCreateData> for NewProjectBudget {
     fn try_from(data: ProjectBudgetCreateData) -> Result<Self, Self::Error> {
         Ok(Self {
             project_id: data.project as u64,
-            year: data.year.unwrap_or(Utc::now().year() as u32),
+            year: data.year.ok_or("Missing budget year".to_string())?,
             amount: data.amount.unwrap_or(0),
         })
     }
@@ -1,10 +1,13 @@
 use anyhow::Context;
 use avina_wire::budgeting::{UserBudget, UserBudgetCreateData};
-use chrono::{Datelike, Utc};
+use chrono::Utc;
 use sqlx::{Executor, FromRow, MySql, Transaction};
 
-use crate::error::{
-    MinimalApiError, NotFoundOrUnexpectedApiError, UnexpectedOnlyError,
+use crate::{
+    configuration::BillingSettings,
+    error::{
+        MinimalApiError, NotFoundOrUnexpectedApiError, UnexpectedOnlyError,
+    },
 };
 
 #[tracing::instrument(
@@ -258,7 +261,7 @@ impl TryFrom<UserBudgetCreateData> for NewUserBudget {
     fn try_from(data: UserBudgetCreateData) -> Result<Self, Self::Error> {
         Ok(Self {
             user_id: data.user as u64,
-            year: data.year.unwrap_or(Utc::now().year() as u32),
+            year: data.year.ok_or("Missing budget year".to_string())?,
             amount: data.amount.unwrap_or(0),
         })
     }
@@ -299,8 +302,9 @@ pub async fn insert_user_budget_into_db(
 #[tracing::instrument(name = "sync_user_budgets_in_db", skip(transaction))]
 pub async fn sync_user_budgets_in_db(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
 ) -> Result<u64, MinimalApiError> {
-    let year = Utc::now().year();
+    let year = billing.year_of(Utc::now());
     let query = sqlx::query!(
         r#"
         UPDATE
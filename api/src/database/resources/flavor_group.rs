@@ -56,6 +56,41 @@ pub async fn select_flavor_group_name_from_db(
         .ok_or(NotFoundOrUnexpectedApiError::NotFoundError)
 }
 
+#[tracing::instrument(
+    name = "select_maybe_flavor_group_id_by_name_from_db",
+    skip(transaction)
+)]
+pub async fn select_maybe_flavor_group_id_by_name_from_db(
+    transaction: &mut Transaction<'_, MySql>,
+    name: &str,
+) -> Result<Option<u32>, UnexpectedOnlyError> {
+    #[derive(FromRow)]
+    #[allow(dead_code)]
+    struct Row {
+        id: u32,
+    }
+    let query = sqlx::query!(
+        r#"
+        SELECT id
+        FROM resources_flavorgroup
+        WHERE name = ?
+        "#,
+        name
+    );
+    let row = transaction
+        .fetch_optional(query)
+        .await
+        .context("Failed to execute select query")?;
+    Ok(match row {
+        Some(row) => Some(
+            Row::from_row(&row)
+                .context("Failed to parse flavor group row")?
+                .id,
+        ),
+        None => None,
+    })
+}
+
 #[derive(Clone, Debug, PartialEq, FromRow)]
 pub struct FlavorGroupDb {
     pub id: u32,
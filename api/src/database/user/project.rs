@@ -44,6 +44,39 @@ pub async fn select_project_from_db(
         .ok_or(NotFoundOrUnexpectedApiError::NotFoundError)
 }
 
+#[tracing::instrument(
+    name = "select_maybe_project_by_openstack_id_from_db",
+    skip(transaction)
+)]
+pub async fn select_maybe_project_by_openstack_id_from_db(
+    transaction: &mut Transaction<'_, MySql>,
+    openstack_id: &str,
+) -> Result<Option<Project>, UnexpectedOnlyError> {
+    let query = sqlx::query!(
+        r#"
+        SELECT
+            id,
+            name,
+            openstack_id,
+            user_class
+        FROM user_project AS project
+        WHERE
+            project.openstack_id = ?
+        "#,
+        openstack_id
+    );
+    let row = transaction
+        .fetch_optional(query)
+        .await
+        .context("Failed to execute select query")?;
+    Ok(match row {
+        Some(row) => Some(
+            Project::from_row(&row).context("Failed to parse project row")?,
+        ),
+        None => None,
+    })
+}
+
 #[tracing::instrument(
     name = "select_maybe_project_minimal_from_db",
     skip(transaction)
@@ -19,6 +19,16 @@ pub fn unauthorized_error(message: &str) -> actix_web::Error {
     .into()
 }
 
+pub fn forbidden_error(message: &str) -> actix_web::Error {
+    InternalError::from_response(
+        anyhow::anyhow!(message.to_string()),
+        HttpResponse::Forbidden().json(ErrorResponse {
+            detail: message.to_string(),
+        }),
+    )
+    .into()
+}
+
 pub fn internal_server_error(message: &str) -> actix_web::Error {
     InternalError::from_response(
         anyhow::anyhow!(message.to_string()),
@@ -1,10 +1,13 @@
 pub mod authentication;
 pub mod authorization;
+pub mod billing;
 pub mod configuration;
 pub mod database;
 pub mod error;
+pub mod notification;
 pub mod openstack;
 pub mod routes;
+pub mod scheduler;
 pub mod startup;
 pub mod telemetry;
 pub mod utils;
@@ -0,0 +1,166 @@
+use anyhow::Context;
+use avina_wire::budgeting::BudgetAlert;
+use lettre::{
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+    message::{Mailbox, header::ContentType},
+    transport::smtp::authentication::Credentials,
+};
+use secrecy::ExposeSecret;
+
+use crate::{
+    configuration::{AlertSettings, SmtpSettings, SmtpTls, WebhookSettings},
+    error::UnexpectedOnlyError,
+};
+
+// A way of delivering budget alerts, new channels are added as variants
+// together with their settings in AlertSettings.
+pub enum NotificationChannel {
+    Smtp(Box<SmtpChannel>),
+    Webhook(WebhookChannel),
+}
+
+impl NotificationChannel {
+    pub fn from_settings(
+        settings: &AlertSettings,
+    ) -> Result<Vec<Self>, anyhow::Error> {
+        let mut channels = vec![];
+        if let Some(smtp) = &settings.smtp {
+            channels.push(Self::Smtp(Box::new(SmtpChannel::new(smtp)?)));
+        }
+        if let Some(webhook) = &settings.webhook {
+            channels.push(Self::Webhook(WebhookChannel::new(webhook)));
+        }
+        Ok(channels)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Smtp(_) => "smtp",
+            Self::Webhook(_) => "webhook",
+        }
+    }
+
+    pub async fn send(
+        &self,
+        alert: &BudgetAlert,
+    ) -> Result<(), UnexpectedOnlyError> {
+        match self {
+            Self::Smtp(channel) => channel.send(alert).await,
+            Self::Webhook(channel) => channel.send(alert).await,
+        }
+    }
+}
+
+pub struct SmtpChannel {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+}
+
+impl SmtpChannel {
+    pub fn new(settings: &SmtpSettings) -> Result<Self, anyhow::Error> {
+        let builder = match settings.tls {
+            SmtpTls::None => {
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(
+                    &settings.host,
+                )
+            }
+            SmtpTls::Starttls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(
+                    &settings.host,
+                )
+                .context("Failed to create SMTP transport")?
+            }
+            SmtpTls::Tls => {
+                AsyncSmtpTransport::<Tokio1Executor>::relay(&settings.host)
+                    .context("Failed to create SMTP transport")?
+            }
+        };
+        let mut builder = builder.port(settings.port);
+        if let (Some(username), Some(password)) =
+            (&settings.username, &settings.password)
+        {
+            builder = builder.credentials(Credentials::new(
+                username.clone(),
+                password.expose_secret().to_string(),
+            ));
+        }
+        Ok(Self {
+            transport: builder.build(),
+            from: settings
+                .from
+                .parse()
+                .context("Failed to parse SMTP from address")?,
+            to: settings
+                .to
+                .iter()
+                .map(|to| to.parse())
+                .collect::<Result<Vec<_>, _>>()
+                .context("Failed to parse SMTP to address")?,
+        })
+    }
+
+    #[tracing::instrument(name = "smtp_channel_send", skip(self))]
+    async fn send(
+        &self,
+        alert: &BudgetAlert,
+    ) -> Result<(), UnexpectedOnlyError> {
+        let mut builder =
+            Message::builder().from(self.from.clone()).subject(format!(
+                "Budget alert: {} {} reached {}% of its {} budget",
+                alert.kind, alert.owner_name, alert.threshold, alert.year
+            ));
+        for to in self.to.iter() {
+            builder = builder.to(to.clone());
+        }
+        let message = builder
+            .header(ContentType::TEXT_PLAIN)
+            .body(format!(
+                "The server cost of {} {} (ID {}) is {:.2} of its budget of \
+                {} for {}, which is at least {}%.\n",
+                alert.kind,
+                alert.owner_name,
+                alert.owner_id,
+                alert.cost,
+                alert.budget,
+                alert.year,
+                alert.threshold,
+            ))
+            .context("Failed to build alert email")?;
+        self.transport
+            .send(message)
+            .await
+            .context("Failed to send alert email")?;
+        Ok(())
+    }
+}
+
+pub struct WebhookChannel {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl WebhookChannel {
+    pub fn new(settings: &WebhookSettings) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: settings.url.clone(),
+        }
+    }
+
+    #[tracing::instrument(name = "webhook_channel_send", skip(self))]
+    async fn send(
+        &self,
+        alert: &BudgetAlert,
+    ) -> Result<(), UnexpectedOnlyError> {
+        self.client
+            .post(&self.url)
+            .json(alert)
+            .send()
+            .await
+            .context("Failed to send alert webhook")?
+            .error_for_status()
+            .context("Alert webhook returned an error")?;
+        Ok(())
+    }
+}
@@ -1,9 +1,10 @@
 use std::{collections::HashMap, time::Instant};
 
 use anyhow::Context;
+use chrono::{DateTime, NaiveDateTime, Utc};
 use jzon::object;
 use reqwest::{
-    ClientBuilder,
+    ClientBuilder, Url,
     header::{CONTENT_TYPE, HeaderMap, HeaderValue},
 };
 use tokio::sync::RwLock;
@@ -71,6 +72,31 @@ pub struct ProjectMinimal {
     pub name: String,
 }
 
+#[derive(Clone, Debug, serde::Deserialize)]
+#[allow(unused)]
+pub struct DomainDetailed {
+    pub id: String,
+    pub name: String,
+    pub enabled: bool,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+#[allow(unused)]
+pub struct ProjectDetailed {
+    pub id: String,
+    pub name: String,
+    pub domain_id: String,
+    pub enabled: bool,
+    pub is_domain: bool,
+    pub parent_id: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct ProjectDetailedList {
+    projects: Vec<ProjectDetailed>,
+}
+
 #[derive(Clone, Debug, serde::Deserialize)]
 pub struct Link {
     pub href: String,
@@ -144,6 +170,41 @@ pub enum ServerDetailedImage {
     None(String),
 }
 
+// Nova returns created and updated with a UTC offset, but the usage
+// timestamps like launched_at without one, those are UTC as well.
+fn parse_nova_datetime(s: &str) -> Result<DateTime<Utc>, chrono::ParseError> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|d| d.to_utc())
+        .or_else(|_| {
+            NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f")
+                .map(|d| d.and_utc())
+        })
+}
+
+fn deserialize_nova_datetime<'de, D>(
+    deserializer: D,
+) -> Result<DateTime<Utc>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let s = <String as serde::Deserialize>::deserialize(deserializer)?;
+    parse_nova_datetime(&s).map_err(serde::de::Error::custom)
+}
+
+fn deserialize_maybe_nova_datetime<'de, D>(
+    deserializer: D,
+) -> Result<Option<DateTime<Utc>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match <Option<String> as serde::Deserialize>::deserialize(deserializer)? {
+        Some(s) => parse_nova_datetime(&s)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+        None => Ok(None),
+    }
+}
+
 // TODO: there are many missing fields here.
 #[derive(Clone, Debug, serde::Deserialize)]
 #[allow(unused)]
@@ -159,10 +220,10 @@ pub struct ServerDetailed {
     pub host_id: String,
     pub image: ServerDetailedImage,
     pub flavor: ServerDetailedFlavor,
-    // TODO: this is actually a datetime
-    pub created: String,
-    // TODO: this is actually a datetime
-    pub updated: String,
+    #[serde(deserialize_with = "deserialize_nova_datetime")]
+    pub created: DateTime<Utc>,
+    #[serde(deserialize_with = "deserialize_nova_datetime")]
+    pub updated: DateTime<Utc>,
     pub addresses: HashMap<String, Vec<ServerDetailedAddress>>,
     #[serde(rename = "accessIPv4")]
     pub access_ipv4: String,
@@ -175,12 +236,18 @@ pub struct ServerDetailed {
     pub availability_zone: String,
     pub config_drive: String,
     pub key_name: Option<String>,
-    // TODO: this is actually a datetime
-    #[serde(rename = "OS-SRV-USG:launched_at")]
-    pub launched_at: Option<String>,
-    // TODO: this is actually a datetime
-    #[serde(rename = "OS-SRV-USG:terminated_at")]
-    pub terminated_at: Option<String>,
+    #[serde(
+        rename = "OS-SRV-USG:launched_at",
+        default,
+        deserialize_with = "deserialize_maybe_nova_datetime"
+    )]
+    pub launched_at: Option<DateTime<Utc>>,
+    #[serde(
+        rename = "OS-SRV-USG:terminated_at",
+        default,
+        deserialize_with = "deserialize_maybe_nova_datetime"
+    )]
+    pub terminated_at: Option<DateTime<Utc>>,
     #[serde(rename = "OS-EXT-SRV-ATTR:host")]
     pub host: Option<String>,
     #[serde(rename = "OS-EXT-SRV-ATTR:instance_name")]
@@ -201,6 +268,8 @@ pub struct ServerDetailed {
 #[derive(Clone, Debug, serde::Deserialize)]
 pub struct ServerDetailedList {
     servers: Vec<ServerDetailed>,
+    #[serde(default)]
+    servers_links: Vec<Link>,
 }
 
 impl OpenStack {
@@ -266,26 +335,59 @@ impl OpenStack {
         Ok(project.token.project)
     }
 
-    pub async fn get_flavors(
+    pub async fn get_domain(&self) -> Result<DomainDetailed, anyhow::Error> {
+        #[derive(Debug, serde::Deserialize)]
+        struct DomainResponse {
+            domain: DomainDetailed,
+        }
+
+        let client = self.client().await?;
+        let url = format!(
+            "{}/domains/{}",
+            self.settings.keystone_endpoint, self.settings.domain_id
+        );
+        let response = client
+            .get(url.as_str())
+            .send()
+            .await
+            .context("Could not retrieve domain")?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "Failed to retrieve domain, returned code {}",
+                response.status().as_u16()
+            ));
+        }
+        let domain: DomainResponse = serde_json::from_str(
+            response
+                .text()
+                .await
+                .context("Could not read response text")?
+                .as_str(),
+        )
+        .context("Could not parse response")?;
+        Ok(domain.domain)
+    }
+
+    pub async fn get_projects(
         &self,
-    ) -> Result<Vec<FlavorDetailed>, anyhow::Error> {
+    ) -> Result<Vec<ProjectDetailed>, anyhow::Error> {
         let client = self.client().await?;
         let url = format!(
-            "{}/v2.1/flavors/detail?is_public=False",
-            self.settings.nova_endpoint
+            "{}/projects?domain_id={}",
+            self.settings.keystone_endpoint, self.settings.domain_id
         );
         let response = client
             .get(url.as_str())
             .send()
             .await
-            .context("Could not retrieve flavor list")?;
+            .context("Could not retrieve project list")?;
         if !response.status().is_success() {
             return Err(anyhow::anyhow!(
-                "Failed to validate user token, returned code {}",
+                "Failed to retrieve project list, returned code {}",
                 response.status().as_u16()
             ));
         }
-        let flavors: FlavorDetailedList = serde_json::from_str(
+        let projects: ProjectDetailedList = serde_json::from_str(
             response
                 .text()
                 .await
@@ -293,29 +395,29 @@ impl OpenStack {
                 .as_str(),
         )
         .context("Could not parse response")?;
-        Ok(flavors.flavors)
+        Ok(projects.projects)
     }
 
-    pub async fn get_servers(
+    pub async fn get_flavors(
         &self,
-    ) -> Result<Vec<ServerDetailed>, anyhow::Error> {
+    ) -> Result<Vec<FlavorDetailed>, anyhow::Error> {
         let client = self.client().await?;
         let url = format!(
-            "{}/v2.1/servers/detail?all_tenants=True",
+            "{}/v2.1/flavors/detail?is_public=False",
             self.settings.nova_endpoint
         );
         let response = client
             .get(url.as_str())
             .send()
             .await
-            .context("Could not retrieve server list")?;
+            .context("Could not retrieve flavor list")?;
         if !response.status().is_success() {
             return Err(anyhow::anyhow!(
                 "Failed to validate user token, returned code {}",
                 response.status().as_u16()
             ));
         }
-        let servers: ServerDetailedList = serde_json::from_str(
+        let flavors: FlavorDetailedList = serde_json::from_str(
             response
                 .text()
                 .await
@@ -323,8 +425,148 @@ impl OpenStack {
                 .as_str(),
         )
         .context("Could not parse response")?;
-        Ok(servers.servers)
+        Ok(flavors.flavors)
+    }
+
+    async fn list_servers(
+        &self,
+        query: &str,
+    ) -> Result<Vec<ServerDetailed>, anyhow::Error> {
+        let client = self.client().await?;
+        let mut servers = Vec::new();
+        let mut query =
+            format!("{}&limit={}", query, self.settings.server_page_size);
+        loop {
+            let url = format!(
+                "{}/v2.1/servers/detail?{}",
+                self.settings.nova_endpoint, query
+            );
+            let response = client
+                .get(url.as_str())
+                .send()
+                .await
+                .context("Could not retrieve server list")?;
+            if !response.status().is_success() {
+                return Err(anyhow::anyhow!(
+                    "Failed to retrieve server list, returned code {}",
+                    response.status().as_u16()
+                ));
+            }
+            let page: ServerDetailedList = serde_json::from_str(
+                response
+                    .text()
+                    .await
+                    .context("Could not read response text")?
+                    .as_str(),
+            )
+            .context("Could not parse response")?;
+            let page_is_empty = page.servers.is_empty();
+            servers.extend(page.servers);
+            let Some(next) =
+                page.servers_links.iter().find(|l| l.rel == "next")
+            else {
+                break;
+            };
+            if page_is_empty {
+                break;
+            }
+            // the link points to the public endpoint, so only its query
+            // with the marker is reused
+            query = Url::parse(&next.href)
+                .context("Could not parse next server list link")?
+                .query()
+                .unwrap_or_default()
+                .to_string();
+        }
+        Ok(servers)
+    }
+
+    pub async fn get_servers(
+        &self,
+    ) -> Result<Vec<ServerDetailed>, anyhow::Error> {
+        self.list_servers("all_tenants=True").await
+    }
+
+    pub async fn get_servers_changed_since(
+        &self,
+        changes_since: DateTime<Utc>,
+    ) -> Result<Vec<ServerDetailed>, anyhow::Error> {
+        self.list_servers(
+            format!(
+                "all_tenants=True&changes-since={}",
+                format_changes_since(changes_since)
+            )
+            .as_str(),
+        )
+        .await
+    }
+
+    pub async fn get_deleted_servers(
+        &self,
+        changes_since: DateTime<Utc>,
+    ) -> Result<Vec<ServerDetailed>, anyhow::Error> {
+        self.list_servers(
+            format!(
+                "all_tenants=True&deleted=True&changes-since={}",
+                format_changes_since(changes_since)
+            )
+            .as_str(),
+        )
+        .await
     }
+
+    async fn server_action(
+        &self,
+        server_id: &str,
+        action: jzon::JsonValue,
+    ) -> Result<(), anyhow::Error> {
+        let client = self.client().await?;
+        let url = format!(
+            "{}/v2.1/servers/{}/action",
+            self.settings.nova_endpoint, server_id
+        );
+        let response = client
+            .post(url.as_str())
+            .body(action.to_string())
+            .send()
+            .await
+            .context("Could not send server action")?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "Failed to perform server action, returned code {}",
+                response.status().as_u16()
+            ));
+        }
+        Ok(())
+    }
+
+    pub async fn lock_server(
+        &self,
+        server_id: &str,
+    ) -> Result<(), anyhow::Error> {
+        self.server_action(server_id, object! { "lock": null })
+            .await
+    }
+
+    pub async fn shelve_server(
+        &self,
+        server_id: &str,
+    ) -> Result<(), anyhow::Error> {
+        self.server_action(server_id, object! { "shelve": null })
+            .await
+    }
+
+    pub async fn stop_server(
+        &self,
+        server_id: &str,
+    ) -> Result<(), anyhow::Error> {
+        self.server_action(server_id, object! { "os-stop": null })
+            .await
+    }
+}
+
+fn format_changes_since(changes_since: DateTime<Utc>) -> String {
+    changes_since.format("%Y-%m-%dT%H:%M:%SZ").to_string()
 }
 
 #[tracing::instrument(name = "Issue an OpenStack token", skip(settings))]
@@ -0,0 +1,23 @@
+use avina_wire::accounting::TimeGrouping;
+use chrono::{DateTime, Utc};
+
+use crate::{
+    billing::Bucket, configuration::BillingSettings, error::OptionApiError,
+};
+
+// upper bound for the buckets of a single grouped request, each bucket is
+// calculated on its own
+pub(crate) const MAX_BUCKETS: usize = 1000;
+
+pub(crate) fn split_into_buckets(
+    billing: &BillingSettings,
+    begin: DateTime<Utc>,
+    end: DateTime<Utc>,
+    by: &TimeGrouping,
+) -> Result<Vec<Bucket>, OptionApiError> {
+    billing.buckets(begin, end, by, MAX_BUCKETS).ok_or_else(|| {
+        OptionApiError::ValidationError(format!(
+            "Too many buckets, at most {MAX_BUCKETS} are allowed per request"
+        ))
+    })
+}
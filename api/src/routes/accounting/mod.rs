@@ -1,11 +1,13 @@
 use actix_web::{Scope, web::scope};
 
-mod server_state;
+pub(crate) mod bucket;
+pub(crate) mod server_state;
 use server_state::server_states_scope;
-mod server_consumption;
+pub mod server_consumption;
 use server_consumption::server_consumption_scope;
 pub(crate) mod server_cost;
 use server_cost::server_cost_scope;
+pub(crate) mod target;
 
 pub fn accounting_scope() -> Scope {
     scope("/accounting")
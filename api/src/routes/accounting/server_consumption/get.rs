@@ -7,36 +7,38 @@ use actix_web::{
 use anyhow::Context;
 use avina_wire::{
     accounting::{
-        ServerConsumptionAll, ServerConsumptionFlavors,
-        ServerConsumptionParams, ServerConsumptionProject,
-        ServerConsumptionServer, ServerConsumptionUser, ServerState,
+        ServerConsumptionAll, ServerConsumptionBucket,
+        ServerConsumptionFlavors, ServerConsumptionParams,
+        ServerConsumptionProject, ServerConsumptionServer,
+        ServerConsumptionUser, ServerState,
     },
-    user::User,
+    user::{Project, User},
 };
-use chrono::{DateTime, Datelike, TimeZone, Utc};
+use chrono::{DateTime, Utc};
 use serde::Serialize;
 use sqlx::{MySql, MySqlPool, Transaction};
 
 use crate::{
-    authorization::{
-        require_admin_user, require_master_user_or_return_not_found,
-        require_user_or_project_master_or_not_found,
-    },
+    configuration::BillingSettings,
     database::{
         accounting::server_state::{
+            select_ordered_server_states_by_begin_and_end_from_db,
             select_ordered_server_states_by_server_begin_and_end_from_db,
             select_ordered_server_states_by_user_begin_and_end_from_db,
-            select_server_states_by_server_from_db,
         },
         user::{
             project::select_all_projects_from_db,
-            user::{select_user_from_db, select_users_by_project_from_db},
+            user::{select_all_users_from_db, select_users_by_project_from_db},
         },
     },
     error::{OptionApiError, UnexpectedOnlyError},
+    routes::accounting::{
+        bucket::split_into_buckets,
+        target::{AccountingTarget, select_accounting_target},
+    },
 };
 
-const CONSUMING_STATES: [&str; 15] = [
+pub(crate) const CONSUMING_STATES: [&str; 15] = [
     "ACTIVE",
     "BUILD",
     "HARD_REBOOT",
@@ -61,7 +63,7 @@ pub async fn calculate_server_consumption_for_server(
     end: Option<DateTime<Utc>>,
     states: Option<Vec<ServerState>>,
 ) -> Result<ServerConsumptionServer, UnexpectedOnlyError> {
-    let mut states = match states {
+    let states = match states {
         Some(states) => states,
         None => {
             select_ordered_server_states_by_server_begin_and_end_from_db(
@@ -73,9 +75,17 @@ pub async fn calculate_server_consumption_for_server(
             .await?
         }
     };
+    Ok(calculate_server_consumption_from_states(states, begin, end))
+}
+
+fn calculate_server_consumption_from_states(
+    mut states: Vec<ServerState>,
+    begin: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+) -> ServerConsumptionServer {
     let mut consumption = ServerConsumptionServer::default();
     if states.is_empty() {
-        return Ok(consumption);
+        return consumption;
     }
     let first = states.first_mut().unwrap();
     if let Some(begin) = begin {
@@ -102,7 +112,7 @@ pub async fn calculate_server_consumption_for_server(
         *entry += (state.end.unwrap() - state.begin).num_seconds() as f64;
     }
     // TODO:
-    Ok(consumption)
+    consumption
 }
 
 #[derive(Serialize)]
@@ -219,38 +229,98 @@ pub enum ServerConsumptionForAll {
     Detail(ServerConsumptionAll),
 }
 
-pub async fn calculate_server_consumption_for_all(
-    transaction: &mut Transaction<'_, MySql>,
+// Calculates the consumption of all projects from the given states in
+// memory, the states have to be ordered by ID like the selects return them.
+pub(crate) fn calculate_server_consumption_for_all_from_states(
+    projects: &[Project],
+    users: &[User],
+    states: &[ServerState],
     begin: Option<DateTime<Utc>>,
     end: Option<DateTime<Utc>>,
-    detail: Option<bool>,
-) -> Result<ServerConsumptionForAll, UnexpectedOnlyError> {
-    let mut consumption = ServerConsumptionAll::default();
+) -> ServerConsumptionAll {
+    let mut states_by_user: HashMap<u32, HashMap<String, Vec<ServerState>>> =
+        HashMap::new();
+    for state in states {
+        if begin.is_some_and(|begin| {
+            state.end.is_some_and(|end| end <= begin.fixed_offset())
+        }) || end.is_some_and(|end| state.begin >= end.fixed_offset())
+        {
+            continue;
+        }
+        states_by_user
+            .entry(state.user)
+            .or_default()
+            .entry(state.instance_id.clone())
+            .or_default()
+            .push(state.clone());
+    }
+    let mut users_by_project: HashMap<u32, Vec<&User>> = HashMap::new();
+    for user in users {
+        users_by_project.entry(user.project).or_default().push(user);
+    }
 
-    let projects = select_all_projects_from_db(transaction).await?;
+    let mut consumption = ServerConsumptionAll::default();
     for project in projects {
-        let project_consumption =
-            match calculate_server_consumption_for_project(
-                transaction,
-                project.id as u64,
-                begin,
-                end,
-                Some(true),
-            )
-            .await?
+        let mut project_consumption = ServerConsumptionProject::default();
+        for user in users_by_project.remove(&project.id).unwrap_or_default() {
+            let mut user_consumption = ServerConsumptionUser::default();
+            for (server_uuid, server_states) in
+                states_by_user.remove(&user.id).unwrap_or_default()
             {
-                ServerConsumptionForProject::Normal(_normal) => unreachable!(),
-                ServerConsumptionForProject::Detail(detail) => detail,
-            };
-
-        for (flavor, value) in project_consumption.total.clone() {
+                user_consumption.servers.insert(
+                    server_uuid,
+                    calculate_server_consumption_from_states(
+                        server_states,
+                        begin,
+                        end,
+                    ),
+                );
+            }
+            for server_consumption in user_consumption.servers.values() {
+                for (flavor, value) in server_consumption {
+                    *user_consumption
+                        .total
+                        .entry(flavor.clone())
+                        .or_default() += value;
+                }
+            }
+            for (flavor, value) in &user_consumption.total {
+                *project_consumption
+                    .total
+                    .entry(flavor.clone())
+                    .or_default() += value;
+            }
+            project_consumption
+                .users
+                .insert(user.name.clone(), user_consumption);
+        }
+        for (flavor, value) in &project_consumption.total {
             *consumption.total.entry(flavor.clone()).or_default() += value;
         }
-
         consumption
             .projects
             .insert(project.name.clone(), project_consumption);
     }
+    consumption
+}
+
+pub async fn calculate_server_consumption_for_all(
+    transaction: &mut Transaction<'_, MySql>,
+    begin: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+    detail: Option<bool>,
+) -> Result<ServerConsumptionForAll, UnexpectedOnlyError> {
+    let projects = select_all_projects_from_db(transaction).await?;
+    let users = select_all_users_from_db(transaction).await?;
+    let states = select_ordered_server_states_by_begin_and_end_from_db(
+        transaction,
+        begin,
+        end,
+    )
+    .await?;
+    let consumption = calculate_server_consumption_for_all_from_states(
+        &projects, &users, &states, begin, end,
+    );
 
     Ok(if detail.is_some() && detail.unwrap() {
         ServerConsumptionForAll::Detail(consumption)
@@ -268,106 +338,118 @@ pub enum ServerConsumption {
     All(ServerConsumptionForAll),
 }
 
-#[tracing::instrument(name = "server_consumption")]
-pub async fn server_consumption(
-    user: ReqData<User>,
-    db_pool: Data<MySqlPool>,
-    params: Query<ServerConsumptionParams>,
-    // TODO: is the ValidationError variant ever used?
-) -> Result<HttpResponse, OptionApiError> {
-    let end = params.end.unwrap_or(Utc::now().fixed_offset());
-    let begin = params.begin.unwrap_or(
-        Utc.with_ymd_and_hms(Utc::now().year(), 1, 1, 1, 0, 0)
-            .unwrap()
-            .fixed_offset(),
-    );
-    let mut transaction = db_pool
-        .begin()
-        .await
-        .context("Failed to begin transaction")?;
-    let consumption = if params.all.unwrap_or(false) {
-        require_admin_user(&user)?;
-        ServerConsumption::All(
+pub(crate) async fn calculate_server_consumption(
+    transaction: &mut Transaction<'_, MySql>,
+    target: &AccountingTarget,
+    begin: DateTime<Utc>,
+    end: DateTime<Utc>,
+    detail: Option<bool>,
+) -> Result<ServerConsumption, UnexpectedOnlyError> {
+    Ok(match target {
+        AccountingTarget::All => ServerConsumption::All(
             calculate_server_consumption_for_all(
-                &mut transaction,
-                Some(begin.into()),
-                Some(end.into()),
-                params.detail,
+                transaction,
+                Some(begin),
+                Some(end),
+                detail,
             )
             .await?,
-        )
-    } else if let Some(project_id) = params.project {
-        require_master_user_or_return_not_found(&user, project_id)?;
-        ServerConsumption::Project(
+        ),
+        AccountingTarget::Project(project_id) => ServerConsumption::Project(
             calculate_server_consumption_for_project(
-                &mut transaction,
-                project_id as u64,
-                Some(begin.into()),
-                Some(end.into()),
-                params.detail,
+                transaction,
+                *project_id,
+                Some(begin),
+                Some(end),
+                detail,
             )
             .await?,
-        )
-    } else if let Some(user_id) = params.user {
-        let user_queried =
-            select_user_from_db(&mut transaction, user_id as u64).await?;
-        require_user_or_project_master_or_not_found(
-            &user,
-            user_id,
-            user_queried.project,
-        )?;
-        ServerConsumption::User(
+        ),
+        AccountingTarget::User(user_id) => ServerConsumption::User(
             calculate_server_consumption_for_user(
-                &mut transaction,
-                user_id as u64,
-                Some(begin.into()),
-                Some(end.into()),
-                params.detail,
+                transaction,
+                *user_id,
+                Some(begin),
+                Some(end),
+                detail,
             )
             .await?,
-        )
-    } else if let Some(server_id) = params.server.clone() {
-        let server_state = select_server_states_by_server_from_db(
-            &mut transaction,
-            server_id.clone(),
-            true,
-        )
-        .await?;
-        let server_state_user =
-            select_user_from_db(&mut transaction, server_state[0].user as u64)
-                .await?;
-        require_user_or_project_master_or_not_found(
-            &user,
-            server_state_user.id,
-            server_state_user.project,
-        )?;
-        ServerConsumption::Server(
+        ),
+        AccountingTarget::Server(server_id) => ServerConsumption::Server(
             calculate_server_consumption_for_server(
-                &mut transaction,
-                server_id.as_str(),
-                Some(begin.into()),
-                Some(end.into()),
+                transaction,
+                server_id,
+                Some(begin),
+                Some(end),
                 None,
             )
             .await?,
-        )
+        ),
+    })
+}
+
+#[tracing::instrument(name = "server_consumption")]
+pub async fn server_consumption(
+    user: ReqData<User>,
+    db_pool: Data<MySqlPool>,
+    billing: Data<BillingSettings>,
+    params: Query<ServerConsumptionParams>,
+    // TODO: is the ValidationError variant ever used?
+) -> Result<HttpResponse, OptionApiError> {
+    let end = params.end.unwrap_or(Utc::now().fixed_offset());
+    let begin = params
+        .begin
+        .unwrap_or(billing.start_of_the_current_year().fixed_offset());
+    let mut transaction = db_pool
+        .begin()
+        .await
+        .context("Failed to begin transaction")?;
+    let target = select_accounting_target(
+        &mut transaction,
+        &user,
+        params.all,
+        params.project,
+        params.user,
+        params.server.clone(),
+    )
+    .await?;
+    let response = if let Some(by) = &params.by {
+        let mut buckets = vec![];
+        for (bucket_begin, bucket_end) in
+            split_into_buckets(&billing, begin.into(), end.into(), by)?
+        {
+            buckets.push(ServerConsumptionBucket {
+                begin: billing.local(bucket_begin),
+                end: billing.local(bucket_end),
+                consumption: calculate_server_consumption(
+                    &mut transaction,
+                    &target,
+                    bucket_begin,
+                    bucket_end,
+                    params.detail,
+                )
+                .await?,
+            });
+        }
+        HttpResponse::Ok()
+            .content_type("application/json")
+            .json(buckets)
     } else {
-        ServerConsumption::User(
-            calculate_server_consumption_for_user(
-                &mut transaction,
-                user.id as u64,
-                Some(begin.into()),
-                Some(end.into()),
-                params.detail,
-            )
-            .await?,
+        let consumption = calculate_server_consumption(
+            &mut transaction,
+            &target,
+            begin.into(),
+            end.into(),
+            params.detail,
         )
+        .await?;
+        HttpResponse::Ok()
+            .content_type("application/json")
+            .json(consumption)
     };
     transaction
         .commit()
         .await
         .context("Failed to commit transaction")?;
-    Ok(HttpResponse::Ok()
-        .content_type("application/json")
-        .json(consumption))
+    Ok(response)
 }
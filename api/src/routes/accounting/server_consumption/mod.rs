@@ -3,7 +3,7 @@ use actix_web::{
     web::{get, scope},
 };
 
-pub(crate) mod get;
+pub mod get;
 use get::server_consumption;
 
 pub fn server_consumption_scope() -> Scope {
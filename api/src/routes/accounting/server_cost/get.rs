@@ -7,27 +7,28 @@ use actix_web::{
 use anyhow::{Context, anyhow};
 use avina_wire::{
     accounting::{
-        ServerCostAll, ServerCostParams, ServerCostProject, ServerCostServer,
-        ServerCostSimple, ServerCostUser,
+        ServerCostAll, ServerCostBucket, ServerCostParams, ServerCostProject,
+        ServerCostServer, ServerCostSimple, ServerCostUser, ServerState,
+        TimeGrouping,
     },
     pricing::FlavorPrice,
-    user::User,
+    user::{Project, User},
 };
-use chrono::{DateTime, Datelike, TimeZone, Utc};
+use chrono::{DateTime, Utc};
 use indexmap::IndexMap;
 use serde::Serialize;
 use sqlx::{MySql, MySqlPool, Transaction};
 use strum::{EnumIter, IntoEnumIterator};
 
 use crate::{
-    authorization::{
-        require_admin_user, require_master_user_or_return_not_found,
-        require_user_or_project_master_or_not_found,
-    },
+    configuration::BillingSettings,
     database::{
-        accounting::server_state::{
-            select_server_states_by_server_from_db,
-            select_user_class_by_server_from_db,
+        accounting::{
+            server_cost_rollup::ServerCostRollupFilter,
+            server_state::{
+                select_ordered_server_states_by_begin_and_end_from_db,
+                select_user_class_by_server_from_db,
+            },
         },
         pricing::flavor_price::select_flavor_prices_for_period_from_db,
         resources::flavor::select_all_flavors_from_db,
@@ -36,21 +37,35 @@ use crate::{
                 select_all_projects_from_db,
                 select_user_class_by_project_from_db,
             },
-            user::{select_user_class_by_user_from_db, select_user_from_db},
+            user::{
+                select_all_users_from_db, select_user_class_by_user_from_db,
+            },
         },
     },
     error::{OptionApiError, UnexpectedOnlyError},
-    routes::accounting::server_consumption::get::{
-        ServerConsumptionForAll, ServerConsumptionForProject,
-        ServerConsumptionForUser, calculate_server_consumption_for_all,
-        calculate_server_consumption_for_project,
-        calculate_server_consumption_for_server,
-        calculate_server_consumption_for_user,
+    routes::accounting::{
+        bucket::split_into_buckets,
+        server_consumption::get::{
+            ServerConsumptionForProject, ServerConsumptionForUser,
+            calculate_server_consumption_for_all_from_states,
+            calculate_server_consumption_for_project,
+            calculate_server_consumption_for_server,
+            calculate_server_consumption_for_user,
+        },
+        server_cost::rollup::{
+            merge_server_cost_all, merge_server_cost_project,
+            merge_server_cost_server, merge_server_cost_user,
+            select_server_cost_rollups, server_cost_all_from_rollups,
+            server_cost_project_from_rollups, server_cost_server_from_rollups,
+            server_cost_total_from_rollups, server_cost_user_from_rollups,
+            split_period_by_server_cost_rollups,
+        },
+        target::{AccountingTarget, select_accounting_target},
     },
 };
 
 #[derive(Hash, PartialEq, Eq, Clone, EnumIter, Debug)]
-enum UserClass {
+pub(crate) enum UserClass {
     UC1 = 1,
     UC2 = 2,
     UC3 = 3,
@@ -60,7 +75,7 @@ enum UserClass {
 }
 
 impl UserClass {
-    fn from_u32(value: u32) -> Result<Self, UnexpectedOnlyError> {
+    pub(crate) fn from_u32(value: u32) -> Result<Self, UnexpectedOnlyError> {
         match value {
             1 => Ok(UserClass::UC1),
             2 => Ok(UserClass::UC2),
@@ -122,11 +137,12 @@ async fn get_flavor_prices_for_period(
     Ok(prices)
 }
 
-type Prices = HashMap<UserClass, HashMap<String, f64>>;
-type PricePeriods = IndexMap<DateTime<Utc>, Prices>;
+pub(crate) type Prices = HashMap<UserClass, HashMap<String, f64>>;
+pub(crate) type PricePeriods = IndexMap<DateTime<Utc>, Prices>;
 
-async fn get_flavor_price_periods(
+pub(crate) async fn get_flavor_price_periods(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     begin: DateTime<Utc>,
     end: DateTime<Utc>,
 ) -> Result<PricePeriods, UnexpectedOnlyError> {
@@ -164,7 +180,7 @@ async fn get_flavor_price_periods(
     periods.insert(current_time, current_prices.clone());
 
     if i == prices.len() {
-        return Ok(periods);
+        return Ok(split_price_periods_by_year(billing, periods, end));
     }
 
     current_time = prices.get(i).unwrap().start_time.to_utc();
@@ -184,18 +200,43 @@ async fn get_flavor_price_periods(
     }
     periods.insert(current_time, current_prices.clone());
 
-    Ok(periods)
+    Ok(split_price_periods_by_year(billing, periods, end))
+}
+
+// prices are yearly, so a period must not span two billing years to divide
+// its cost by the length of a single year
+fn split_price_periods_by_year(
+    billing: &BillingSettings,
+    periods: PricePeriods,
+    end: DateTime<Utc>,
+) -> PricePeriods {
+    let mut end_times = periods.keys().skip(1).cloned().collect::<Vec<_>>();
+    end_times.push(end);
+
+    let mut split_periods = PricePeriods::new();
+    for ((start_time, prices), end_time) in periods.into_iter().zip(end_times) {
+        let year_starts =
+            billing.starts_of_the_years_between(start_time, end_time);
+        split_periods.insert(start_time, prices.clone());
+        for year_start in year_starts {
+            split_periods.insert(year_start, prices.clone());
+        }
+    }
+    split_periods
 }
 
 fn calculate_flavor_consumption_cost(
+    billing: &BillingSettings,
     flavor_consumption: f64,
     prices: Prices,
     user_class: UserClass,
     flavor: String,
+    period_start: DateTime<Utc>,
 ) -> f64 {
     let mut cost = 0.0;
     if let Some(price) = prices.get(&user_class).unwrap().get(&flavor) {
-        cost = (flavor_consumption * price) / ((365 * 24 * 60 * 60) as f64);
+        cost = (flavor_consumption * price)
+            / billing.seconds_in_the_year(billing.year_of(period_start));
     }
     cost
 }
@@ -209,6 +250,7 @@ pub enum ServerCostForServer {
 
 pub async fn calculate_server_cost_for_server_normal(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     server_uuid: &str,
     begin: DateTime<Utc>,
     end: DateTime<Utc>,
@@ -225,7 +267,7 @@ pub async fn calculate_server_cost_for_server_normal(
         return Ok(cost);
     };
     let price_periods =
-        get_flavor_price_periods(transaction, begin, end).await?;
+        get_flavor_price_periods(transaction, billing, begin, end).await?;
 
     let mut end_times =
         price_periods.keys().skip(1).cloned().collect::<Vec<_>>();
@@ -246,10 +288,12 @@ pub async fn calculate_server_cost_for_server_normal(
                 continue;
             }
             let flavor_cost = calculate_flavor_consumption_cost(
+                billing,
                 flavor_consumption,
                 prices.clone(),
                 user_class.clone(),
                 flavor_name,
+                *start_time,
             );
             if flavor_cost <= 0. {
                 continue;
@@ -264,6 +308,7 @@ pub async fn calculate_server_cost_for_server_normal(
 // TODO: can we use macros to get rid of the code duplication here
 pub async fn calculate_server_cost_for_server_detail(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     server_uuid: &str,
     begin: DateTime<Utc>,
     end: DateTime<Utc>,
@@ -283,7 +328,7 @@ pub async fn calculate_server_cost_for_server_detail(
         return Ok(cost);
     };
     let price_periods =
-        get_flavor_price_periods(transaction, begin, end).await?;
+        get_flavor_price_periods(transaction, billing, begin, end).await?;
 
     let mut end_times =
         price_periods.keys().skip(1).cloned().collect::<Vec<_>>();
@@ -301,10 +346,12 @@ pub async fn calculate_server_cost_for_server_detail(
         .await?;
         for (flavor_name, flavor_consumption) in consumption {
             let flavor_cost = calculate_flavor_consumption_cost(
+                billing,
                 flavor_consumption,
                 prices.clone(),
                 user_class.clone(),
                 flavor_name.clone(),
+                *start_time,
             );
             *cost.flavors.entry(flavor_name).or_default() += flavor_cost;
             if flavor_cost <= 0. {
@@ -319,30 +366,57 @@ pub async fn calculate_server_cost_for_server_detail(
 
 pub async fn calculate_server_cost_for_server(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     server_uuid: &str,
     begin: DateTime<Utc>,
     end: DateTime<Utc>,
     detail: Option<bool>,
 ) -> Result<ServerCostForServer, UnexpectedOnlyError> {
+    let periods =
+        split_period_by_server_cost_rollups(transaction, billing, begin, end)
+            .await?;
+    let rollups = select_server_cost_rollups(
+        transaction,
+        billing,
+        &periods,
+        ServerCostRollupFilter::Server(server_uuid.to_string()),
+    )
+    .await?;
     Ok(match detail {
-        Some(true) => ServerCostForServer::Detail(
-            calculate_server_cost_for_server_detail(
-                transaction,
-                server_uuid,
-                begin,
-                end,
-            )
-            .await?,
-        ),
-        _ => ServerCostForServer::Normal(
-            calculate_server_cost_for_server_normal(
-                transaction,
-                server_uuid,
-                begin,
-                end,
-            )
-            .await?,
-        ),
+        Some(true) => {
+            let mut cost = server_cost_server_from_rollups(rollups);
+            for (begin, end) in periods.raw {
+                merge_server_cost_server(
+                    &mut cost,
+                    calculate_server_cost_for_server_detail(
+                        transaction,
+                        billing,
+                        server_uuid,
+                        begin,
+                        end,
+                    )
+                    .await?,
+                );
+            }
+            ServerCostForServer::Detail(cost)
+        }
+        _ => {
+            let mut cost = ServerCostSimple {
+                total: server_cost_total_from_rollups(&rollups),
+            };
+            for (begin, end) in periods.raw {
+                cost.total += calculate_server_cost_for_server_normal(
+                    transaction,
+                    billing,
+                    server_uuid,
+                    begin,
+                    end,
+                )
+                .await?
+                .total;
+            }
+            ServerCostForServer::Normal(cost)
+        }
     })
 }
 
@@ -356,6 +430,7 @@ pub enum ServerCostForUser {
 // TODO: shouldn't this return not found, when the user doesn't exist?
 pub async fn calculate_server_cost_for_user_normal(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     user_id: u64,
     begin: DateTime<Utc>,
     end: DateTime<Utc>,
@@ -370,7 +445,7 @@ pub async fn calculate_server_cost_for_user_normal(
         return Ok(cost);
     };
     let price_periods =
-        get_flavor_price_periods(transaction, begin, end).await?;
+        get_flavor_price_periods(transaction, billing, begin, end).await?;
 
     let mut end_times =
         price_periods.keys().skip(1).cloned().collect::<Vec<_>>();
@@ -397,10 +472,12 @@ pub async fn calculate_server_cost_for_user_normal(
                 continue;
             }
             let flavor_cost = calculate_flavor_consumption_cost(
+                billing,
                 flavor_consumption,
                 prices.clone(),
                 user_class.clone(),
                 flavor_name,
+                *start_time,
             );
             cost.total += flavor_cost;
         }
@@ -412,6 +489,7 @@ pub async fn calculate_server_cost_for_user_normal(
 // TODO: can we use macros to get rid of the code duplication here
 pub async fn calculate_server_cost_for_user_detail(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     user_id: u64,
     begin: DateTime<Utc>,
     end: DateTime<Utc>,
@@ -430,7 +508,7 @@ pub async fn calculate_server_cost_for_user_detail(
         return Ok(cost);
     };
     let price_periods =
-        get_flavor_price_periods(transaction, begin, end).await?;
+        get_flavor_price_periods(transaction, billing, begin, end).await?;
 
     let mut end_times =
         price_periods.keys().skip(1).cloned().collect::<Vec<_>>();
@@ -463,10 +541,12 @@ pub async fn calculate_server_cost_for_user_detail(
                 });
             for (flavor_name, flavor_consumption) in server_consumption {
                 let flavor_cost = calculate_flavor_consumption_cost(
+                    billing,
                     flavor_consumption,
                     prices.clone(),
                     user_class.clone(),
                     flavor_name.clone(),
+                    *start_time,
                 );
                 *server_cost.flavors.entry(flavor_name.clone()).or_default() +=
                     flavor_cost;
@@ -485,30 +565,57 @@ pub async fn calculate_server_cost_for_user_detail(
 
 pub async fn calculate_server_cost_for_user(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     user_id: u64,
     begin: DateTime<Utc>,
     end: DateTime<Utc>,
     detail: Option<bool>,
 ) -> Result<ServerCostForUser, UnexpectedOnlyError> {
+    let periods =
+        split_period_by_server_cost_rollups(transaction, billing, begin, end)
+            .await?;
+    let rollups = select_server_cost_rollups(
+        transaction,
+        billing,
+        &periods,
+        ServerCostRollupFilter::User(user_id),
+    )
+    .await?;
     Ok(match detail {
-        Some(true) => ServerCostForUser::Detail(
-            calculate_server_cost_for_user_detail(
-                transaction,
-                user_id,
-                begin,
-                end,
-            )
-            .await?,
-        ),
-        _ => ServerCostForUser::Normal(
-            calculate_server_cost_for_user_normal(
-                transaction,
-                user_id,
-                begin,
-                end,
-            )
-            .await?,
-        ),
+        Some(true) => {
+            let mut cost = server_cost_user_from_rollups(rollups);
+            for (begin, end) in periods.raw {
+                merge_server_cost_user(
+                    &mut cost,
+                    calculate_server_cost_for_user_detail(
+                        transaction,
+                        billing,
+                        user_id,
+                        begin,
+                        end,
+                    )
+                    .await?,
+                );
+            }
+            ServerCostForUser::Detail(cost)
+        }
+        _ => {
+            let mut cost = ServerCostSimple {
+                total: server_cost_total_from_rollups(&rollups),
+            };
+            for (begin, end) in periods.raw {
+                cost.total += calculate_server_cost_for_user_normal(
+                    transaction,
+                    billing,
+                    user_id,
+                    begin,
+                    end,
+                )
+                .await?
+                .total;
+            }
+            ServerCostForUser::Normal(cost)
+        }
     })
 }
 
@@ -521,6 +628,7 @@ pub enum ServerCostForProject {
 
 pub async fn calculate_server_cost_for_project_normal(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     project_id: u64,
     begin: DateTime<Utc>,
     end: DateTime<Utc>,
@@ -535,7 +643,7 @@ pub async fn calculate_server_cost_for_project_normal(
         return Ok(cost);
     };
     let price_periods =
-        get_flavor_price_periods(transaction, begin, end).await?;
+        get_flavor_price_periods(transaction, billing, begin, end).await?;
 
     let mut end_times =
         price_periods.keys().skip(1).cloned().collect::<Vec<_>>();
@@ -563,10 +671,12 @@ pub async fn calculate_server_cost_for_project_normal(
                 continue;
             }
             let flavor_cost = calculate_flavor_consumption_cost(
+                billing,
                 flavor_consumption,
                 prices.clone(),
                 user_class.clone(),
                 flavor_name,
+                *start_time,
             );
             if flavor_cost <= 0. {
                 continue;
@@ -581,6 +691,7 @@ pub async fn calculate_server_cost_for_project_normal(
 // TODO: can we use macros to get rid of the code duplication here
 pub async fn calculate_server_cost_for_project_detail(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     project_id: u64,
     begin: DateTime<Utc>,
     end: DateTime<Utc>,
@@ -599,7 +710,7 @@ pub async fn calculate_server_cost_for_project_detail(
         return Ok(cost);
     };
     let price_periods =
-        get_flavor_price_periods(transaction, begin, end).await?;
+        get_flavor_price_periods(transaction, billing, begin, end).await?;
 
     let mut end_times =
         price_periods.keys().skip(1).cloned().collect::<Vec<_>>();
@@ -641,10 +752,12 @@ pub async fn calculate_server_cost_for_project_detail(
                     });
                 for (flavor_name, flavor_consumption) in server_consumption {
                     let flavor_cost = calculate_flavor_consumption_cost(
+                        billing,
                         flavor_consumption,
                         prices.clone(),
                         user_class.clone(),
                         flavor_name.clone(),
+                        *start_time,
                     );
                     *server_cost
                         .flavors
@@ -672,30 +785,57 @@ pub async fn calculate_server_cost_for_project_detail(
 
 pub async fn calculate_server_cost_for_project(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     project_id: u64,
     begin: DateTime<Utc>,
     end: DateTime<Utc>,
     detail: Option<bool>,
 ) -> Result<ServerCostForProject, UnexpectedOnlyError> {
+    let periods =
+        split_period_by_server_cost_rollups(transaction, billing, begin, end)
+            .await?;
+    let rollups = select_server_cost_rollups(
+        transaction,
+        billing,
+        &periods,
+        ServerCostRollupFilter::Project(project_id),
+    )
+    .await?;
     Ok(match detail {
-        Some(true) => ServerCostForProject::Detail(
-            calculate_server_cost_for_project_detail(
-                transaction,
-                project_id,
-                begin,
-                end,
-            )
-            .await?,
-        ),
-        _ => ServerCostForProject::Normal(
-            calculate_server_cost_for_project_normal(
-                transaction,
-                project_id,
-                begin,
-                end,
-            )
-            .await?,
-        ),
+        Some(true) => {
+            let mut cost = server_cost_project_from_rollups(rollups);
+            for (begin, end) in periods.raw {
+                merge_server_cost_project(
+                    &mut cost,
+                    calculate_server_cost_for_project_detail(
+                        transaction,
+                        billing,
+                        project_id,
+                        begin,
+                        end,
+                    )
+                    .await?,
+                );
+            }
+            ServerCostForProject::Detail(cost)
+        }
+        _ => {
+            let mut cost = ServerCostSimple {
+                total: server_cost_total_from_rollups(&rollups),
+            };
+            for (begin, end) in periods.raw {
+                cost.total += calculate_server_cost_for_project_normal(
+                    transaction,
+                    billing,
+                    project_id,
+                    begin,
+                    end,
+                )
+                .await?
+                .total;
+            }
+            ServerCostForProject::Normal(cost)
+        }
     })
 }
 
@@ -706,41 +846,44 @@ pub enum ServerCostForAll {
     Detail(ServerCostAll),
 }
 
-// TODO: optimize/parallelize this and other functions
 pub async fn calculate_server_cost_for_all_normal(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     begin: DateTime<Utc>,
     end: DateTime<Utc>,
 ) -> Result<ServerCostSimple, UnexpectedOnlyError> {
     let mut cost = ServerCostSimple { total: 0.0 };
     let price_periods =
-        get_flavor_price_periods(transaction, begin, end).await?;
+        get_flavor_price_periods(transaction, billing, begin, end).await?;
 
     let mut end_times =
         price_periods.keys().skip(1).cloned().collect::<Vec<_>>();
     end_times.push(end);
 
-    let projects = select_all_projects_from_db(transaction)
-        .await?
-        .into_iter()
+    // everything is fetched at once and the consumption of the single price
+    // periods is calculated in memory to avoid queries per user and period
+    let project_list = select_all_projects_from_db(transaction).await?;
+    let users = select_all_users_from_db(transaction).await?;
+    let states = select_ordered_server_states_by_begin_and_end_from_db(
+        transaction,
+        Some(begin),
+        Some(end),
+    )
+    .await?;
+    let projects = project_list
+        .iter()
         .map(|p| (p.name.clone(), p))
         .collect::<HashMap<_, _>>();
 
     for ((start_time, prices), end_time) in price_periods.iter().zip(end_times)
     {
-        let ServerConsumptionForAll::Detail(consumption) =
-            calculate_server_consumption_for_all(
-                transaction,
-                Some(*start_time),
-                Some(end_time),
-                Some(true),
-            )
-            .await?
-        else {
-            return Err(
-                anyhow!("Unexpected ServerConsumptionForAll variant").into()
-            );
-        };
+        let consumption = calculate_server_consumption_for_all_from_states(
+            &project_list,
+            &users,
+            &states,
+            Some(*start_time),
+            Some(end_time),
+        );
         for (project_name, project_consumption) in consumption.projects {
             let Some(project) = projects.get(&project_name) else {
                 continue;
@@ -754,10 +897,12 @@ pub async fn calculate_server_cost_for_all_normal(
                     continue;
                 }
                 let flavor_cost = calculate_flavor_consumption_cost(
+                    billing,
                     flavor_consumption,
                     prices.clone(),
                     user_class.clone(),
                     flavor_name,
+                    *start_time,
                 );
                 if flavor_cost <= 0. {
                     continue;
@@ -773,42 +918,76 @@ pub async fn calculate_server_cost_for_all_normal(
 // TODO: can we use macros to get rid of the code duplication here
 pub async fn calculate_server_cost_for_all_detail(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     begin: DateTime<Utc>,
     end: DateTime<Utc>,
 ) -> Result<ServerCostAll, UnexpectedOnlyError> {
+    let price_periods =
+        get_flavor_price_periods(transaction, billing, begin, end).await?;
+
+    // everything is fetched at once and the consumption of the single price
+    // periods is calculated in memory to avoid queries per user and period
+    let projects = select_all_projects_from_db(transaction).await?;
+    let users = select_all_users_from_db(transaction).await?;
+    let states = select_ordered_server_states_by_begin_and_end_from_db(
+        transaction,
+        Some(begin),
+        Some(end),
+    )
+    .await?;
+
+    Ok(calculate_server_cost_for_all_detail_from_states(
+        billing,
+        &price_periods,
+        &projects,
+        &users,
+        &states,
+        begin,
+        end,
+    ))
+}
+
+// The price periods need to cover [begin, end), but may start earlier and
+// end later, so that the periods of a longer range can be reused for the
+// single days within it.
+pub(crate) fn calculate_server_cost_for_all_detail_from_states(
+    billing: &BillingSettings,
+    price_periods: &PricePeriods,
+    project_list: &[Project],
+    users: &[User],
+    states: &[ServerState],
+    begin: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> ServerCostAll {
     let mut cost = ServerCostAll {
         total: 0.0,
         flavors: HashMap::new(),
         projects: HashMap::new(),
     };
-    let price_periods =
-        get_flavor_price_periods(transaction, begin, end).await?;
 
     let mut end_times =
         price_periods.keys().skip(1).cloned().collect::<Vec<_>>();
     end_times.push(end);
 
-    let projects = select_all_projects_from_db(transaction)
-        .await?
-        .into_iter()
+    let projects = project_list
+        .iter()
         .map(|p| (p.name.clone(), p))
         .collect::<HashMap<_, _>>();
 
     for ((start_time, prices), end_time) in price_periods.iter().zip(end_times)
     {
-        let ServerConsumptionForAll::Detail(consumption) =
-            calculate_server_consumption_for_all(
-                transaction,
-                Some(*start_time),
-                Some(end_time),
-                Some(true),
-            )
-            .await?
-        else {
-            return Err(
-                anyhow!("Unexpected ServerConsumptionForAll variant").into()
-            );
-        };
+        let (start_time, end_time) =
+            ((*start_time).max(begin), end_time.min(end));
+        if start_time >= end_time {
+            continue;
+        }
+        let consumption = calculate_server_consumption_for_all_from_states(
+            project_list,
+            users,
+            states,
+            Some(start_time),
+            Some(end_time),
+        );
         for (project_name, project_consumption) in consumption.projects {
             let Some(project) = projects.get(&project_name) else {
                 continue;
@@ -847,10 +1026,12 @@ pub async fn calculate_server_cost_for_all_detail(
                     for (flavor_name, flavor_consumption) in server_consumption
                     {
                         let flavor_cost = calculate_flavor_consumption_cost(
+                            billing,
                             flavor_consumption,
                             prices.clone(),
                             user_class.clone(),
                             flavor_name.clone(),
+                            start_time,
                         );
                         *server_cost
                             .flavors
@@ -879,24 +1060,59 @@ pub async fn calculate_server_cost_for_all_detail(
         }
     }
 
-    Ok(cost)
+    cost
 }
 
 pub async fn calculate_server_cost_for_all(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     begin: DateTime<Utc>,
     end: DateTime<Utc>,
     detail: Option<bool>,
 ) -> Result<ServerCostForAll, UnexpectedOnlyError> {
+    let periods =
+        split_period_by_server_cost_rollups(transaction, billing, begin, end)
+            .await?;
+    let rollups = select_server_cost_rollups(
+        transaction,
+        billing,
+        &periods,
+        ServerCostRollupFilter::All,
+    )
+    .await?;
     Ok(match detail {
-        Some(true) => ServerCostForAll::Detail(
-            calculate_server_cost_for_all_detail(transaction, begin, end)
-                .await?,
-        ),
-        _ => ServerCostForAll::Normal(
-            calculate_server_cost_for_all_normal(transaction, begin, end)
-                .await?,
-        ),
+        Some(true) => {
+            let mut cost = server_cost_all_from_rollups(rollups);
+            for (begin, end) in periods.raw {
+                merge_server_cost_all(
+                    &mut cost,
+                    calculate_server_cost_for_all_detail(
+                        transaction,
+                        billing,
+                        begin,
+                        end,
+                    )
+                    .await?,
+                );
+            }
+            ServerCostForAll::Detail(cost)
+        }
+        _ => {
+            let mut cost = ServerCostSimple {
+                total: server_cost_total_from_rollups(&rollups),
+            };
+            for (begin, end) in periods.raw {
+                cost.total += calculate_server_cost_for_all_normal(
+                    transaction,
+                    billing,
+                    begin,
+                    end,
+                )
+                .await?
+                .total;
+            }
+            ServerCostForAll::Normal(cost)
+        }
     })
 }
 
@@ -909,106 +1125,168 @@ pub enum ServerCost {
     All(ServerCostForAll),
 }
 
+impl ServerCost {
+    pub(crate) fn total(&self) -> f64 {
+        match self {
+            ServerCost::Server(ServerCostForServer::Normal(cost)) => cost.total,
+            ServerCost::Server(ServerCostForServer::Detail(cost)) => cost.total,
+            ServerCost::User(ServerCostForUser::Normal(cost)) => cost.total,
+            ServerCost::User(ServerCostForUser::Detail(cost)) => cost.total,
+            ServerCost::Project(ServerCostForProject::Normal(cost)) => {
+                cost.total
+            }
+            ServerCost::Project(ServerCostForProject::Detail(cost)) => {
+                cost.total
+            }
+            ServerCost::All(ServerCostForAll::Normal(cost)) => cost.total,
+            ServerCost::All(ServerCostForAll::Detail(cost)) => cost.total,
+        }
+    }
+}
+
+pub(crate) async fn calculate_server_cost(
+    transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
+    target: &AccountingTarget,
+    begin: DateTime<Utc>,
+    end: DateTime<Utc>,
+    detail: Option<bool>,
+) -> Result<ServerCost, UnexpectedOnlyError> {
+    Ok(match target {
+        AccountingTarget::All => ServerCost::All(
+            calculate_server_cost_for_all(
+                transaction,
+                billing,
+                begin,
+                end,
+                detail,
+            )
+            .await?,
+        ),
+        AccountingTarget::Project(project_id) => ServerCost::Project(
+            calculate_server_cost_for_project(
+                transaction,
+                billing,
+                *project_id,
+                begin,
+                end,
+                detail,
+            )
+            .await?,
+        ),
+        AccountingTarget::User(user_id) => ServerCost::User(
+            calculate_server_cost_for_user(
+                transaction,
+                billing,
+                *user_id,
+                begin,
+                end,
+                detail,
+            )
+            .await?,
+        ),
+        AccountingTarget::Server(server_id) => ServerCost::Server(
+            calculate_server_cost_for_server(
+                transaction,
+                billing,
+                server_id,
+                begin,
+                end,
+                detail,
+            )
+            .await?,
+        ),
+    })
+}
+
+// Calculates the cost of the target per year, month or day of the billing
+// calendar, shared by the grouped cost and the cost series.
+pub(crate) async fn calculate_server_cost_buckets(
+    transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
+    target: &AccountingTarget,
+    begin: DateTime<Utc>,
+    end: DateTime<Utc>,
+    by: &TimeGrouping,
+    detail: Option<bool>,
+) -> Result<Vec<ServerCostBucket<ServerCost>>, OptionApiError> {
+    let mut buckets = vec![];
+    for (bucket_begin, bucket_end) in
+        split_into_buckets(billing, begin, end, by)?
+    {
+        buckets.push(ServerCostBucket {
+            begin: billing.local(bucket_begin),
+            end: billing.local(bucket_end),
+            cost: calculate_server_cost(
+                transaction,
+                billing,
+                target,
+                bucket_begin,
+                bucket_end,
+                detail,
+            )
+            .await?,
+        });
+    }
+    Ok(buckets)
+}
+
 #[tracing::instrument(name = "server_cost")]
 pub async fn server_cost(
     user: ReqData<User>,
     db_pool: Data<MySqlPool>,
+    billing: Data<BillingSettings>,
     params: Query<ServerCostParams>,
     // TODO: is the ValidationError variant ever used?
 ) -> Result<HttpResponse, OptionApiError> {
     let end = params.end.unwrap_or(Utc::now().fixed_offset());
-    let begin = params.begin.unwrap_or(
-        Utc.with_ymd_and_hms(Utc::now().year(), 1, 1, 1, 0, 0)
-            .unwrap()
-            .fixed_offset(),
-    );
+    let begin = params
+        .begin
+        .unwrap_or(billing.start_of_the_current_year().fixed_offset());
     let mut transaction = db_pool
         .begin()
         .await
         .context("Failed to begin transaction")?;
-    let cost = if params.all.unwrap_or(false) {
-        require_admin_user(&user)?;
-        ServerCost::All(
-            calculate_server_cost_for_all(
-                &mut transaction,
-                begin.into(),
-                end.into(),
-                params.detail,
-            )
-            .await?,
-        )
-    } else if let Some(project_id) = params.project {
-        require_master_user_or_return_not_found(&user, project_id)?;
-        ServerCost::Project(
-            calculate_server_cost_for_project(
-                &mut transaction,
-                project_id as u64,
-                begin.into(),
-                end.into(),
-                params.detail,
-            )
-            .await?,
-        )
-    } else if let Some(user_id) = params.user {
-        let user_queried =
-            select_user_from_db(&mut transaction, user_id as u64).await?;
-        require_user_or_project_master_or_not_found(
-            &user,
-            user_id,
-            user_queried.project,
-        )?;
-        ServerCost::User(
-            calculate_server_cost_for_user(
-                &mut transaction,
-                user_id as u64,
-                begin.into(),
-                end.into(),
-                params.detail,
-            )
-            .await?,
-        )
-    } else if let Some(server_id) = params.server.clone() {
-        let server_state = select_server_states_by_server_from_db(
+    let target = select_accounting_target(
+        &mut transaction,
+        &user,
+        params.all,
+        params.project,
+        params.user,
+        params.server.clone(),
+    )
+    .await?;
+    let response = if let Some(by) = &params.by {
+        let buckets = calculate_server_cost_buckets(
             &mut transaction,
-            server_id.clone(),
-            true,
+            &billing,
+            &target,
+            begin.into(),
+            end.into(),
+            by,
+            params.detail,
         )
         .await?;
-        let server_state_user =
-            select_user_from_db(&mut transaction, server_state[0].user as u64)
-                .await?;
-        require_user_or_project_master_or_not_found(
-            &user,
-            server_state_user.id,
-            server_state_user.project,
-        )?;
-        ServerCost::Server(
-            calculate_server_cost_for_server(
-                &mut transaction,
-                server_id.as_str(),
-                begin.into(),
-                end.into(),
-                params.detail,
-            )
-            .await?,
-        )
+        HttpResponse::Ok()
+            .content_type("application/json")
+            .json(buckets)
     } else {
-        ServerCost::User(
-            calculate_server_cost_for_user(
-                &mut transaction,
-                user.id as u64,
-                begin.into(),
-                end.into(),
-                params.detail,
-            )
-            .await?,
+        let cost = calculate_server_cost(
+            &mut transaction,
+            &billing,
+            &target,
+            begin.into(),
+            end.into(),
+            params.detail,
         )
+        .await?;
+        HttpResponse::Ok()
+            .content_type("application/json")
+            .json(cost)
     };
     transaction
         .commit()
         .await
         .context("Failed to commit transaction")?;
-    Ok(HttpResponse::Ok()
-        .content_type("application/json")
-        .json(cost))
+    Ok(response)
 }
@@ -5,7 +5,17 @@ use actix_web::{
 
 pub(crate) mod get;
 use get::server_cost;
+pub(crate) mod rate;
+use rate::server_cost_rate;
+pub(crate) mod rollup;
+use rollup::server_cost_rollup;
+mod series;
+use series::server_cost_series;
 
 pub fn server_cost_scope() -> Scope {
-    scope("/servercost").route("/", get().to(server_cost))
+    scope("/servercost")
+        .route("/", get().to(server_cost))
+        .route("/rate/", get().to(server_cost_rate))
+        .route("/rollup/", get().to(server_cost_rollup))
+        .route("/series/", get().to(server_cost_series))
 }
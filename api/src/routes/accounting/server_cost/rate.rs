@@ -0,0 +1,188 @@
+use std::collections::{BTreeMap, HashMap};
+
+use actix_web::{
+    HttpResponse,
+    web::{Data, Query, ReqData},
+};
+use anyhow::Context;
+use avina_wire::{
+    accounting::{
+        ServerCostRate, ServerCostRateFlavor, ServerCostRateParams,
+        ServerCostRateServer, ServerState,
+    },
+    user::User,
+};
+use chrono::{DateTime, Utc};
+use sqlx::{MySql, MySqlPool, Transaction};
+
+use super::get::{UserClass, get_flavor_price_periods};
+use crate::{
+    configuration::BillingSettings,
+    database::{
+        accounting::server_state::select_unfinished_server_states_from_db,
+        user::{
+            project::select_all_projects_from_db,
+            user::select_all_users_from_db,
+        },
+    },
+    error::{OptionApiError, UnexpectedOnlyError},
+    routes::accounting::{
+        server_consumption::get::CONSUMING_STATES,
+        target::{AccountingTarget, select_accounting_target},
+    },
+};
+
+pub(crate) struct RunningServerCost {
+    pub state: ServerState,
+    pub project_id: u32,
+    pub cost_per_second: f64,
+}
+
+#[tracing::instrument(name = "server_cost_rate")]
+pub async fn server_cost_rate(
+    user: ReqData<User>,
+    db_pool: Data<MySqlPool>,
+    billing: Data<BillingSettings>,
+    params: Query<ServerCostRateParams>,
+) -> Result<HttpResponse, OptionApiError> {
+    let mut transaction = db_pool
+        .begin()
+        .await
+        .context("Failed to begin transaction")?;
+    let target = select_accounting_target(
+        &mut transaction,
+        &user,
+        params.all,
+        params.project,
+        params.user,
+        params.server.clone(),
+    )
+    .await?;
+    let states =
+        select_unfinished_server_states_from_db(&mut transaction).await?;
+    let now = Utc::now();
+    let costs = price_running_server_states(
+        &mut transaction,
+        &billing,
+        &target,
+        states,
+        now,
+    )
+    .await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit transaction")?;
+    let seconds_in_a_month =
+        billing.seconds_in_the_year(billing.year_of(now)) / 12.0;
+
+    let mut servers = vec![];
+    let mut flavors = BTreeMap::<String, (u32, f64)>::new();
+    let mut cost_per_second = 0.0;
+    for cost in costs {
+        let flavor = flavors.entry(cost.state.flavor_name.clone()).or_default();
+        flavor.0 += 1;
+        flavor.1 += cost.cost_per_second;
+        cost_per_second += cost.cost_per_second;
+        servers.push(ServerCostRateServer {
+            server_id: cost.state.instance_id,
+            server_name: cost.state.instance_name,
+            flavor: cost.state.flavor_name,
+            user_id: cost.state.user,
+            user_name: cost.state.username,
+            hourly: cost.cost_per_second * 60.0 * 60.0,
+            daily: cost.cost_per_second * 24.0 * 60.0 * 60.0,
+            monthly: cost.cost_per_second * seconds_in_a_month,
+        });
+    }
+    servers.sort_by(|a, b| a.server_id.cmp(&b.server_id));
+    let rate = ServerCostRate {
+        hourly: cost_per_second * 60.0 * 60.0,
+        daily: cost_per_second * 24.0 * 60.0 * 60.0,
+        monthly: cost_per_second * seconds_in_a_month,
+        flavors: flavors
+            .into_iter()
+            .map(|(flavor, (server_count, cost_per_second))| {
+                ServerCostRateFlavor {
+                    flavor,
+                    server_count,
+                    hourly: cost_per_second * 60.0 * 60.0,
+                    daily: cost_per_second * 24.0 * 60.0 * 60.0,
+                    monthly: cost_per_second * seconds_in_a_month,
+                }
+            })
+            .collect(),
+        servers,
+    };
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(rate))
+}
+
+// Prices the consuming states of the target with the flavor prices valid
+// at the given time, other states are dropped.
+#[tracing::instrument(
+    name = "price_running_server_states",
+    skip(transaction, states)
+)]
+pub(crate) async fn price_running_server_states(
+    transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
+    target: &AccountingTarget,
+    states: Vec<ServerState>,
+    at: DateTime<Utc>,
+) -> Result<Vec<RunningServerCost>, UnexpectedOnlyError> {
+    let projects = select_all_projects_from_db(transaction)
+        .await?
+        .into_iter()
+        .map(|project| (project.id, project))
+        .collect::<HashMap<_, _>>();
+    let users = select_all_users_from_db(transaction)
+        .await?
+        .into_iter()
+        .map(|user| (user.id, user))
+        .collect::<HashMap<_, _>>();
+    let prices = get_flavor_price_periods(transaction, billing, at, at)
+        .await?
+        .into_values()
+        .next()
+        .unwrap_or_default();
+    let seconds_in_the_year = billing.seconds_in_the_year(billing.year_of(at));
+
+    let mut costs = vec![];
+    for state in states {
+        if !CONSUMING_STATES.contains(&state.status.as_str()) {
+            continue;
+        }
+        let Some(user) = users.get(&state.user) else {
+            continue;
+        };
+        let Some(project) = projects.get(&user.project) else {
+            continue;
+        };
+        let matches = match target {
+            AccountingTarget::Server(server_id) => {
+                state.instance_id == *server_id
+            }
+            AccountingTarget::User(user_id) => user.id as u64 == *user_id,
+            AccountingTarget::Project(project_id) => {
+                project.id as u64 == *project_id
+            }
+            AccountingTarget::All => true,
+        };
+        if !matches {
+            continue;
+        }
+        let price = prices
+            .get(&UserClass::from_u32(project.user_class)?)
+            .and_then(|flavor_prices| flavor_prices.get(&state.flavor_name))
+            .copied()
+            .unwrap_or(0.0);
+        costs.push(RunningServerCost {
+            state,
+            project_id: project.id,
+            cost_per_second: price / seconds_in_the_year,
+        });
+    }
+    Ok(costs)
+}
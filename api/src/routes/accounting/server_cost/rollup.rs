@@ -0,0 +1,635 @@
+use std::collections::HashMap;
+
+use actix_web::{
+    HttpResponse,
+    web::{Data, Query, ReqData},
+};
+use anyhow::{Context, anyhow};
+use avina_wire::{
+    accounting::{
+        ServerCostAll, ServerCostProject, ServerCostRollup,
+        ServerCostRollupParams, ServerCostServer, ServerCostUser, ServerState,
+    },
+    user::{Project, User},
+};
+use chrono::{DateTime, Utc};
+use sqlx::{MySql, MySqlPool, Transaction};
+
+use super::get::{
+    calculate_server_cost_for_all_detail_from_states, get_flavor_price_periods,
+};
+use crate::{
+    authorization::require_admin_user,
+    configuration::BillingSettings,
+    database::{
+        accounting::{
+            server_cost_rollup::{
+                NewServerCostRollup, ServerCostRollupFilter,
+                ServerCostRollupRow, delete_server_cost_rollup_day_from_db,
+                insert_server_cost_rollup_day_into_db,
+                select_server_cost_rollup_days_by_filter_from_db,
+                select_server_cost_rollup_days_from_db,
+                select_server_cost_rollups_from_db,
+            },
+            server_state::select_ordered_server_states_by_begin_and_end_from_db,
+        },
+        resources::flavor::select_all_flavors_from_db,
+        user::{
+            project::select_all_projects_from_db,
+            user::select_all_users_from_db,
+        },
+    },
+    error::{NormalApiError, UnexpectedOnlyError},
+};
+
+// rollup days are the days of the billing calendar, so that they line up
+// with the day, month, and year buckets of the cost calculation
+fn day_of(billing: &BillingSettings, time: DateTime<Utc>) -> DateTime<Utc> {
+    billing.start_of_the_day(billing.local(time).date_naive())
+}
+
+// days are 23 or 25 hours long when daylight saving time begins or ends
+fn next_day(billing: &BillingSettings, day: DateTime<Utc>) -> DateTime<Utc> {
+    billing
+        .local(day)
+        .date_naive()
+        .succ_opt()
+        .map_or(DateTime::<Utc>::MAX_UTC, |date| {
+            billing.start_of_the_day(date)
+        })
+}
+
+fn previous_day(
+    billing: &BillingSettings,
+    day: DateTime<Utc>,
+) -> DateTime<Utc> {
+    billing
+        .local(day)
+        .date_naive()
+        .pred_opt()
+        .map_or(DateTime::<Utc>::MIN_UTC, |date| {
+            billing.start_of_the_day(date)
+        })
+}
+
+#[tracing::instrument(name = "server_cost_rollup")]
+pub async fn server_cost_rollup(
+    user: ReqData<User>,
+    db_pool: Data<MySqlPool>,
+    billing: Data<BillingSettings>,
+    params: Query<ServerCostRollupParams>,
+) -> Result<HttpResponse, NormalApiError> {
+    require_admin_user(&user)?;
+    // the current day is never rolled up, since it is not over yet
+    let today = day_of(&billing, Utc::now());
+    let begin = params
+        .begin
+        .map(|begin| day_of(&billing, begin.to_utc()))
+        .unwrap_or(previous_day(&billing, today));
+    let end = params.end.map_or(today, |end| end.to_utc().min(today));
+    let mut days = Vec::new();
+    let mut day = begin;
+    while day < end {
+        days.push(day);
+        day = next_day(&billing, day);
+    }
+    let mut transaction = db_pool
+        .begin()
+        .await
+        .context("Failed to begin transaction")?;
+    let day_count =
+        roll_up_server_cost_for_days(&mut transaction, &billing, &days).await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit transaction")?;
+    Ok(HttpResponse::Ok().content_type("application/json").json(
+        ServerCostRollup {
+            begin: begin.fixed_offset(),
+            end: days
+                .last()
+                .map_or(begin, |day| next_day(&billing, *day))
+                .into(),
+            day_count,
+        },
+    ))
+}
+
+fn server_cost_rollups_from_cost(
+    cost: ServerCostAll,
+    flavors: &HashMap<String, u32>,
+    projects: &HashMap<String, &Project>,
+    users: &HashMap<String, u32>,
+) -> Result<Vec<NewServerCostRollup>, UnexpectedOnlyError> {
+    let mut rollups = Vec::new();
+    for (project_name, project_cost) in cost.projects {
+        let project = projects.get(&project_name).ok_or(anyhow!(
+            "Could not find project with name {project_name}"
+        ))?;
+        for (user_name, user_cost) in project_cost.users {
+            let user_id = *users
+                .get(&user_name)
+                .ok_or(anyhow!("Could not find user with name {user_name}"))?;
+            for (instance_id, server_cost) in user_cost.servers {
+                for (flavor_name, cost) in server_cost.flavors {
+                    let flavor_id =
+                        *flavors.get(&flavor_name).ok_or(anyhow!(
+                            "Could not find flavor with name {flavor_name}"
+                        ))?;
+                    rollups.push(NewServerCostRollup {
+                        instance_id: instance_id.clone(),
+                        user_id,
+                        project_id: project.id,
+                        flavor_id,
+                        user_class: project.user_class,
+                        cost,
+                    });
+                }
+            }
+        }
+    }
+    Ok(rollups)
+}
+
+// distributes the states onto the sorted days they overlap, keeping their
+// order, so that every day only goes through its own states
+fn split_server_states_by_day(
+    billing: &BillingSettings,
+    states: &[ServerState],
+    days: &[DateTime<Utc>],
+) -> Vec<Vec<ServerState>> {
+    let mut states_by_day = vec![Vec::new(); days.len()];
+    for state in states {
+        let first =
+            days.partition_point(|day| next_day(billing, *day) <= state.begin);
+        for (i, day) in days.iter().enumerate().skip(first) {
+            if state.end.is_some_and(|end| end <= *day) {
+                break;
+            }
+            states_by_day[i].push(state.clone());
+        }
+    }
+    states_by_day
+}
+
+// The rollup of a day is calculated with the raw cost calculation for that
+// day, so that summing up rolled up days gives the same result as
+// calculating the cost for the whole period. Prices and states are fetched
+// once for all days, since refreshes can cover years of rolled up days.
+#[tracing::instrument(
+    name = "roll_up_server_cost_for_days",
+    skip(transaction, days)
+)]
+pub(crate) async fn roll_up_server_cost_for_days(
+    transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
+    days: &[DateTime<Utc>],
+) -> Result<u32, UnexpectedOnlyError> {
+    let mut days = days.to_vec();
+    days.sort();
+    days.dedup();
+    let (Some(first), Some(last)) = (days.first(), days.last()) else {
+        return Ok(0);
+    };
+    let (begin, end) = (*first, next_day(billing, *last));
+    let flavors = select_all_flavors_from_db(transaction)
+        .await?
+        .into_iter()
+        .map(|f| (f.name, f.id))
+        .collect::<HashMap<_, _>>();
+    let project_list = select_all_projects_from_db(transaction).await?;
+    let projects = project_list
+        .iter()
+        .map(|p| (p.name.clone(), p))
+        .collect::<HashMap<_, _>>();
+    let user_list = select_all_users_from_db(transaction).await?;
+    let users = user_list
+        .iter()
+        .map(|u| (u.name.clone(), u.id))
+        .collect::<HashMap<_, _>>();
+    let price_periods =
+        get_flavor_price_periods(transaction, billing, begin, end).await?;
+    let states = select_ordered_server_states_by_begin_and_end_from_db(
+        transaction,
+        Some(begin),
+        Some(end),
+    )
+    .await?;
+    let calendar = billing.key();
+    for (day, states) in days
+        .iter()
+        .zip(split_server_states_by_day(billing, &states, &days))
+    {
+        let cost = calculate_server_cost_for_all_detail_from_states(
+            billing,
+            &price_periods,
+            &project_list,
+            &user_list,
+            &states,
+            *day,
+            next_day(billing, *day),
+        );
+        let rollups =
+            server_cost_rollups_from_cost(cost, &flavors, &projects, &users)?;
+        delete_server_cost_rollup_day_from_db(transaction, *day).await?;
+        insert_server_cost_rollup_day_into_db(
+            transaction,
+            &calendar,
+            *day,
+            &rollups,
+        )
+        .await?;
+    }
+    Ok(days.len() as u32)
+}
+
+/// Recalculates the already rolled up days from the day of `since` on.
+///
+/// Has to be called whenever server states or prices change for the past.
+#[tracing::instrument(name = "refresh_server_cost_rollups", skip(transaction))]
+pub(crate) async fn refresh_server_cost_rollups(
+    transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
+    since: DateTime<Utc>,
+) -> Result<u32, UnexpectedOnlyError> {
+    let days = select_server_cost_rollup_days_from_db(
+        transaction,
+        &billing.key(),
+        Some(day_of(billing, since)),
+    )
+    .await?;
+    roll_up_server_cost_for_days(transaction, billing, &days).await
+}
+
+/// Recalculates the already rolled up days with costs matching `filter`.
+///
+/// Has to be called whenever the project of a user or the user class of a
+/// project changes, since the rollups keep the ones of the time they were
+/// rolled up.
+#[tracing::instrument(
+    name = "refresh_server_cost_rollups_by_filter",
+    skip(transaction)
+)]
+pub(crate) async fn refresh_server_cost_rollups_by_filter(
+    transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
+    filter: ServerCostRollupFilter,
+) -> Result<u32, UnexpectedOnlyError> {
+    let days = select_server_cost_rollup_days_by_filter_from_db(
+        transaction,
+        &billing.key(),
+        filter,
+    )
+    .await?;
+    roll_up_server_cost_for_days(transaction, billing, &days).await
+}
+
+/// Refreshes the rolled up days like [`refresh_server_cost_rollups`] and
+/// additionally rolls up the days that passed since the last rolled up day.
+///
+/// Nothing before yesterday is rolled up for the first time here, older
+/// days need to be rolled up explicitly via the rollup endpoint.
+#[tracing::instrument(name = "update_server_cost_rollups", skip(transaction))]
+pub(crate) async fn update_server_cost_rollups(
+    transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
+    since: Option<DateTime<Utc>>,
+) -> Result<u32, UnexpectedOnlyError> {
+    let today = day_of(billing, Utc::now());
+    let yesterday = previous_day(billing, today);
+    let rolled_up_days = select_server_cost_rollup_days_from_db(
+        transaction,
+        &billing.key(),
+        None,
+    )
+    .await?;
+    let mut days = match since {
+        Some(since) => rolled_up_days
+            .iter()
+            .filter(|day| **day >= day_of(billing, since))
+            .cloned()
+            .collect::<Vec<_>>(),
+        None => Vec::new(),
+    };
+    let mut day = rolled_up_days
+        .last()
+        .map_or(yesterday, |day| next_day(billing, *day).min(yesterday));
+    while day < today {
+        if !days.contains(&day) {
+            days.push(day);
+        }
+        day = next_day(billing, day);
+    }
+    roll_up_server_cost_for_days(transaction, billing, &days).await
+}
+
+#[derive(Debug)]
+pub(crate) struct ServerCostPeriods {
+    // range of the rolled up days, days in between that are not rolled up
+    // have no rows in the rollup table and are part of the raw periods
+    pub rollup: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    // remaining periods that need to be calculated from the server states
+    pub raw: Vec<(DateTime<Utc>, DateTime<Utc>)>,
+}
+
+#[tracing::instrument(
+    name = "split_period_by_server_cost_rollups",
+    skip(transaction)
+)]
+pub(crate) async fn split_period_by_server_cost_rollups(
+    transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
+    begin: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<ServerCostPeriods, UnexpectedOnlyError> {
+    let days = select_server_cost_rollup_days_from_db(
+        transaction,
+        &billing.key(),
+        Some(begin),
+    )
+    .await?
+    .into_iter()
+    .filter(|day| next_day(billing, *day) <= end)
+    .collect::<Vec<_>>();
+    let (Some(first), Some(last)) = (days.first(), days.last()) else {
+        return Ok(ServerCostPeriods {
+            rollup: None,
+            raw: vec![(begin, end)],
+        });
+    };
+    let mut raw = Vec::new();
+    let mut current = begin;
+    for day in &days {
+        if current < *day {
+            raw.push((current, *day));
+        }
+        current = next_day(billing, *day);
+    }
+    if current < end {
+        raw.push((current, end));
+    }
+    Ok(ServerCostPeriods {
+        rollup: Some((*first, next_day(billing, *last))),
+        raw,
+    })
+}
+
+#[tracing::instrument(name = "select_server_cost_rollups", skip(transaction))]
+pub(crate) async fn select_server_cost_rollups(
+    transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
+    periods: &ServerCostPeriods,
+    filter: ServerCostRollupFilter,
+) -> Result<Vec<ServerCostRollupRow>, UnexpectedOnlyError> {
+    Ok(match periods.rollup {
+        Some((begin, end)) => {
+            select_server_cost_rollups_from_db(
+                transaction,
+                &billing.key(),
+                begin,
+                end,
+                filter,
+            )
+            .await?
+        }
+        None => Vec::new(),
+    })
+}
+
+fn empty_server_cost_server() -> ServerCostServer {
+    ServerCostServer {
+        total: 0.0,
+        flavors: HashMap::new(),
+    }
+}
+
+fn empty_server_cost_user() -> ServerCostUser {
+    ServerCostUser {
+        total: 0.0,
+        flavors: HashMap::new(),
+        servers: HashMap::new(),
+    }
+}
+
+fn empty_server_cost_project() -> ServerCostProject {
+    ServerCostProject {
+        total: 0.0,
+        flavors: HashMap::new(),
+        users: HashMap::new(),
+    }
+}
+
+// like the raw calculation, the totals only include positive costs
+fn add_to_server_cost(cost: &mut ServerCostServer, flavor: &str, value: f64) {
+    *cost.flavors.entry(flavor.to_string()).or_default() += value;
+    if value > 0. {
+        cost.total += value;
+    }
+}
+
+fn add_to_user_cost(
+    cost: &mut ServerCostUser,
+    server: &str,
+    flavor: &str,
+    value: f64,
+) {
+    add_to_server_cost(
+        cost.servers
+            .entry(server.to_string())
+            .or_insert_with(empty_server_cost_server),
+        flavor,
+        value,
+    );
+    *cost.flavors.entry(flavor.to_string()).or_default() += value;
+    if value > 0. {
+        cost.total += value;
+    }
+}
+
+fn add_to_project_cost(
+    cost: &mut ServerCostProject,
+    user: &str,
+    server: &str,
+    flavor: &str,
+    value: f64,
+) {
+    add_to_user_cost(
+        cost.users
+            .entry(user.to_string())
+            .or_insert_with(empty_server_cost_user),
+        server,
+        flavor,
+        value,
+    );
+    *cost.flavors.entry(flavor.to_string()).or_default() += value;
+    if value > 0. {
+        cost.total += value;
+    }
+}
+
+fn add_to_all_cost(
+    cost: &mut ServerCostAll,
+    project: &str,
+    user: &str,
+    server: &str,
+    flavor: &str,
+    value: f64,
+) {
+    add_to_project_cost(
+        cost.projects
+            .entry(project.to_string())
+            .or_insert_with(empty_server_cost_project),
+        user,
+        server,
+        flavor,
+        value,
+    );
+    *cost.flavors.entry(flavor.to_string()).or_default() += value;
+    if value > 0. {
+        cost.total += value;
+    }
+}
+
+// The merge functions keep entries without any cost, since the raw
+// calculation lists all servers, users, and projects it came across.
+pub(crate) fn merge_server_cost_server(
+    cost: &mut ServerCostServer,
+    other: ServerCostServer,
+) {
+    for (flavor, value) in other.flavors {
+        add_to_server_cost(cost, &flavor, value);
+    }
+}
+
+pub(crate) fn merge_server_cost_user(
+    cost: &mut ServerCostUser,
+    other: ServerCostUser,
+) {
+    for (server, server_cost) in other.servers {
+        cost.servers
+            .entry(server.clone())
+            .or_insert_with(empty_server_cost_server);
+        for (flavor, value) in server_cost.flavors {
+            add_to_user_cost(cost, &server, &flavor, value);
+        }
+    }
+}
+
+pub(crate) fn merge_server_cost_project(
+    cost: &mut ServerCostProject,
+    other: ServerCostProject,
+) {
+    for (user, user_cost) in other.users {
+        let entry = cost
+            .users
+            .entry(user.clone())
+            .or_insert_with(empty_server_cost_user);
+        for server in user_cost.servers.keys() {
+            entry
+                .servers
+                .entry(server.clone())
+                .or_insert_with(empty_server_cost_server);
+        }
+        for (server, server_cost) in user_cost.servers {
+            for (flavor, value) in server_cost.flavors {
+                add_to_project_cost(cost, &user, &server, &flavor, value);
+            }
+        }
+    }
+}
+
+pub(crate) fn merge_server_cost_all(
+    cost: &mut ServerCostAll,
+    other: ServerCostAll,
+) {
+    for (project, project_cost) in other.projects {
+        let entry = cost
+            .projects
+            .entry(project.clone())
+            .or_insert_with(empty_server_cost_project);
+        for (user, user_cost) in &project_cost.users {
+            let entry = entry
+                .users
+                .entry(user.clone())
+                .or_insert_with(empty_server_cost_user);
+            for server in user_cost.servers.keys() {
+                entry
+                    .servers
+                    .entry(server.clone())
+                    .or_insert_with(empty_server_cost_server);
+            }
+        }
+        for (user, user_cost) in project_cost.users {
+            for (server, server_cost) in user_cost.servers {
+                for (flavor, value) in server_cost.flavors {
+                    add_to_all_cost(
+                        cost, &project, &user, &server, &flavor, value,
+                    );
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn server_cost_total_from_rollups(
+    rollups: &[ServerCostRollupRow],
+) -> f64 {
+    rollups.iter().map(|r| r.cost).filter(|c| *c > 0.).sum()
+}
+
+pub(crate) fn server_cost_server_from_rollups(
+    rollups: Vec<ServerCostRollupRow>,
+) -> ServerCostServer {
+    let mut cost = empty_server_cost_server();
+    for r in rollups {
+        add_to_server_cost(&mut cost, &r.flavor_name, r.cost);
+    }
+    cost
+}
+
+pub(crate) fn server_cost_user_from_rollups(
+    rollups: Vec<ServerCostRollupRow>,
+) -> ServerCostUser {
+    let mut cost = empty_server_cost_user();
+    for r in rollups {
+        add_to_user_cost(&mut cost, &r.instance_id, &r.flavor_name, r.cost);
+    }
+    cost
+}
+
+pub(crate) fn server_cost_project_from_rollups(
+    rollups: Vec<ServerCostRollupRow>,
+) -> ServerCostProject {
+    let mut cost = empty_server_cost_project();
+    for r in rollups {
+        add_to_project_cost(
+            &mut cost,
+            &r.username,
+            &r.instance_id,
+            &r.flavor_name,
+            r.cost,
+        );
+    }
+    cost
+}
+
+pub(crate) fn server_cost_all_from_rollups(
+    rollups: Vec<ServerCostRollupRow>,
+) -> ServerCostAll {
+    let mut cost = ServerCostAll {
+        total: 0.0,
+        flavors: HashMap::new(),
+        projects: HashMap::new(),
+    };
+    for r in rollups {
+        add_to_all_cost(
+            &mut cost,
+            &r.project_name,
+            &r.username,
+            &r.instance_id,
+            &r.flavor_name,
+            r.cost,
+        );
+    }
+    cost
+}
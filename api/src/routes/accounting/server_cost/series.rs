@@ -0,0 +1,75 @@
+use actix_web::{
+    HttpResponse,
+    web::{Data, Query, ReqData},
+};
+use anyhow::Context;
+use avina_wire::{
+    accounting::{ServerCostPoint, ServerCostSeriesParams, TimeGrouping},
+    user::User,
+};
+use chrono::Utc;
+use sqlx::MySqlPool;
+
+use super::get::calculate_server_cost_buckets;
+use crate::{
+    configuration::BillingSettings, error::OptionApiError,
+    routes::accounting::target::select_accounting_target,
+};
+
+#[tracing::instrument(name = "server_cost_series")]
+pub async fn server_cost_series(
+    user: ReqData<User>,
+    db_pool: Data<MySqlPool>,
+    billing: Data<BillingSettings>,
+    params: Query<ServerCostSeriesParams>,
+) -> Result<HttpResponse, OptionApiError> {
+    let end = params.end.map_or(Utc::now(), |end| end.to_utc());
+    let begin = params
+        .begin
+        .map_or(billing.start_of_the_current_year(), |begin| begin.to_utc());
+    let by = params.by.clone().unwrap_or(TimeGrouping::Month);
+    let mut transaction = db_pool
+        .begin()
+        .await
+        .context("Failed to begin transaction")?;
+    let target = select_accounting_target(
+        &mut transaction,
+        &user,
+        params.all,
+        params.project,
+        params.user,
+        params.server.clone(),
+    )
+    .await?;
+    let buckets = calculate_server_cost_buckets(
+        &mut transaction,
+        &billing,
+        &target,
+        begin,
+        end,
+        &by,
+        None,
+    )
+    .await?;
+    let mut cumulative = 0.0;
+    let points = buckets
+        .into_iter()
+        .map(|bucket| {
+            let cost = bucket.cost.total();
+            cumulative += cost;
+            ServerCostPoint {
+                begin: bucket.begin,
+                end: bucket.end,
+                cost,
+                cumulative,
+            }
+        })
+        .collect::<Vec<_>>();
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit transaction")?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(points))
+}
@@ -0,0 +1,52 @@
+use actix_web::{
+    HttpResponse,
+    web::{Data, Query, ReqData},
+};
+use anyhow::Context;
+use avina_wire::{
+    accounting::{ServerStateArchive, ServerStateArchiveParams},
+    user::User,
+};
+use chrono::Utc;
+use sqlx::MySqlPool;
+
+use crate::{
+    authorization::require_admin_user, configuration::BillingSettings,
+    database::accounting::server_state_archive::archive_server_states_in_db,
+    error::NormalApiError,
+};
+
+#[tracing::instrument(name = "server_state_archive")]
+pub async fn server_state_archive(
+    user: ReqData<User>,
+    db_pool: Data<MySqlPool>,
+    billing: Data<BillingSettings>,
+    params: Query<ServerStateArchiveParams>,
+) -> Result<HttpResponse, NormalApiError> {
+    require_admin_user(&user)?;
+    let year = billing
+        .year_of(Utc::now())
+        .checked_sub(params.years)
+        .ok_or(NormalApiError::ValidationError(
+            "Number of years is too large".to_string(),
+        ))?;
+    // only whole billing years are archived, so that costs of a year are
+    // either calculated from the archive or from the live tables
+    let before = billing.start_of_the_year(year);
+    let mut transaction = db_pool
+        .begin()
+        .await
+        .context("Failed to begin transaction")?;
+    let archived_state_count =
+        archive_server_states_in_db(&mut transaction, before).await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit transaction")?;
+    Ok(HttpResponse::Ok().content_type("application/json").json(
+        ServerStateArchive {
+            before: before.fixed_offset(),
+            archived_state_count: archived_state_count as u32,
+        },
+    ))
+}
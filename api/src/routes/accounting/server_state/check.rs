@@ -0,0 +1,261 @@
+use std::collections::BTreeMap;
+
+use actix_web::{
+    HttpResponse,
+    web::{Data, Query, ReqData},
+};
+use anyhow::Context;
+use avina_wire::{
+    accounting::{
+        ServerState, ServerStateCheck, ServerStateCheckParams,
+        ServerStateIssue, ServerStateIssueKind,
+    },
+    user::User,
+};
+use chrono::{DateTime, Utc};
+use sqlx::{MySql, MySqlPool, Transaction};
+
+use super::delete::delete_server_state_from_db;
+use crate::{
+    authorization::require_admin_user,
+    configuration::BillingSettings,
+    database::accounting::server_state::{
+        select_all_server_states_from_db, update_server_state_end_in_db,
+    },
+    error::{MinimalApiError, NormalApiError},
+    routes::accounting::{
+        server_consumption::get::CONSUMING_STATES,
+        server_cost::rollup::refresh_server_cost_rollups,
+    },
+};
+
+const NON_CONSUMING_STATES: [&str; 5] = [
+    "DELETED",
+    "ERROR",
+    "SHELVED",
+    "SHELVED_OFFLOADED",
+    "SOFT_DELETED",
+];
+
+#[tracing::instrument(name = "server_state_check")]
+pub async fn server_state_check(
+    user: ReqData<User>,
+    db_pool: Data<MySqlPool>,
+    billing: Data<BillingSettings>,
+    params: Query<ServerStateCheckParams>,
+) -> Result<HttpResponse, NormalApiError> {
+    require_admin_user(&user)?;
+    let mut transaction = db_pool
+        .begin()
+        .await
+        .context("Failed to begin transaction")?;
+    let check = check_server_states(
+        &mut transaction,
+        &billing,
+        params.repair.unwrap_or(false),
+    )
+    .await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit transaction")?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(check))
+}
+
+fn is_identical(state: &ServerState, next: &ServerState) -> bool {
+    state.instance_name == next.instance_name
+        && state.flavor == next.flavor
+        && state.status == next.status
+        && state.user == next.user
+}
+
+pub(super) fn group_server_states_by_server(
+    states: Vec<ServerState>,
+) -> BTreeMap<String, Vec<ServerState>> {
+    let mut states_by_server = BTreeMap::<_, Vec<_>>::new();
+    for state in states {
+        states_by_server
+            .entry(state.instance_id.clone())
+            .or_default()
+            .push(state);
+    }
+    for states in states_by_server.values_mut() {
+        states.sort_by_key(|s| (s.begin, s.id));
+    }
+    states_by_server
+}
+
+// Merges each chain of identical adjacent states of one server into the
+// first state of the chain and returns the (first, merged) index pairs.
+pub(super) fn merge_adjacent_server_states(
+    states: &mut [ServerState],
+) -> Vec<(usize, usize)> {
+    let mut merges = Vec::new();
+    let mut first = 0;
+    for i in 1..states.len() {
+        if states[first].end == Some(states[i].begin)
+            && is_identical(&states[first], &states[i])
+        {
+            states[first].end = states[i].end;
+            merges.push((first, i));
+        } else {
+            first = i;
+        }
+    }
+    merges
+}
+
+#[tracing::instrument(name = "check_server_states", skip(transaction))]
+pub async fn check_server_states(
+    transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
+    repair: bool,
+) -> Result<ServerStateCheck, MinimalApiError> {
+    let states = select_all_server_states_from_db(transaction).await?;
+    let checked_state_count = states.len() as u32;
+    let states_by_server = group_server_states_by_server(states);
+
+    let mut issues = Vec::new();
+    let mut closed_state_count = 0;
+    let mut merged_state_count = 0;
+    // closing a duplicate open state changes the cost from its new end on,
+    // merging identical states does not change the cost
+    let mut changed_since: Option<DateTime<Utc>> = None;
+    for (instance_id, mut states) in states_by_server {
+        let issue =
+            |kind, state: &ServerState, other: Option<u32>| ServerStateIssue {
+                kind,
+                instance_id: instance_id.clone(),
+                server_state: state.id,
+                other_server_state: other,
+                repaired: repair
+                    && matches!(
+                        kind,
+                        ServerStateIssueKind::DuplicateOpen
+                            | ServerStateIssueKind::Mergeable
+                    ),
+            };
+
+        for state in &states {
+            if state.end.is_some_and(|end| end < state.begin) {
+                issues.push(issue(
+                    ServerStateIssueKind::NegativeDuration,
+                    state,
+                    None,
+                ));
+            }
+            let status = state.status.as_str();
+            if !CONSUMING_STATES.contains(&status)
+                && !NON_CONSUMING_STATES.contains(&status)
+            {
+                issues.push(issue(
+                    ServerStateIssueKind::UnknownStatus,
+                    state,
+                    None,
+                ));
+            }
+        }
+
+        // each state is compared with the earlier state that ends last, so a
+        // long state is checked against all states it covers, duplicate open
+        // states are closed when the next state begins, overlaps and gaps of
+        // finished states need a manual decision
+        let mut changed = vec![false; states.len()];
+        let mut last = 0;
+        for i in 1..states.len() {
+            let (next_begin, next_end, next_id) =
+                (states[i].begin, states[i].end, states[i].id);
+            let state = &mut states[last];
+            match (state.end, next_end) {
+                (None, None) => {
+                    issues.push(issue(
+                        ServerStateIssueKind::DuplicateOpen,
+                        state,
+                        Some(next_id),
+                    ));
+                    state.end = Some(next_begin);
+                    changed[last] = true;
+                    closed_state_count += 1;
+                    let since = next_begin.to_utc();
+                    changed_since = Some(
+                        changed_since.map_or(since, |other| other.min(since)),
+                    );
+                }
+                (None, Some(_)) => issues.push(issue(
+                    ServerStateIssueKind::Overlap,
+                    state,
+                    Some(next_id),
+                )),
+                (Some(end), _) if end > next_begin => issues.push(issue(
+                    ServerStateIssueKind::Overlap,
+                    state,
+                    Some(next_id),
+                )),
+                (Some(end), _) if end < next_begin => issues.push(issue(
+                    ServerStateIssueKind::Gap,
+                    state,
+                    Some(next_id),
+                )),
+                _ => {}
+            }
+            let ends_later = match (state.end, next_end) {
+                (_, None) => true,
+                (None, Some(_)) => false,
+                (Some(end), Some(next_end)) => next_end > end,
+            };
+            if ends_later {
+                last = i;
+            }
+        }
+
+        let mut merged = vec![false; states.len()];
+        for (first, i) in merge_adjacent_server_states(&mut states) {
+            issues.push(issue(
+                ServerStateIssueKind::Mergeable,
+                &states[first],
+                Some(states[i].id),
+            ));
+            changed[first] = true;
+            merged[i] = true;
+            merged_state_count += 1;
+        }
+
+        if !repair {
+            continue;
+        }
+        for (i, state) in states.iter().enumerate() {
+            if merged[i] {
+                delete_server_state_from_db(transaction, state.id as u64)
+                    .await?;
+            } else if changed[i] {
+                update_server_state_end_in_db(
+                    transaction,
+                    state.id as u64,
+                    state.end.map(|end| end.to_utc()),
+                )
+                .await?;
+            }
+        }
+    }
+
+    if repair && let Some(since) = changed_since {
+        refresh_server_cost_rollups(transaction, billing, since).await?;
+    }
+
+    let count = |kind| issues.iter().filter(|i| i.kind == kind).count() as u32;
+    Ok(ServerStateCheck {
+        checked_state_count,
+        overlap_count: count(ServerStateIssueKind::Overlap),
+        gap_count: count(ServerStateIssueKind::Gap),
+        duplicate_open_count: count(ServerStateIssueKind::DuplicateOpen),
+        negative_duration_count: count(ServerStateIssueKind::NegativeDuration),
+        unknown_status_count: count(ServerStateIssueKind::UnknownStatus),
+        mergeable_count: count(ServerStateIssueKind::Mergeable),
+        repair,
+        closed_state_count: if repair { closed_state_count } else { 0 },
+        merged_state_count: if repair { merged_state_count } else { 0 },
+        issues,
+    })
+}
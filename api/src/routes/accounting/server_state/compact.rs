@@ -0,0 +1,74 @@
+use std::collections::BTreeSet;
+
+use actix_web::{
+    HttpResponse,
+    web::{Data, ReqData},
+};
+use anyhow::Context;
+use avina_wire::{accounting::ServerStateCompaction, user::User};
+use sqlx::{MySql, MySqlPool, Transaction};
+
+use super::{
+    check::{group_server_states_by_server, merge_adjacent_server_states},
+    delete::delete_server_state_from_db,
+};
+use crate::{
+    authorization::require_admin_user,
+    database::accounting::server_state::{
+        select_all_server_states_from_db, update_server_state_end_in_db,
+    },
+    error::{MinimalApiError, NormalApiError},
+};
+
+#[tracing::instrument(name = "server_state_compact")]
+pub async fn server_state_compact(
+    user: ReqData<User>,
+    db_pool: Data<MySqlPool>,
+) -> Result<HttpResponse, NormalApiError> {
+    require_admin_user(&user)?;
+    let mut transaction = db_pool
+        .begin()
+        .await
+        .context("Failed to begin transaction")?;
+    let compaction = compact_server_states(&mut transaction).await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit transaction")?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(compaction))
+}
+
+#[tracing::instrument(name = "compact_server_states", skip(transaction))]
+pub async fn compact_server_states(
+    transaction: &mut Transaction<'_, MySql>,
+) -> Result<ServerStateCompaction, MinimalApiError> {
+    let states = select_all_server_states_from_db(transaction).await?;
+    let checked_state_count = states.len() as u32;
+    let mut merged_state_count = 0;
+    for mut states in group_server_states_by_server(states).into_values() {
+        let merges = merge_adjacent_server_states(&mut states);
+        for &(_, merged) in &merges {
+            delete_server_state_from_db(transaction, states[merged].id as u64)
+                .await?;
+        }
+        let firsts = merges
+            .iter()
+            .map(|(first, _)| *first)
+            .collect::<BTreeSet<_>>();
+        for first in firsts {
+            update_server_state_end_in_db(
+                transaction,
+                states[first].id as u64,
+                states[first].end.map(|end| end.to_utc()),
+            )
+            .await?;
+        }
+        merged_state_count += merges.len() as u32;
+    }
+    Ok(ServerStateCompaction {
+        checked_state_count,
+        merged_state_count,
+    })
+}
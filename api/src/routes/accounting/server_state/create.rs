@@ -11,6 +11,7 @@ use sqlx::MySqlPool;
 
 use crate::{
     authorization::require_admin_user,
+    configuration::BillingSettings,
     database::{
         accounting::server_state::{
             NewServerState, insert_server_state_into_db,
@@ -19,12 +20,14 @@ use crate::{
         user::user::select_user_name_from_db,
     },
     error::{NormalApiError, OptionApiError},
+    routes::accounting::server_cost::rollup::refresh_server_cost_rollups,
 };
 
 #[tracing::instrument(name = "server_state_create")]
 pub async fn server_state_create(
     user: ReqData<User>,
     db_pool: Data<MySqlPool>,
+    billing: Data<BillingSettings>,
     data: Json<ServerStateCreateData>,
 ) -> Result<HttpResponse, OptionApiError> {
     require_admin_user(&user)?;
@@ -48,6 +51,12 @@ pub async fn server_state_create(
     .await?;
     let id = insert_server_state_into_db(&mut transaction, &new_server_state)
         .await?;
+    refresh_server_cost_rollups(
+        &mut transaction,
+        &billing,
+        new_server_state.begin,
+    )
+    .await?;
     transaction
         .commit()
         .await
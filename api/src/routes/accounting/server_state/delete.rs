@@ -9,13 +9,17 @@ use sqlx::{Executor, MySql, MySqlPool, Transaction};
 use super::ServerStateIdParam;
 use crate::{
     authorization::require_admin_user,
+    configuration::BillingSettings,
+    database::accounting::server_state::select_maybe_server_state_from_db,
     error::{MinimalApiError, NormalApiError},
+    routes::accounting::server_cost::rollup::refresh_server_cost_rollups,
 };
 
 #[tracing::instrument(name = "server_state_delete")]
 pub async fn server_state_delete(
     user: ReqData<User>,
     db_pool: Data<MySqlPool>,
+    billing: Data<BillingSettings>,
     params: Path<ServerStateIdParam>,
 ) -> Result<HttpResponse, NormalApiError> {
     require_admin_user(&user)?;
@@ -23,11 +27,24 @@ pub async fn server_state_delete(
         .begin()
         .await
         .context("Failed to begin transaction")?;
+    let server_state = select_maybe_server_state_from_db(
+        &mut transaction,
+        params.server_state_id as u64,
+    )
+    .await?;
     delete_server_state_from_db(
         &mut transaction,
         params.server_state_id as u64,
     )
     .await?;
+    if let Some(server_state) = server_state {
+        refresh_server_cost_rollups(
+            &mut transaction,
+            &billing,
+            server_state.begin.to_utc(),
+        )
+        .await?;
+    }
     transaction
         .commit()
         .await
@@ -36,7 +53,7 @@ pub async fn server_state_delete(
 }
 
 #[tracing::instrument(name = "delete_server_state_from_db", skip(transaction))]
-async fn delete_server_state_from_db(
+pub(crate) async fn delete_server_state_from_db(
     transaction: &mut Transaction<'_, MySql>,
     server_state_id: u64,
 ) -> Result<(), MinimalApiError> {
@@ -2,23 +2,36 @@ use std::{collections::HashMap, hash::Hash};
 
 use actix_web::{
     HttpResponse,
-    web::{Data, ReqData},
+    web::{Data, Query, ReqData},
 };
 use anyhow::{Context, anyhow};
-use avina_wire::{accounting::ServerStateImport, user::User};
-use chrono::Utc;
+use avina_wire::{
+    accounting::{
+        ServerStateImport, ServerStateImportEndedState,
+        ServerStateImportNewState, ServerStateImportParams,
+        ServerStateImportSkip,
+    },
+    user::User,
+};
+use chrono::{DateTime, Utc};
 use sqlx::{Executor, FromRow, MySql, MySqlPool, Transaction};
 
 use crate::{
     authorization::require_admin_user,
-    database::accounting::server_state::{
-        NewServerState, insert_server_state_into_db,
-        select_unfinished_server_states_from_db,
+    database::{
+        accounting::server_state::{
+            NewServerState, insert_server_state_into_db,
+            select_maybe_last_server_state_end_by_server_from_db,
+            select_unfinished_server_states_from_db,
+        },
+        resources::flavor::select_all_flavors_from_db,
+        user::user::select_all_users_from_db,
     },
     error::{
         NotFoundOrUnexpectedApiError, OptionApiError, UnexpectedOnlyError,
     },
     openstack::{OpenStack, ServerDetailed},
+    scheduler::ImportScheduler,
 };
 
 // NOTE: the hashmap cannot contain (None, None).
@@ -43,60 +56,240 @@ where
     hm3
 }
 
-#[tracing::instrument(name = "server_state_import", skip(openstack))]
+#[tracing::instrument(
+    name = "server_state_import",
+    skip(openstack, import_scheduler)
+)]
 pub async fn server_state_import(
     user: ReqData<User>,
     db_pool: Data<MySqlPool>,
     openstack: Data<OpenStack>,
+    import_scheduler: Data<ImportScheduler>,
+    params: Query<ServerStateImportParams>,
     // TODO: is the NormalApiError::ValidationError used?
     // Maybe we need a AuthOrUnexpectedError type.
 ) -> Result<HttpResponse, OptionApiError> {
     require_admin_user(&user)?;
-    let mut transaction = db_pool
-        .begin()
-        .await
-        .context("Failed to begin transaction")?;
+    let server_state_import = import_scheduler
+        .run_server_state_import(
+            &db_pool,
+            &openstack,
+            params.dry_run.unwrap_or(false),
+        )
+        .await?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(server_state_import))
+}
+
+#[tracing::instrument(
+    name = "server_state_import_status",
+    skip(import_scheduler)
+)]
+pub async fn server_state_import_status(
+    user: ReqData<User>,
+    import_scheduler: Data<ImportScheduler>,
+) -> Result<HttpResponse, OptionApiError> {
+    require_admin_user(&user)?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(import_scheduler.status()))
+}
 
+#[tracing::instrument(
+    name = "import_server_states",
+    skip(transaction, openstack)
+)]
+// An incremental import with changes_since only lists the servers changed
+// since then, which includes the deleted ones, and leaves the states of all
+// other servers as they are.
+pub async fn import_server_states(
+    transaction: &mut Transaction<'_, MySql>,
+    openstack: &OpenStack,
+    changes_since: Option<DateTime<Utc>>,
+) -> Result<ServerStateImport, OptionApiError> {
     // TODO: should we add additional context to the error here?
-    let servers = openstack
-        .get_servers()
-        .await?
-        .iter()
-        .cloned()
+    let (deleted_servers, servers): (Vec<_>, Vec<_>) = match changes_since {
+        Some(since) => openstack
+            .get_servers_changed_since(since)
+            .await?
+            .into_iter()
+            .partition(|s| s.status == "DELETED"),
+        None => (Vec::new(), openstack.get_servers().await?),
+    };
+    let servers = servers
+        .into_iter()
+        .map(|s| (s.id.clone(), s))
+        .collect::<HashMap<_, _>>();
+    let mut deleted_servers = deleted_servers
+        .into_iter()
         .map(|s| (s.id.clone(), s))
         .collect::<HashMap<_, _>>();
-    let states = select_unfinished_server_states_from_db(&mut transaction)
+    let mut states = select_unfinished_server_states_from_db(transaction)
         .await?
         .iter()
         .cloned()
         .map(|s| (s.instance_id.clone(), s))
         .collect::<HashMap<_, _>>();
+    if changes_since.is_some() {
+        states.retain(|id, _| {
+            servers.contains_key(id) || deleted_servers.contains_key(id)
+        });
+    }
+
+    let flavor_ids = select_all_flavors_from_db(transaction)
+        .await?
+        .into_iter()
+        .map(|f| (f.openstack_id, f.id))
+        .collect::<HashMap<_, _>>();
+    let user_ids = select_all_users_from_db(transaction)
+        .await?
+        .into_iter()
+        .map(|u| (u.openstack_id, u.id))
+        .collect::<HashMap<_, _>>();
 
+    // servers deleted since the oldest vanished state began, so that their
+    // states can be ended at the actual deletion time, an incremental import
+    // already listed them
+    let vanished_since = states
+        .values()
+        .filter(|s| !servers.contains_key(&s.instance_id))
+        .map(|s| s.begin.to_utc())
+        .min();
+    if let (None, Some(since)) = (changes_since, vanished_since) {
+        deleted_servers = openstack
+            .get_deleted_servers(since)
+            .await?
+            .into_iter()
+            .map(|s| (s.id.clone(), s))
+            .collect::<HashMap<_, _>>();
+    }
+
+    let now = Utc::now();
     let servers_and_states = union_hash_zip(servers, states);
 
-    let mut new_state_count = 0;
-    let mut end_state_count = 0;
+    let mut new_states = Vec::new();
+    let mut ended_states = Vec::new();
+    let mut skipped = Vec::new();
+    let mut resize_count = 0;
+    let mut rename_count = 0;
+    let mut transfer_count = 0;
 
     for server_and_state in servers_and_states.values() {
         match server_and_state {
             (Some(server), Some(state)) => {
-                if server.status != state.status {
-                    end_server_state_in_db(&mut transaction, state.id as u64)
-                        .await?;
-                    end_state_count += 1;
-                    new_state_count +=
-                        create_server_state_in_db(&mut transaction, server)
-                            .await?;
+                let flavor_id = flavor_ids.get(&server.flavor.id);
+                let user_id = user_ids.get(&server.tenant_id);
+                let resized = flavor_id != Some(&state.flavor);
+                let transferred = user_id != Some(&state.user);
+                let renamed = server.name != state.instance_name;
+                if server.status != state.status
+                    || resized
+                    || transferred
+                    || renamed
+                {
+                    // the open state is kept until the server can be billed
+                    // with a replacement, instead of not billing it at all
+                    let reason = match (flavor_id, user_id) {
+                        (None, _) => Some(format!(
+                            "Flavor {} not found",
+                            server.flavor.id
+                        )),
+                        (_, None) => {
+                            Some(format!("User {} not found", server.tenant_id))
+                        }
+                        _ => None,
+                    };
+                    if let Some(reason) = reason {
+                        tracing::warn!(
+                            "{reason}, keeping server state {} open.",
+                            state.id
+                        );
+                        skipped.push(ServerStateImportSkip {
+                            instance_id: server.id.clone(),
+                            instance_name: server.name.clone(),
+                            reason,
+                        });
+                        continue;
+                    }
+                    // the last update of the server is the transition time
+                    let transition =
+                        server.updated.max(state.begin.to_utc()).min(now);
+                    end_server_state_in_db(
+                        transaction,
+                        state.id as u64,
+                        transition,
+                    )
+                    .await?;
+                    ended_states.push(ServerStateImportEndedState {
+                        id: state.id,
+                        end: transition.fixed_offset(),
+                        instance_id: state.instance_id.clone(),
+                        instance_name: state.instance_name.clone(),
+                        status: state.status.clone(),
+                    });
+                    new_states.extend(
+                        create_server_state_in_db(
+                            transaction,
+                            server,
+                            transition,
+                            &mut skipped,
+                        )
+                        .await?,
+                    );
+                    if resized {
+                        resize_count += 1;
+                    }
+                    if renamed {
+                        rename_count += 1;
+                    }
+                    if transferred {
+                        transfer_count += 1;
+                    }
                 }
             }
             (Some(server), None) => {
-                new_state_count +=
-                    create_server_state_in_db(&mut transaction, server).await?;
+                // the server might have had states before, e.g. ones ended
+                // by an earlier import, which must not overlap the new one
+                let last_end =
+                    select_maybe_last_server_state_end_by_server_from_db(
+                        transaction,
+                        server.id.clone(),
+                    )
+                    .await?;
+                let begin = server
+                    .launched_at
+                    .unwrap_or(server.created)
+                    .max(last_end.unwrap_or(DateTime::<Utc>::MIN_UTC))
+                    .min(now);
+                new_states.extend(
+                    create_server_state_in_db(
+                        transaction,
+                        server,
+                        begin,
+                        &mut skipped,
+                    )
+                    .await?,
+                );
             }
             (None, Some(state)) => {
-                end_server_state_in_db(&mut transaction, state.id as u64)
+                let end = match deleted_servers.get(&state.instance_id) {
+                    Some(server) => server
+                        .terminated_at
+                        .unwrap_or(server.updated)
+                        .max(state.begin.to_utc())
+                        .min(now),
+                    None => now,
+                };
+                end_server_state_in_db(transaction, state.id as u64, end)
                     .await?;
-                end_state_count += 1;
+                ended_states.push(ServerStateImportEndedState {
+                    id: state.id,
+                    end: end.fixed_offset(),
+                    instance_id: state.instance_id.clone(),
+                    instance_name: state.instance_name.clone(),
+                    status: state.status.clone(),
+                });
             }
             (None, None) => {
                 return Err(anyhow!(
@@ -107,22 +300,24 @@ pub async fn server_state_import(
         }
     }
 
-    transaction
-        .commit()
-        .await
-        .context("Failed to commit transaction")?;
-    Ok(HttpResponse::Ok().content_type("application/json").json(
-        ServerStateImport {
-            new_state_count,
-            end_state_count,
-        },
-    ))
+    Ok(ServerStateImport {
+        new_state_count: new_states.len() as u32,
+        end_state_count: ended_states.len() as u32,
+        resize_count,
+        rename_count,
+        transfer_count,
+        dry_run: false,
+        new_states,
+        ended_states,
+        skipped,
+    })
 }
 
 #[tracing::instrument(name = "end_server_state_in_db", skip(transaction))]
 pub async fn end_server_state_in_db(
     transaction: &mut Transaction<'_, MySql>,
     server_state_id: u64,
+    end: DateTime<Utc>,
 ) -> Result<(), NotFoundOrUnexpectedApiError> {
     let query = sqlx::query!(
         r#"
@@ -131,7 +326,7 @@ pub async fn end_server_state_in_db(
             end = ?
         WHERE id = ?
         "#,
-        Utc::now(),
+        end,
         server_state_id,
     );
     transaction
@@ -141,11 +336,16 @@ pub async fn end_server_state_in_db(
     Ok(())
 }
 
-#[tracing::instrument(name = "create_server_state_in_db", skip(transaction))]
+#[tracing::instrument(
+    name = "create_server_state_in_db",
+    skip(transaction, skipped)
+)]
 pub async fn create_server_state_in_db(
     transaction: &mut Transaction<'_, MySql>,
     server: &ServerDetailed,
-) -> Result<u32, OptionApiError> {
+    begin: DateTime<Utc>,
+    skipped: &mut Vec<ServerStateImportSkip>,
+) -> Result<Option<ServerStateImportNewState>, OptionApiError> {
     let Some(flavor_id) = select_maybe_flavor_id_by_openstack_id_from_db(
         transaction,
         server.flavor.id.clone(),
@@ -156,7 +356,12 @@ pub async fn create_server_state_in_db(
             "Flavor {} not found, skipping server state creation.",
             server.flavor.id.clone()
         );
-        return Ok(0);
+        skipped.push(ServerStateImportSkip {
+            instance_id: server.id.clone(),
+            instance_name: server.name.clone(),
+            reason: format!("Flavor {} not found", server.flavor.id),
+        });
+        return Ok(None);
     };
     let Some(user_id) = select_maybe_user_id_by_openstack_id_from_db(
         transaction,
@@ -168,10 +373,15 @@ pub async fn create_server_state_in_db(
             "User {} not found, skipping server state creation.",
             server.tenant_id.clone()
         );
-        return Ok(0);
+        skipped.push(ServerStateImportSkip {
+            instance_id: server.id.clone(),
+            instance_name: server.name.clone(),
+            reason: format!("User {} not found", server.tenant_id),
+        });
+        return Ok(None);
     };
     let server_state = NewServerState {
-        begin: Utc::now(),
+        begin,
         end: None,
         instance_id: server.id.clone(),
         instance_name: server.name.clone(),
@@ -180,7 +390,14 @@ pub async fn create_server_state_in_db(
         user: user_id as u32,
     };
     let _ = insert_server_state_into_db(transaction, &server_state).await?;
-    Ok(1)
+    Ok(Some(ServerStateImportNewState {
+        begin: begin.fixed_offset(),
+        instance_id: server_state.instance_id,
+        instance_name: server_state.instance_name,
+        flavor: server_state.flavor,
+        status: server_state.status,
+        user: server_state.user,
+    }))
 }
 
 #[tracing::instrument(
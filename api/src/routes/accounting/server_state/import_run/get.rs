@@ -0,0 +1,39 @@
+use actix_web::{
+    HttpResponse,
+    web::{Data, Path, ReqData},
+};
+use anyhow::Context;
+use avina_wire::user::User;
+use sqlx::MySqlPool;
+
+use super::ImportRunIdParam;
+use crate::{
+    authorization::require_admin_user,
+    database::accounting::server_state_import_run::select_server_state_import_run_from_db,
+    error::OptionApiError,
+};
+
+#[tracing::instrument(name = "server_state_import_run_get")]
+pub async fn server_state_import_run_get(
+    user: ReqData<User>,
+    db_pool: Data<MySqlPool>,
+    params: Path<ImportRunIdParam>,
+) -> Result<HttpResponse, OptionApiError> {
+    require_admin_user(&user)?;
+    let mut transaction = db_pool
+        .begin()
+        .await
+        .context("Failed to begin transaction")?;
+    let import_run = select_server_state_import_run_from_db(
+        &mut transaction,
+        params.import_run_id as u64,
+    )
+    .await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit transaction")?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(import_run))
+}
@@ -0,0 +1,44 @@
+use actix_web::{
+    HttpResponse,
+    web::{Data, Query, ReqData},
+};
+use anyhow::Context;
+use avina_wire::{accounting::ServerStateImportRunListParams, user::User};
+use chrono::{DateTime, Utc};
+use sqlx::MySqlPool;
+
+use crate::{
+    authorization::require_admin_user,
+    database::accounting::server_state_import_run::select_server_state_import_runs_from_db,
+    error::OptionApiError,
+};
+
+const DEFAULT_IMPORT_RUN_LIMIT: u32 = 100;
+
+#[tracing::instrument(name = "server_state_import_run_list")]
+pub async fn server_state_import_run_list(
+    user: ReqData<User>,
+    db_pool: Data<MySqlPool>,
+    params: Query<ServerStateImportRunListParams>,
+) -> Result<HttpResponse, OptionApiError> {
+    require_admin_user(&user)?;
+    let since = params
+        .since
+        .map(|since| since.to_utc())
+        .unwrap_or(DateTime::<Utc>::UNIX_EPOCH);
+    let limit = params.limit.unwrap_or(DEFAULT_IMPORT_RUN_LIMIT);
+    let mut transaction = db_pool
+        .begin()
+        .await
+        .context("Failed to begin transaction")?;
+    let import_runs =
+        select_server_state_import_runs_from_db(&mut transaction, since, limit)
+            .await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit transaction")?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(import_runs))
+}
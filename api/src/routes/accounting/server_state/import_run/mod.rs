@@ -0,0 +1,21 @@
+use actix_web::{
+    Scope,
+    web::{get, scope},
+};
+use serde::Deserialize;
+
+mod list;
+use list::server_state_import_run_list;
+mod get;
+use get::server_state_import_run_get;
+
+pub fn server_state_import_runs_scope() -> Scope {
+    scope("/importruns")
+        .route("/", get().to(server_state_import_run_list))
+        .route("/{import_run_id}", get().to(server_state_import_run_get))
+}
+
+#[derive(Deserialize, Debug)]
+struct ImportRunIdParam {
+    import_run_id: u32,
+}
@@ -14,8 +14,16 @@ mod modify;
 use modify::server_state_modify;
 mod delete;
 use delete::server_state_delete;
-mod import;
-use import::server_state_import;
+pub(crate) mod import;
+use import::{server_state_import, server_state_import_status};
+mod import_run;
+use import_run::server_state_import_runs_scope;
+mod check;
+use check::server_state_check;
+mod compact;
+use compact::server_state_compact;
+mod archive;
+use archive::server_state_archive;
 
 pub fn server_states_scope() -> Scope {
     scope("/serverstates")
@@ -26,6 +34,11 @@ pub fn server_states_scope() -> Scope {
         .route("/{server_state_id}/", patch().to(server_state_modify))
         .route("/{server_state_id}/", delete().to(server_state_delete))
         .route("/import/", get().to(server_state_import))
+        .route("/import/status/", get().to(server_state_import_status))
+        .route("/check/", get().to(server_state_check))
+        .route("/compact/", get().to(server_state_compact))
+        .route("/archive/", get().to(server_state_archive))
+        .service(server_state_import_runs_scope())
 }
 
 // TODO: wouldn't a general IdParam be better?
@@ -12,14 +12,17 @@ use sqlx::{Executor, MySql, MySqlPool, Transaction};
 use super::ServerStateIdParam;
 use crate::{
     authorization::require_admin_user,
+    configuration::BillingSettings,
     database::accounting::server_state::select_server_state_from_db,
     error::{NotFoundOrUnexpectedApiError, OptionApiError},
+    routes::accounting::server_cost::rollup::refresh_server_cost_rollups,
 };
 
 #[tracing::instrument(name = "server_state_modify")]
 pub async fn server_state_modify(
     user: ReqData<User>,
     db_pool: Data<MySqlPool>,
+    billing: Data<BillingSettings>,
     data: Json<ServerStateModifyData>,
     params: Path<ServerStateIdParam>,
 ) -> Result<HttpResponse, OptionApiError> {
@@ -34,8 +37,16 @@ pub async fn server_state_modify(
         .begin()
         .await
         .context("Failed to begin transaction")?;
+    let previous_server_state =
+        select_server_state_from_db(&mut transaction, data.id as u64).await?;
     let server_state =
         update_server_state_in_db(&mut transaction, &data).await?;
+    refresh_server_cost_rollups(
+        &mut transaction,
+        &billing,
+        previous_server_state.begin.min(server_state.begin).to_utc(),
+    )
+    .await?;
     transaction
         .commit()
         .await
@@ -0,0 +1,67 @@
+use avina_wire::user::User;
+use sqlx::{MySql, Transaction};
+
+use crate::{
+    authorization::{
+        require_admin_user, require_master_user_or_return_not_found,
+        require_user_or_project_master_or_not_found,
+    },
+    database::{
+        accounting::server_state::select_user_id_by_server_from_db,
+        user::user::select_user_from_db,
+    },
+    error::OptionApiError,
+};
+
+// what the consumption or cost is calculated for
+#[derive(Debug, Clone)]
+pub(crate) enum AccountingTarget {
+    Server(String),
+    User(u64),
+    Project(u64),
+    All,
+}
+
+// Picks the target from the query parameters in the order all, project,
+// user, server and defaults to the requesting user, after checking that
+// the requesting user is allowed to see it.
+#[tracing::instrument(name = "select_accounting_target", skip(transaction))]
+pub(crate) async fn select_accounting_target(
+    transaction: &mut Transaction<'_, MySql>,
+    user: &User,
+    all: Option<bool>,
+    project: Option<u32>,
+    user_id: Option<u32>,
+    server: Option<String>,
+) -> Result<AccountingTarget, OptionApiError> {
+    if all.unwrap_or(false) {
+        require_admin_user(user)?;
+        Ok(AccountingTarget::All)
+    } else if let Some(project_id) = project {
+        require_master_user_or_return_not_found(user, project_id)?;
+        Ok(AccountingTarget::Project(project_id as u64))
+    } else if let Some(user_id) = user_id {
+        let user_queried =
+            select_user_from_db(transaction, user_id as u64).await?;
+        require_user_or_project_master_or_not_found(
+            user,
+            user_id,
+            user_queried.project,
+        )?;
+        Ok(AccountingTarget::User(user_id as u64))
+    } else if let Some(server_id) = server {
+        let server_user_id =
+            select_user_id_by_server_from_db(transaction, server_id.clone())
+                .await?;
+        let server_state_user =
+            select_user_from_db(transaction, server_user_id as u64).await?;
+        require_user_or_project_master_or_not_found(
+            user,
+            server_state_user.id,
+            server_state_user.project,
+        )?;
+        Ok(AccountingTarget::Server(server_id))
+    } else {
+        Ok(AccountingTarget::User(user.id as u64))
+    }
+}
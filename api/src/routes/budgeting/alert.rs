@@ -0,0 +1,178 @@
+use anyhow::Context;
+use avina_wire::budgeting::{BudgetAlert, BudgetAlertKind};
+use chrono::Utc;
+use sqlx::MySqlPool;
+
+use super::{
+    project_budget::over::calculate_project_budget_over_for_all_detail,
+    user_budget::over::calculate_user_budget_over_for_all_detail,
+};
+use crate::{
+    configuration::{AlertSettings, BillingSettings},
+    database::budgeting::budget_alert::{
+        NewBudgetAlert, insert_budget_alert_into_db,
+        select_budget_alert_thresholds_from_db,
+    },
+    error::UnexpectedOnlyError,
+    notification::NotificationChannel,
+};
+
+pub struct BudgetAlerter {
+    // ascending and without duplicates
+    thresholds: Vec<u32>,
+    channels: Vec<NotificationChannel>,
+}
+
+impl BudgetAlerter {
+    pub fn new(settings: &AlertSettings) -> Result<Self, anyhow::Error> {
+        let mut thresholds = settings.thresholds.clone();
+        thresholds.sort_unstable();
+        thresholds.dedup();
+        Ok(Self {
+            thresholds,
+            channels: NotificationChannel::from_settings(settings)?,
+        })
+    }
+
+    // Checks the user and project budgets of the running billing year and
+    // sends one alert per budget and channel for the highest threshold
+    // reached since the last alert via that channel. Lower thresholds passed
+    // at the same time are recorded without an alert of their own. Each
+    // delivered alert is recorded right away, and the transaction is not
+    // held open while sending.
+    #[tracing::instrument(
+        name = "evaluate_budget_alerts",
+        skip(self, db_pool, billing)
+    )]
+    pub async fn evaluate(
+        &self,
+        db_pool: &MySqlPool,
+        billing: &BillingSettings,
+    ) -> Result<Vec<BudgetAlert>, UnexpectedOnlyError> {
+        let mut alerts = vec![];
+        if self.thresholds.is_empty() || self.channels.is_empty() {
+            return Ok(alerts);
+        }
+        let now = Utc::now();
+        let year = billing.year_of(now);
+        let mut transaction = db_pool
+            .begin()
+            .await
+            .context("Failed to begin transaction")?;
+        let sent =
+            select_budget_alert_thresholds_from_db(&mut transaction).await?;
+        let users = calculate_user_budget_over_for_all_detail(
+            &mut transaction,
+            billing,
+            now,
+        )
+        .await?
+        .into_iter()
+        .map(|over| {
+            (
+                BudgetAlertKind::User,
+                over.budget_id,
+                over.user_id,
+                over.user_name,
+                over.cost,
+                over.budget,
+            )
+        });
+        let projects = calculate_project_budget_over_for_all_detail(
+            &mut transaction,
+            billing,
+            now,
+        )
+        .await?
+        .into_iter()
+        .map(|over| {
+            (
+                BudgetAlertKind::Project,
+                over.budget_id,
+                over.project_id,
+                over.project_name,
+                over.cost,
+                over.budget,
+            )
+        });
+        let budgets = users.chain(projects).collect::<Vec<_>>();
+        transaction
+            .commit()
+            .await
+            .context("Failed to commit transaction")?;
+
+        for (kind, budget_id, owner_id, owner_name, cost, budget) in budgets {
+            // every threshold of an empty budget is reached right away
+            if budget == 0 {
+                continue;
+            }
+            for channel in self.channels.iter() {
+                let is_sent = |threshold: u32| {
+                    sent.contains(&(
+                        kind.clone(),
+                        budget_id,
+                        threshold,
+                        channel.name().to_string(),
+                    ))
+                };
+                let reached = self
+                    .thresholds
+                    .iter()
+                    .copied()
+                    .filter(|threshold| {
+                        cost >= budget as f64 * *threshold as f64 / 100.0
+                            && !is_sent(*threshold)
+                    })
+                    .collect::<Vec<_>>();
+                let Some(threshold) = reached.last().copied() else {
+                    continue;
+                };
+                let alert = BudgetAlert {
+                    kind: kind.clone(),
+                    budget_id,
+                    owner_id,
+                    owner_name: owner_name.clone(),
+                    year,
+                    threshold,
+                    cost,
+                    budget,
+                    created: now.fixed_offset(),
+                };
+                // undelivered alerts are not recorded, so they are retried
+                // via this channel after the next import
+                if let Err(error) = channel.send(&alert).await {
+                    tracing::error!(
+                        "Failed to send {alert} via {}: {error:?}",
+                        channel.name()
+                    );
+                    continue;
+                }
+                let mut transaction = db_pool
+                    .begin()
+                    .await
+                    .context("Failed to begin transaction")?;
+                for threshold in reached {
+                    let new_budget_alert = NewBudgetAlert {
+                        kind: kind.clone(),
+                        budget_id,
+                        threshold,
+                        channel: channel.name().to_string(),
+                        cost,
+                        created: now,
+                    };
+                    insert_budget_alert_into_db(
+                        &mut transaction,
+                        &new_budget_alert,
+                    )
+                    .await?;
+                }
+                transaction
+                    .commit()
+                    .await
+                    .context("Failed to commit transaction")?;
+                alerts.push(alert);
+            }
+        }
+        Ok(alerts)
+    }
+}
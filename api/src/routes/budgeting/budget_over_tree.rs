@@ -0,0 +1,366 @@
+use std::collections::HashMap;
+
+use actix_web::{
+    HttpResponse,
+    web::{Data, Query, ReqData},
+};
+use anyhow::{Context, anyhow};
+use avina_wire::{
+    budgeting::{
+        BudgetOverTree, BudgetOverTreeParams, BudgetOverTreeProject,
+        BudgetOverTreeServer, BudgetOverTreeUser, ProjectBudget, UserBudget,
+    },
+    user::User,
+};
+use chrono::{DateTime, Utc};
+use sqlx::{MySql, MySqlPool, Transaction};
+
+use crate::{
+    authorization::{
+        require_admin_user, require_master_user_or_return_not_found,
+        require_user_or_project_master_or_not_found,
+    },
+    configuration::BillingSettings,
+    database::{
+        budgeting::{
+            project_budget::{
+                select_maybe_project_budget_by_project_and_year_from_db,
+                select_project_budgets_by_year_from_db,
+            },
+            user_budget::{
+                select_maybe_user_budget_by_user_and_year_from_db,
+                select_user_budgets_by_project_and_year_from_db,
+            },
+        },
+        user::user::select_user_from_db,
+    },
+    error::{OptionApiError, UnexpectedOnlyError},
+    routes::accounting::server_cost::get::{
+        ServerCostForProject, ServerCostForUser,
+        calculate_server_cost_for_project, calculate_server_cost_for_user,
+    },
+};
+
+fn build_budget_over_tree_user(
+    user_budget: &UserBudget,
+    project_over: bool,
+    cost: f64,
+    flavors: HashMap<String, f64>,
+    servers: HashMap<String, BudgetOverTreeServer>,
+) -> BudgetOverTreeUser {
+    BudgetOverTreeUser {
+        cost,
+        budget_id: user_budget.id,
+        budget: user_budget.amount as u64,
+        over: project_over || cost >= user_budget.amount as f64,
+        servers,
+        flavors,
+    }
+}
+
+async fn calculate_budget_over_tree_project(
+    transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
+    project_budget: &ProjectBudget,
+    end: DateTime<Utc>,
+    with_flavors: bool,
+) -> Result<BudgetOverTreeProject, UnexpectedOnlyError> {
+    let begin = billing.start_of_the_year(project_budget.year);
+    let ServerCostForProject::Detail(mut cost) =
+        calculate_server_cost_for_project(
+            transaction,
+            billing,
+            project_budget.project as u64,
+            begin,
+            end,
+            Some(true),
+        )
+        .await?
+    else {
+        return Err(anyhow!("Unexpected ServerCostForProject variant.").into());
+    };
+    let over = cost.total >= project_budget.amount as f64;
+    let mut users = HashMap::new();
+    for user_budget in select_user_budgets_by_project_and_year_from_db(
+        transaction,
+        project_budget.project as u64,
+        project_budget.year,
+    )
+    .await?
+    {
+        let (user_cost, flavors, servers) =
+            match cost.users.remove(&user_budget.username) {
+                Some(user_cost) => (
+                    user_cost.total,
+                    user_cost.flavors,
+                    user_cost
+                        .servers
+                        .into_iter()
+                        .map(|(uuid, s)| {
+                            (
+                                uuid,
+                                BudgetOverTreeServer {
+                                    total: s.total,
+                                    flavors: s.flavors,
+                                },
+                            )
+                        })
+                        .collect(),
+                ),
+                None => (0.0, HashMap::new(), HashMap::new()),
+            };
+        users.insert(
+            user_budget.username.clone(),
+            build_budget_over_tree_user(
+                &user_budget,
+                over,
+                user_cost,
+                flavors,
+                servers,
+            ),
+        );
+    }
+    Ok(BudgetOverTreeProject {
+        cost: cost.total,
+        budget_id: project_budget.id,
+        budget: project_budget.amount as u64,
+        over,
+        users,
+        flavors: with_flavors.then_some(cost.flavors),
+    })
+}
+
+pub async fn calculate_budget_over_tree_for_all(
+    transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
+    end: DateTime<Utc>,
+) -> Result<BudgetOverTree, UnexpectedOnlyError> {
+    let mut tree = BudgetOverTree {
+        cost: Some(0.0),
+        projects: HashMap::new(),
+        flavors: Some(HashMap::new()),
+    };
+    for project_budget in select_project_budgets_by_year_from_db(
+        transaction,
+        billing.year_of(end),
+    )
+    .await?
+    {
+        let project = calculate_budget_over_tree_project(
+            transaction,
+            billing,
+            &project_budget,
+            end,
+            true,
+        )
+        .await?;
+        if let Some(cost) = tree.cost.as_mut() {
+            *cost += project.cost;
+        }
+        if let (Some(flavors), Some(project_flavors)) =
+            (tree.flavors.as_mut(), project.flavors.as_ref())
+        {
+            for (flavor_name, flavor_cost) in project_flavors {
+                *flavors.entry(flavor_name.clone()).or_default() += flavor_cost;
+            }
+        }
+        tree.projects
+            .insert(project_budget.project_name.clone(), project);
+    }
+    Ok(tree)
+}
+
+pub async fn calculate_budget_over_tree_for_project(
+    transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
+    project_id: u64,
+    end: DateTime<Utc>,
+) -> Result<BudgetOverTree, UnexpectedOnlyError> {
+    let mut tree = BudgetOverTree {
+        cost: None,
+        projects: HashMap::new(),
+        flavors: None,
+    };
+    let Some(project_budget) =
+        select_maybe_project_budget_by_project_and_year_from_db(
+            transaction,
+            project_id,
+            billing.year_of(end),
+        )
+        .await?
+    else {
+        return Ok(tree);
+    };
+    let project = calculate_budget_over_tree_project(
+        transaction,
+        billing,
+        &project_budget,
+        end,
+        true,
+    )
+    .await?;
+    tree.projects.insert(project_budget.project_name, project);
+    Ok(tree)
+}
+
+pub async fn calculate_budget_over_tree_for_user(
+    transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
+    user_id: u64,
+    end: DateTime<Utc>,
+) -> Result<BudgetOverTree, UnexpectedOnlyError> {
+    let mut tree = BudgetOverTree {
+        cost: None,
+        projects: HashMap::new(),
+        flavors: None,
+    };
+    let year = billing.year_of(end);
+    let begin = billing.start_of_the_year(year);
+    let user = select_user_from_db(transaction, user_id)
+        .await
+        .context("Failed to select user")?;
+    let Some(project_budget) =
+        select_maybe_project_budget_by_project_and_year_from_db(
+            transaction,
+            user.project as u64,
+            year,
+        )
+        .await?
+    else {
+        return Ok(tree);
+    };
+    let ServerCostForProject::Normal(project_cost) =
+        calculate_server_cost_for_project(
+            transaction,
+            billing,
+            user.project as u64,
+            begin,
+            end,
+            None,
+        )
+        .await?
+    else {
+        return Err(anyhow!("Unexpected ServerCostForProject variant.").into());
+    };
+    let project_over = project_cost.total >= project_budget.amount as f64;
+    let mut users = HashMap::new();
+    if let Some(user_budget) =
+        select_maybe_user_budget_by_user_and_year_from_db(
+            transaction,
+            user_id,
+            year,
+        )
+        .await?
+    {
+        let ServerCostForUser::Detail(user_cost) =
+            calculate_server_cost_for_user(
+                transaction,
+                billing,
+                user_id,
+                begin,
+                end,
+                Some(true),
+            )
+            .await?
+        else {
+            return Err(anyhow!("Unexpected ServerCostForUser variant.").into());
+        };
+        let servers = user_cost
+            .servers
+            .into_iter()
+            .map(|(uuid, s)| {
+                (
+                    uuid,
+                    BudgetOverTreeServer {
+                        total: s.total,
+                        flavors: s.flavors,
+                    },
+                )
+            })
+            .collect();
+        users.insert(
+            user_budget.username.clone(),
+            build_budget_over_tree_user(
+                &user_budget,
+                project_over,
+                user_cost.total,
+                user_cost.flavors,
+                servers,
+            ),
+        );
+    }
+    tree.projects.insert(
+        project_budget.project_name,
+        BudgetOverTreeProject {
+            cost: project_cost.total,
+            budget_id: project_budget.id,
+            budget: project_budget.amount as u64,
+            over: project_over,
+            users,
+            flavors: None,
+        },
+    );
+    Ok(tree)
+}
+
+#[tracing::instrument(name = "budget_over_tree")]
+pub async fn budget_over_tree(
+    user: ReqData<User>,
+    db_pool: Data<MySqlPool>,
+    billing: Data<BillingSettings>,
+    params: Query<BudgetOverTreeParams>,
+) -> Result<HttpResponse, OptionApiError> {
+    let end = params.end.unwrap_or(Utc::now().fixed_offset());
+    let mut transaction = db_pool
+        .begin()
+        .await
+        .context("Failed to begin transaction")?;
+    let tree = if params.all.unwrap_or(false) {
+        require_admin_user(&user)?;
+        calculate_budget_over_tree_for_all(
+            &mut transaction,
+            &billing,
+            end.into(),
+        )
+        .await?
+    } else if let Some(project_id) = params.project {
+        require_master_user_or_return_not_found(&user, project_id)?;
+        calculate_budget_over_tree_for_project(
+            &mut transaction,
+            &billing,
+            project_id as u64,
+            end.into(),
+        )
+        .await?
+    } else if let Some(user_id) = params.user {
+        let user_queried =
+            select_user_from_db(&mut transaction, user_id as u64).await?;
+        require_user_or_project_master_or_not_found(
+            &user,
+            user_id,
+            user_queried.project,
+        )?;
+        calculate_budget_over_tree_for_user(
+            &mut transaction,
+            &billing,
+            user_id as u64,
+            end.into(),
+        )
+        .await?
+    } else {
+        calculate_budget_over_tree_for_user(
+            &mut transaction,
+            &billing,
+            user.id as u64,
+            end.into(),
+        )
+        .await?
+    };
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit transaction")?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(tree))
+}
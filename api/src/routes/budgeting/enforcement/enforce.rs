@@ -0,0 +1,272 @@
+use std::collections::HashSet;
+
+use actix_web::{
+    HttpResponse,
+    web::{Data, Json, ReqData},
+};
+use anyhow::Context;
+use avina_wire::{
+    budgeting::{
+        BudgetEnforcementAction, BudgetEnforcementData, EnforcementAction,
+    },
+    user::User,
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use sqlx::MySqlPool;
+
+use crate::{
+    authorization::require_admin_user,
+    configuration::{BillingSettings, EnforcementSettings},
+    database::{
+        accounting::server_state::select_unfinished_server_states_by_user_from_db,
+        budgeting::{
+            budget_enforcement::{
+                NewEnforcementAction, delete_user_budget_over_from_db,
+                insert_enforcement_action_into_db,
+                insert_user_budget_over_into_db,
+                select_enforcement_actions_by_user_since_from_db,
+                select_user_budget_overs_from_db,
+            },
+            user_budget::select_user_budgets_by_year_from_db,
+        },
+    },
+    error::{OptionApiError, UnexpectedOnlyError},
+    openstack::OpenStack,
+    routes::budgeting::user_budget::over::calculate_user_budget_over_for_budget_combined_detail,
+    scheduler::ImportScheduler,
+};
+
+#[tracing::instrument(
+    name = "budget_enforcement_run",
+    skip(openstack, import_scheduler)
+)]
+pub async fn budget_enforcement_run(
+    user: ReqData<User>,
+    db_pool: Data<MySqlPool>,
+    openstack: Data<OpenStack>,
+    import_scheduler: Data<ImportScheduler>,
+    data: Json<BudgetEnforcementData>,
+) -> Result<HttpResponse, OptionApiError> {
+    require_admin_user(&user)?;
+    let actions = import_scheduler
+        .run_budget_enforcement(&db_pool, &openstack, data.dry_run)
+        .await?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(actions))
+}
+
+pub struct BudgetEnforcer {
+    settings: EnforcementSettings,
+}
+
+impl BudgetEnforcer {
+    pub fn new(settings: EnforcementSettings) -> Self {
+        Self { settings }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.settings.enabled
+    }
+
+    pub fn is_dry_run(&self, dry_run: Option<bool>) -> bool {
+        dry_run.unwrap_or(self.settings.dry_run)
+    }
+
+    // servers in other states are left alone, e.g. stopping a server that
+    // is already shut off would fail
+    fn applies_to(&self, status: &str) -> bool {
+        match self.settings.action {
+            EnforcementAction::Lock => true,
+            EnforcementAction::Shelve => {
+                ["ACTIVE", "SHUTOFF", "PAUSED", "SUSPENDED"].contains(&status)
+            }
+            EnforcementAction::Stop => status == "ACTIVE",
+        }
+    }
+
+    async fn act(
+        &self,
+        openstack: &OpenStack,
+        server_id: &str,
+    ) -> Result<(), anyhow::Error> {
+        match self.settings.action {
+            EnforcementAction::Lock => openstack.lock_server(server_id).await,
+            EnforcementAction::Shelve => {
+                openstack.shelve_server(server_id).await
+            }
+            EnforcementAction::Stop => openstack.stop_server(server_id).await,
+        }
+    }
+
+    // Takes the configured action on the running servers of each user whose
+    // combined budget check of the running billing year has been over for
+    // at least the grace period. Budgets with an amount of 0 count as no
+    // budget, as budgets are created with that amount by default. Users and
+    // projects on the allow list are skipped, and so are servers that the
+    // action was already taken on since the user went over. Failed actions
+    // are recorded with their error and retried in the next run. A dry run
+    // only reports the actions, but still keeps track of since when users
+    // are over.
+    #[tracing::instrument(
+        name = "enforce_budgets",
+        skip(self, db_pool, openstack, billing)
+    )]
+    pub async fn enforce(
+        &self,
+        db_pool: &MySqlPool,
+        openstack: &OpenStack,
+        billing: &BillingSettings,
+        dry_run: bool,
+    ) -> Result<Vec<BudgetEnforcementAction>, UnexpectedOnlyError> {
+        let now = Utc::now();
+        let mut actions = self.plan(db_pool, billing, now, dry_run).await?;
+        if dry_run {
+            return Ok(actions);
+        }
+        // the transaction is not held open during the calls to nova, and
+        // each action is recorded right after it was taken, so that later
+        // failures do not lose the record of actions already taken
+        for action in actions.iter_mut() {
+            action.error = self
+                .act(openstack, &action.server_id)
+                .await
+                .err()
+                .map(|error| error.to_string());
+            let mut transaction = db_pool
+                .begin()
+                .await
+                .context("Failed to begin transaction")?;
+            let new_enforcement_action = NewEnforcementAction {
+                user_id: action.user_id,
+                instance_id: action.server_id.clone(),
+                instance_name: action.server_name.clone(),
+                action: action.action.clone(),
+                error: action.error.clone(),
+                created: now,
+            };
+            insert_enforcement_action_into_db(
+                &mut transaction,
+                &new_enforcement_action,
+            )
+            .await?;
+            transaction
+                .commit()
+                .await
+                .context("Failed to commit transaction")?;
+        }
+        Ok(actions)
+    }
+
+    // Updates since when users are over and returns the actions to take,
+    // without taking them.
+    async fn plan(
+        &self,
+        db_pool: &MySqlPool,
+        billing: &BillingSettings,
+        now: DateTime<Utc>,
+        dry_run: bool,
+    ) -> Result<Vec<BudgetEnforcementAction>, UnexpectedOnlyError> {
+        let mut actions = vec![];
+        let grace_period =
+            TimeDelta::hours(self.settings.grace_period_hours as i64);
+        let mut transaction = db_pool
+            .begin()
+            .await
+            .context("Failed to begin transaction")?;
+        let overs_since =
+            select_user_budget_overs_from_db(&mut transaction).await?;
+        let budgets = select_user_budgets_by_year_from_db(
+            &mut transaction,
+            billing.year_of(now),
+        )
+        .await?;
+        for budget in budgets {
+            let Some(over) =
+                calculate_user_budget_over_for_budget_combined_detail(
+                    &mut transaction,
+                    billing,
+                    budget.id as u64,
+                    now,
+                )
+                .await?
+                .pop()
+            else {
+                continue;
+            };
+            let since = overs_since.get(&budget.id).copied();
+            let user_over = over.user_budget > 0
+                && over.user_cost >= over.user_budget as f64;
+            let project_over = over.project_budget.is_some_and(|budget| {
+                budget > 0 && over.project_cost >= budget as f64
+            });
+            if !user_over && !project_over {
+                if since.is_some() {
+                    delete_user_budget_over_from_db(
+                        &mut transaction,
+                        budget.id,
+                    )
+                    .await?;
+                }
+                continue;
+            }
+            let since = match since {
+                Some(since) => since,
+                None => {
+                    insert_user_budget_over_into_db(
+                        &mut transaction,
+                        budget.id,
+                        now,
+                    )
+                    .await?;
+                    now
+                }
+            };
+            if now - since < grace_period
+                || self.settings.allow_list.contains(&over.user_name)
+                || self.settings.allow_list.contains(&over.project_name)
+            {
+                continue;
+            }
+            let done = select_enforcement_actions_by_user_since_from_db(
+                &mut transaction,
+                over.user_id,
+                since,
+            )
+            .await?
+            .into_iter()
+            .filter(|action| {
+                action.action == self.settings.action && action.error.is_none()
+            })
+            .map(|action| action.server_id)
+            .collect::<HashSet<_>>();
+            let states = select_unfinished_server_states_by_user_from_db(
+                &mut transaction,
+                over.user_id as u64,
+            )
+            .await?;
+            for state in states {
+                if done.contains(&state.instance_id)
+                    || !self.applies_to(&state.status)
+                {
+                    continue;
+                }
+                actions.push(BudgetEnforcementAction {
+                    user_id: over.user_id,
+                    user_name: over.user_name.clone(),
+                    server_id: state.instance_id,
+                    server_name: state.instance_name,
+                    action: self.settings.action.clone(),
+                    dry_run,
+                    error: None,
+                    created: now.fixed_offset(),
+                });
+            }
+        }
+        transaction
+            .commit()
+            .await
+            .context("Failed to commit transaction")?;
+        Ok(actions)
+    }
+}
@@ -0,0 +1,33 @@
+use actix_web::{
+    HttpResponse,
+    web::{Data, ReqData},
+};
+use anyhow::Context;
+use avina_wire::user::User;
+use sqlx::MySqlPool;
+
+use crate::{
+    authorization::require_admin_user,
+    database::budgeting::budget_enforcement::select_enforcement_actions_from_db,
+    error::OptionApiError,
+};
+
+#[tracing::instrument(name = "budget_enforcement_list")]
+pub async fn budget_enforcement_list(
+    user: ReqData<User>,
+    db_pool: Data<MySqlPool>,
+) -> Result<HttpResponse, OptionApiError> {
+    require_admin_user(&user)?;
+    let mut transaction = db_pool
+        .begin()
+        .await
+        .context("Failed to begin transaction")?;
+    let actions = select_enforcement_actions_from_db(&mut transaction).await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit transaction")?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(actions))
+}
@@ -0,0 +1,15 @@
+use actix_web::{
+    Scope,
+    web::{get, post, scope},
+};
+
+pub(crate) mod enforce;
+use enforce::budget_enforcement_run;
+mod list;
+use list::budget_enforcement_list;
+
+pub fn budget_enforcement_scope() -> Scope {
+    scope("/budgetenforcement")
+        .route("/", get().to(budget_enforcement_list))
+        .route("/", post().to(budget_enforcement_run))
+}
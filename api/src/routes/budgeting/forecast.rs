@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use sqlx::{MySql, Transaction};
+
+use crate::{
+    configuration::BillingSettings,
+    database::accounting::server_state::select_ordered_server_states_by_begin_and_end_from_db,
+    error::UnexpectedOnlyError,
+    routes::accounting::{
+        server_cost::rate::price_running_server_states,
+        target::AccountingTarget,
+    },
+};
+
+pub(crate) struct BudgetForecast {
+    // cost per day
+    pub rate: f64,
+    pub projected_cost: f64,
+    pub exhausted_at: Option<DateTime<Utc>>,
+}
+
+// Cost per second of the running servers summed up per user and project.
+#[derive(Default)]
+struct RunningServerCostRates {
+    users: HashMap<u32, f64>,
+    projects: HashMap<u32, f64>,
+}
+
+// Cost per second of all servers running at a point in time, each priced
+// with the flavor price of its owner's user class at that time.
+#[tracing::instrument(
+    name = "calculate_running_server_cost_rates",
+    skip(transaction)
+)]
+async fn calculate_running_server_cost_rates(
+    transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
+    at: DateTime<Utc>,
+) -> Result<RunningServerCostRates, UnexpectedOnlyError> {
+    let states = select_ordered_server_states_by_begin_and_end_from_db(
+        transaction,
+        Some(at),
+        Some(at),
+    )
+    .await?;
+    let costs = price_running_server_states(
+        transaction,
+        billing,
+        &AccountingTarget::All,
+        states,
+        at,
+    )
+    .await?;
+    let mut rates = RunningServerCostRates::default();
+    for cost in costs {
+        *rates.users.entry(cost.state.user).or_default() +=
+            cost.cost_per_second;
+        *rates.projects.entry(cost.project_id).or_default() +=
+            cost.cost_per_second;
+    }
+    Ok(rates)
+}
+
+// Extrapolates the cost of the servers running at end to the end of its
+// billing year, assuming they keep running at the current prices. The
+// running servers and their prices are loaded once for all budgets.
+pub(crate) struct BudgetForecaster {
+    rates: RunningServerCostRates,
+    end: DateTime<Utc>,
+    end_of_the_year: DateTime<Utc>,
+}
+
+impl BudgetForecaster {
+    pub(crate) async fn new(
+        transaction: &mut Transaction<'_, MySql>,
+        billing: &BillingSettings,
+        end: DateTime<Utc>,
+    ) -> Result<Self, UnexpectedOnlyError> {
+        Ok(Self {
+            rates: calculate_running_server_cost_rates(
+                transaction,
+                billing,
+                end,
+            )
+            .await?,
+            end,
+            end_of_the_year: billing
+                .start_of_the_year(billing.year_of(end) + 1),
+        })
+    }
+
+    pub(crate) fn forecast_user(
+        &self,
+        user_id: u32,
+        cost: f64,
+        budget: u32,
+    ) -> BudgetForecast {
+        let cost_per_second =
+            self.rates.users.get(&user_id).copied().unwrap_or(0.0);
+        self.forecast(cost_per_second, cost, budget)
+    }
+
+    pub(crate) fn forecast_project(
+        &self,
+        project_id: u32,
+        cost: f64,
+        budget: u32,
+    ) -> BudgetForecast {
+        let cost_per_second =
+            self.rates.projects.get(&project_id).copied().unwrap_or(0.0);
+        self.forecast(cost_per_second, cost, budget)
+    }
+
+    fn forecast(
+        &self,
+        cost_per_second: f64,
+        cost: f64,
+        budget: u32,
+    ) -> BudgetForecast {
+        let remaining_seconds =
+            (self.end_of_the_year - self.end).num_seconds() as f64;
+        let projected_cost = cost + cost_per_second * remaining_seconds;
+        let budget = budget as f64;
+        let exhausted_at = if cost >= budget || cost_per_second <= 0.0 {
+            None
+        } else {
+            // compared before converting, since tiny rates or large budgets
+            // overflow the time delta
+            let seconds = ((budget - cost) / cost_per_second).ceil();
+            (seconds <= remaining_seconds)
+                .then(|| self.end + TimeDelta::seconds(seconds as i64))
+        };
+        BudgetForecast {
+            rate: cost_per_second * 24.0 * 60.0 * 60.0,
+            projected_cost,
+            exhausted_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn forecast_beyond_the_end_of_the_year_does_not_overflow() {
+        let billing = BillingSettings::default();
+        let end = Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap();
+        let forecaster = BudgetForecaster {
+            rates: RunningServerCostRates::default(),
+            end,
+            end_of_the_year: billing.start_of_the_year(2025),
+        };
+        assert!(
+            forecaster
+                .forecast(1e-300, 0.0, u32::MAX)
+                .exhausted_at
+                .is_none()
+        );
+        assert_eq!(
+            forecaster.forecast(1.0, 0.0, 60).exhausted_at,
+            Some(end + TimeDelta::seconds(60))
+        );
+    }
+}
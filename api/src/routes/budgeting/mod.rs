@@ -1,18 +1,26 @@
 use actix_web::{
     Scope,
-    web::{post, scope},
+    web::{get, post, scope},
 };
 
+pub(crate) mod alert;
 mod project_budget;
 use project_budget::project_budgets_scope;
 mod user_budget;
 use user_budget::user_budgets_scope;
 mod bulk_create;
 use bulk_create::budget_bulk_create;
+mod budget_over_tree;
+use budget_over_tree::budget_over_tree;
+pub(crate) mod enforcement;
+use enforcement::budget_enforcement_scope;
+mod forecast;
 
 pub fn budgeting_scope() -> Scope {
     scope("/budgeting")
         .service(project_budgets_scope())
         .service(user_budgets_scope())
+        .service(budget_enforcement_scope())
         .route("/budgetbulkcreate/", post().to(budget_bulk_create))
+        .route("/budgetovertree/", get().to(budget_over_tree))
 }
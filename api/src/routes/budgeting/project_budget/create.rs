@@ -7,10 +7,12 @@ use avina_wire::{
     budgeting::{ProjectBudget, ProjectBudgetCreateData},
     user::User,
 };
+use chrono::Utc;
 use sqlx::MySqlPool;
 
 use crate::{
     authorization::require_admin_user,
+    configuration::BillingSettings,
     database::{
         budgeting::project_budget::{
             NewProjectBudget, insert_project_budget_into_db,
@@ -24,9 +26,13 @@ use crate::{
 pub async fn project_budget_create(
     user: ReqData<User>,
     db_pool: Data<MySqlPool>,
+    billing: Data<BillingSettings>,
     data: Json<ProjectBudgetCreateData>,
 ) -> Result<HttpResponse, OptionApiError> {
     require_admin_user(&user)?;
+    let mut data = data.into_inner();
+    // budgets without a year are for the running billing year
+    data.year.get_or_insert_with(|| billing.year_of(Utc::now()));
     let new_project_budget: NewProjectBudget = data
         .clone()
         .try_into()
@@ -14,7 +14,7 @@ mod modify;
 use modify::project_budget_modify;
 mod delete;
 use delete::project_budget_delete;
-mod over;
+pub(crate) mod over;
 use over::project_budget_over;
 
 pub fn project_budgets_scope() -> Scope {
@@ -5,12 +5,12 @@ use actix_web::{
 use anyhow::{Context, anyhow};
 use avina_wire::{
     budgeting::{
-        ProjectBudgetOverDetail, ProjectBudgetOverParams,
-        ProjectBudgetOverSimple,
+        ProjectBudgetOverDetail, ProjectBudgetOverForecast,
+        ProjectBudgetOverParams, ProjectBudgetOverSimple,
     },
     user::User,
 };
-use chrono::{DateTime, Datelike, Utc};
+use chrono::{DateTime, Utc};
 use serde::Serialize;
 use sqlx::{MySql, MySqlPool, Transaction};
 
@@ -18,16 +18,19 @@ use crate::{
     authorization::{
         require_admin_user, require_project_user_or_return_not_found,
     },
+    configuration::BillingSettings,
     database::budgeting::project_budget::{
         select_maybe_project_budget_by_project_and_year_from_db,
         select_maybe_project_budget_from_db, select_project_budget_from_db,
         select_project_budgets_by_year_from_db,
     },
     error::{OptionApiError, UnexpectedOnlyError},
-    routes::accounting::server_cost::get::{
-        ServerCostForProject, calculate_server_cost_for_project,
+    routes::{
+        accounting::server_cost::get::{
+            ServerCostForProject, calculate_server_cost_for_project,
+        },
+        budgeting::forecast::BudgetForecaster,
     },
-    utils::start_of_the_year,
 };
 
 #[derive(Serialize)]
@@ -35,10 +38,12 @@ use crate::{
 pub enum ProjectBudgetOver {
     Normal(Vec<ProjectBudgetOverSimple>),
     Detail(Vec<ProjectBudgetOverDetail>),
+    Forecast(Vec<ProjectBudgetOverForecast>),
 }
 
 pub async fn calculate_project_budget_over_for_budget_normal(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     // TODO: should be a u32
     budget_id: u64,
     end: DateTime<Utc>,
@@ -50,12 +55,13 @@ pub async fn calculate_project_budget_over_for_budget_normal(
         return Ok(overs);
     };
     let year = budget.year;
-    if year != end.year() as u32 {
+    if year != billing.year_of(end) {
         return Ok(overs);
     }
-    let begin = start_of_the_year(year);
+    let begin = billing.start_of_the_year(year);
     let ServerCostForProject::Normal(cost) = calculate_server_cost_for_project(
         transaction,
+        billing,
         budget.project as u64,
         begin,
         end,
@@ -77,6 +83,7 @@ pub async fn calculate_project_budget_over_for_budget_normal(
 
 pub async fn calculate_project_budget_over_for_budget_detail(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     budget_id: u64,
     end: DateTime<Utc>,
 ) -> Result<Vec<ProjectBudgetOverDetail>, UnexpectedOnlyError> {
@@ -87,12 +94,13 @@ pub async fn calculate_project_budget_over_for_budget_detail(
         return Ok(overs);
     };
     let year = budget.year;
-    if year != end.year() as u32 {
+    if year != billing.year_of(end) {
         return Ok(overs);
     }
-    let begin = start_of_the_year(year);
+    let begin = billing.start_of_the_year(year);
     let ServerCostForProject::Normal(cost) = calculate_server_cost_for_project(
         transaction,
+        billing,
         budget.project as u64,
         begin,
         end,
@@ -116,6 +124,7 @@ pub async fn calculate_project_budget_over_for_budget_detail(
 
 pub async fn calculate_project_budget_over_for_budget(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     budget_id: u64,
     end: DateTime<Utc>,
     detail: Option<bool>,
@@ -124,6 +133,7 @@ pub async fn calculate_project_budget_over_for_budget(
         Some(true) => ProjectBudgetOver::Detail(
             calculate_project_budget_over_for_budget_detail(
                 transaction,
+                billing,
                 budget_id,
                 end,
             )
@@ -132,6 +142,7 @@ pub async fn calculate_project_budget_over_for_budget(
         _ => ProjectBudgetOver::Normal(
             calculate_project_budget_over_for_budget_normal(
                 transaction,
+                billing,
                 budget_id,
                 end,
             )
@@ -142,11 +153,12 @@ pub async fn calculate_project_budget_over_for_budget(
 
 pub async fn calculate_project_budget_over_for_project_normal(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     project_id: u64,
     end: DateTime<Utc>,
 ) -> Result<Vec<ProjectBudgetOverSimple>, UnexpectedOnlyError> {
     let mut overs = vec![];
-    let year = end.year() as u32;
+    let year = billing.year_of(end);
     let Some(budget) = select_maybe_project_budget_by_project_and_year_from_db(
         transaction,
         project_id,
@@ -156,9 +168,10 @@ pub async fn calculate_project_budget_over_for_project_normal(
     else {
         return Ok(overs);
     };
-    let begin = start_of_the_year(year);
+    let begin = billing.start_of_the_year(year);
     let ServerCostForProject::Normal(cost) = calculate_server_cost_for_project(
         transaction,
+        billing,
         budget.project as u64,
         begin,
         end,
@@ -180,11 +193,12 @@ pub async fn calculate_project_budget_over_for_project_normal(
 
 pub async fn calculate_project_budget_over_for_project_detail(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     project_id: u64,
     end: DateTime<Utc>,
 ) -> Result<Vec<ProjectBudgetOverDetail>, UnexpectedOnlyError> {
     let mut overs = vec![];
-    let year = end.year() as u32;
+    let year = billing.year_of(end);
     let Some(budget) = select_maybe_project_budget_by_project_and_year_from_db(
         transaction,
         project_id,
@@ -194,9 +208,10 @@ pub async fn calculate_project_budget_over_for_project_detail(
     else {
         return Ok(overs);
     };
-    let begin = start_of_the_year(year);
+    let begin = billing.start_of_the_year(year);
     let ServerCostForProject::Normal(cost) = calculate_server_cost_for_project(
         transaction,
+        billing,
         budget.project as u64,
         begin,
         end,
@@ -220,6 +235,7 @@ pub async fn calculate_project_budget_over_for_project_detail(
 
 pub async fn calculate_project_budget_over_for_project(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     project_id: u64,
     end: DateTime<Utc>,
     detail: Option<bool>,
@@ -228,6 +244,7 @@ pub async fn calculate_project_budget_over_for_project(
         Some(true) => ProjectBudgetOver::Detail(
             calculate_project_budget_over_for_project_detail(
                 transaction,
+                billing,
                 project_id,
                 end,
             )
@@ -236,6 +253,7 @@ pub async fn calculate_project_budget_over_for_project(
         _ => ProjectBudgetOver::Normal(
             calculate_project_budget_over_for_project_normal(
                 transaction,
+                billing,
                 project_id,
                 end,
             )
@@ -246,17 +264,19 @@ pub async fn calculate_project_budget_over_for_project(
 
 pub async fn calculate_project_budget_over_for_all_normal(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     end: DateTime<Utc>,
 ) -> Result<Vec<ProjectBudgetOverSimple>, UnexpectedOnlyError> {
     let mut overs = vec![];
-    let year = end.year() as u32;
+    let year = billing.year_of(end);
     let budgets =
         select_project_budgets_by_year_from_db(transaction, year).await?;
-    let begin = start_of_the_year(year);
+    let begin = billing.start_of_the_year(year);
     for budget in budgets {
         let ServerCostForProject::Normal(cost) =
             calculate_server_cost_for_project(
                 transaction,
+                billing,
                 budget.project as u64,
                 begin,
                 end,
@@ -281,17 +301,19 @@ pub async fn calculate_project_budget_over_for_all_normal(
 
 pub async fn calculate_project_budget_over_for_all_detail(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     end: DateTime<Utc>,
 ) -> Result<Vec<ProjectBudgetOverDetail>, UnexpectedOnlyError> {
     let mut overs = vec![];
-    let year = end.year() as u32;
+    let year = billing.year_of(end);
     let budgets =
         select_project_budgets_by_year_from_db(transaction, year).await?;
-    let begin = start_of_the_year(year);
+    let begin = billing.start_of_the_year(year);
     for budget in budgets {
         let ServerCostForProject::Normal(cost) =
             calculate_server_cost_for_project(
                 transaction,
+                billing,
                 budget.project as u64,
                 begin,
                 end,
@@ -318,25 +340,70 @@ pub async fn calculate_project_budget_over_for_all_detail(
 
 pub async fn calculate_project_budget_over_for_all(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     end: DateTime<Utc>,
     detail: Option<bool>,
 ) -> Result<ProjectBudgetOver, UnexpectedOnlyError> {
     Ok(match detail {
         Some(true) => ProjectBudgetOver::Detail(
-            calculate_project_budget_over_for_all_detail(transaction, end)
-                .await?,
+            calculate_project_budget_over_for_all_detail(
+                transaction,
+                billing,
+                end,
+            )
+            .await?,
         ),
         _ => ProjectBudgetOver::Normal(
-            calculate_project_budget_over_for_all_normal(transaction, end)
-                .await?,
+            calculate_project_budget_over_for_all_normal(
+                transaction,
+                billing,
+                end,
+            )
+            .await?,
         ),
     })
 }
 
+// turns the detailed budget overs into forecasts, other variants are kept
+pub async fn forecast_project_budget_over(
+    transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
+    over: ProjectBudgetOver,
+    end: DateTime<Utc>,
+) -> Result<ProjectBudgetOver, UnexpectedOnlyError> {
+    let ProjectBudgetOver::Detail(details) = over else {
+        return Ok(over);
+    };
+    let forecaster = BudgetForecaster::new(transaction, billing, end).await?;
+    let mut forecasts = vec![];
+    for detail in details {
+        let forecast = forecaster.forecast_project(
+            detail.project_id,
+            detail.cost,
+            detail.budget,
+        );
+        forecasts.push(ProjectBudgetOverForecast {
+            budget_id: detail.budget_id,
+            project_id: detail.project_id,
+            project_name: detail.project_name,
+            over: detail.over,
+            cost: detail.cost,
+            budget: detail.budget,
+            rate: forecast.rate,
+            projected_cost: forecast.projected_cost,
+            exhausted_at: forecast
+                .exhausted_at
+                .map(|exhausted_at| exhausted_at.fixed_offset()),
+        });
+    }
+    Ok(ProjectBudgetOver::Forecast(forecasts))
+}
+
 #[tracing::instrument(name = "project_budget_over")]
 pub async fn project_budget_over(
     user: ReqData<User>,
     db_pool: Data<MySqlPool>,
+    billing: Data<BillingSettings>,
     params: Query<ProjectBudgetOverParams>,
     // TODO: is the ValidationError variant ever used?
 ) -> Result<HttpResponse, OptionApiError> {
@@ -345,21 +412,25 @@ pub async fn project_budget_over(
         .begin()
         .await
         .context("Failed to begin transaction")?;
-    let over = if params.all.unwrap_or(false) {
+    let forecast = params.forecast.unwrap_or(false);
+    let detail = if forecast { Some(true) } else { params.detail };
+    let mut over = if params.all.unwrap_or(false) {
         require_admin_user(&user)?;
         calculate_project_budget_over_for_all(
             &mut transaction,
+            &billing,
             end.into(),
-            params.detail,
+            detail,
         )
         .await?
     } else if let Some(project_id) = params.project {
         require_project_user_or_return_not_found(&user, project_id)?;
         calculate_project_budget_over_for_project(
             &mut transaction,
+            &billing,
             project_id as u64,
             end.into(),
-            params.detail,
+            detail,
         )
         .await?
     } else if let Some(budget_id) = params.budget {
@@ -372,20 +443,31 @@ pub async fn project_budget_over(
         )?;
         calculate_project_budget_over_for_budget(
             &mut transaction,
+            &billing,
             budget_id as u64,
             end.into(),
-            params.detail,
+            detail,
         )
         .await?
     } else {
         calculate_project_budget_over_for_project(
             &mut transaction,
+            &billing,
             user.project as u64,
             end.into(),
-            params.detail,
+            detail,
         )
         .await?
     };
+    if forecast {
+        over = forecast_project_budget_over(
+            &mut transaction,
+            &billing,
+            over,
+            end.into(),
+        )
+        .await?;
+    }
     transaction
         .commit()
         .await
@@ -7,10 +7,12 @@ use avina_wire::{
     budgeting::{UserBudget, UserBudgetCreateData},
     user::User,
 };
+use chrono::Utc;
 use sqlx::MySqlPool;
 
 use crate::{
     authorization::require_admin_user,
+    configuration::BillingSettings,
     database::{
         budgeting::user_budget::{NewUserBudget, insert_user_budget_into_db},
         user::user::select_user_name_from_db,
@@ -22,9 +24,13 @@ use crate::{
 pub async fn user_budget_create(
     user: ReqData<User>,
     db_pool: Data<MySqlPool>,
+    billing: Data<BillingSettings>,
     data: Json<UserBudgetCreateData>,
 ) -> Result<HttpResponse, OptionApiError> {
     require_admin_user(&user)?;
+    let mut data = data.into_inner();
+    // budgets without a year are for the running billing year
+    data.year.get_or_insert_with(|| billing.year_of(Utc::now()));
     let new_user_budget: NewUserBudget = data
         .clone()
         .try_into()
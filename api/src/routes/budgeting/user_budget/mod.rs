@@ -14,7 +14,7 @@ mod modify;
 use modify::user_budget_modify;
 mod delete;
 use delete::user_budget_delete;
-mod over;
+pub(crate) mod over;
 use over::user_budget_over;
 mod sync;
 use sync::user_budget_sync;
@@ -6,11 +6,12 @@ use anyhow::{Context, anyhow};
 use avina_wire::{
     budgeting::{
         UserBudgetOverCombined, UserBudgetOverCombinedDetail,
-        UserBudgetOverDetail, UserBudgetOverParams, UserBudgetOverSimple,
+        UserBudgetOverDetail, UserBudgetOverForecast, UserBudgetOverParams,
+        UserBudgetOverSimple,
     },
     user::User,
 };
-use chrono::{DateTime, Datelike, Utc};
+use chrono::{DateTime, Utc};
 use serde::Serialize;
 use sqlx::{MySql, MySqlPool, Transaction};
 
@@ -19,6 +20,7 @@ use crate::{
         require_admin_user, require_master_user_or_return_not_found,
         require_user_or_project_master_or_not_found,
     },
+    configuration::BillingSettings,
     database::{
         budgeting::{
             project_budget::select_maybe_project_budget_by_project_and_year_from_db,
@@ -36,11 +38,11 @@ use crate::{
         accounting::server_cost::get::{
             ServerCostForUser, calculate_server_cost_for_user,
         },
+        budgeting::forecast::BudgetForecaster,
         server_cost::get::{
             ServerCostForProject, calculate_server_cost_for_project,
         },
     },
-    utils::start_of_the_year,
 };
 
 #[derive(Serialize)]
@@ -50,10 +52,12 @@ pub enum UserBudgetOver {
     Combined(Vec<UserBudgetOverCombined>),
     Detail(Vec<UserBudgetOverDetail>),
     CombinedDetail(Vec<UserBudgetOverCombinedDetail>),
+    Forecast(Vec<UserBudgetOverForecast>),
 }
 
 pub async fn calculate_user_budget_over_for_budget_normal(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     budget_id: u64,
     end: DateTime<Utc>,
 ) -> Result<Vec<UserBudgetOverSimple>, UnexpectedOnlyError> {
@@ -64,12 +68,13 @@ pub async fn calculate_user_budget_over_for_budget_normal(
         return Ok(overs);
     };
     let year = budget.year;
-    if year != end.year() as u32 {
+    if year != billing.year_of(end) {
         return Ok(overs);
     }
-    let begin = start_of_the_year(year);
+    let begin = billing.start_of_the_year(year);
     let ServerCostForUser::Normal(cost) = calculate_server_cost_for_user(
         transaction,
+        billing,
         budget.user as u64,
         begin,
         end,
@@ -91,6 +96,7 @@ pub async fn calculate_user_budget_over_for_budget_normal(
 
 pub async fn calculate_user_budget_over_for_budget_combined(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     budget_id: u64,
     end: DateTime<Utc>,
 ) -> Result<Vec<UserBudgetOverCombined>, UnexpectedOnlyError> {
@@ -111,12 +117,13 @@ pub async fn calculate_user_budget_over_for_budget_combined(
             year,
         )
         .await?;
-    if year != end.year() as u32 {
+    if year != billing.year_of(end) {
         return Ok(overs);
     }
-    let begin = start_of_the_year(year);
+    let begin = billing.start_of_the_year(year);
     let ServerCostForUser::Normal(cost) = calculate_server_cost_for_user(
         transaction,
+        billing,
         budget.user as u64,
         begin,
         end,
@@ -129,6 +136,7 @@ pub async fn calculate_user_budget_over_for_budget_combined(
     let ServerCostForProject::Normal(project_cost) =
         calculate_server_cost_for_project(
             transaction,
+            billing,
             user.project as u64,
             begin,
             end,
@@ -159,6 +167,7 @@ pub async fn calculate_user_budget_over_for_budget_combined(
 
 pub async fn calculate_user_budget_over_for_budget_detail(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     budget_id: u64,
     end: DateTime<Utc>,
 ) -> Result<Vec<UserBudgetOverDetail>, UnexpectedOnlyError> {
@@ -169,12 +178,13 @@ pub async fn calculate_user_budget_over_for_budget_detail(
         return Ok(overs);
     };
     let year = budget.year;
-    if year != end.year() as u32 {
+    if year != billing.year_of(end) {
         return Ok(overs);
     }
-    let begin = start_of_the_year(year);
+    let begin = billing.start_of_the_year(year);
     let ServerCostForUser::Normal(cost) = calculate_server_cost_for_user(
         transaction,
+        billing,
         budget.user as u64,
         begin,
         end,
@@ -198,6 +208,7 @@ pub async fn calculate_user_budget_over_for_budget_detail(
 
 pub async fn calculate_user_budget_over_for_budget_combined_detail(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     budget_id: u64,
     end: DateTime<Utc>,
 ) -> Result<Vec<UserBudgetOverCombinedDetail>, UnexpectedOnlyError> {
@@ -218,12 +229,13 @@ pub async fn calculate_user_budget_over_for_budget_combined_detail(
             year,
         )
         .await?;
-    if year != end.year() as u32 {
+    if year != billing.year_of(end) {
         return Ok(overs);
     }
-    let begin = start_of_the_year(year);
+    let begin = billing.start_of_the_year(year);
     let ServerCostForUser::Normal(cost) = calculate_server_cost_for_user(
         transaction,
+        billing,
         budget.user as u64,
         begin,
         end,
@@ -236,6 +248,7 @@ pub async fn calculate_user_budget_over_for_budget_combined_detail(
     let ServerCostForProject::Normal(project_cost) =
         calculate_server_cost_for_project(
             transaction,
+            billing,
             user.project as u64,
             begin,
             end,
@@ -270,6 +283,7 @@ pub async fn calculate_user_budget_over_for_budget_combined_detail(
 
 pub async fn calculate_user_budget_over_for_budget(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     budget_id: u64,
     end: DateTime<Utc>,
     combined: Option<bool>,
@@ -279,6 +293,7 @@ pub async fn calculate_user_budget_over_for_budget(
         (Some(true), Some(true)) => UserBudgetOver::CombinedDetail(
             calculate_user_budget_over_for_budget_combined_detail(
                 transaction,
+                billing,
                 budget_id,
                 end,
             )
@@ -287,6 +302,7 @@ pub async fn calculate_user_budget_over_for_budget(
         (None | Some(false), Some(true)) => UserBudgetOver::Detail(
             calculate_user_budget_over_for_budget_detail(
                 transaction,
+                billing,
                 budget_id,
                 end,
             )
@@ -295,6 +311,7 @@ pub async fn calculate_user_budget_over_for_budget(
         (Some(true), None | Some(false)) => UserBudgetOver::Combined(
             calculate_user_budget_over_for_budget_combined(
                 transaction,
+                billing,
                 budget_id,
                 end,
             )
@@ -303,6 +320,7 @@ pub async fn calculate_user_budget_over_for_budget(
         (None | Some(false), None | Some(false)) => UserBudgetOver::Normal(
             calculate_user_budget_over_for_budget_normal(
                 transaction,
+                billing,
                 budget_id,
                 end,
             )
@@ -313,11 +331,12 @@ pub async fn calculate_user_budget_over_for_budget(
 
 pub async fn calculate_user_budget_over_for_user_normal(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     user_id: u64,
     end: DateTime<Utc>,
 ) -> Result<Vec<UserBudgetOverSimple>, UnexpectedOnlyError> {
     let mut overs = vec![];
-    let year = end.year() as u32;
+    let year = billing.year_of(end);
     let Some(budget) = select_maybe_user_budget_by_user_and_year_from_db(
         transaction,
         user_id,
@@ -327,9 +346,10 @@ pub async fn calculate_user_budget_over_for_user_normal(
     else {
         return Ok(overs);
     };
-    let begin = start_of_the_year(year);
+    let begin = billing.start_of_the_year(year);
     let ServerCostForUser::Normal(cost) = calculate_server_cost_for_user(
         transaction,
+        billing,
         budget.user as u64,
         begin,
         end,
@@ -351,11 +371,12 @@ pub async fn calculate_user_budget_over_for_user_normal(
 
 pub async fn calculate_user_budget_over_for_user_combined(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     user_id: u64,
     end: DateTime<Utc>,
 ) -> Result<Vec<UserBudgetOverCombined>, UnexpectedOnlyError> {
     let mut overs = vec![];
-    let year = end.year() as u32;
+    let year = billing.year_of(end);
     let Some(budget) = select_maybe_user_budget_by_user_and_year_from_db(
         transaction,
         user_id,
@@ -375,12 +396,13 @@ pub async fn calculate_user_budget_over_for_user_combined(
             year,
         )
         .await?;
-    if year != end.year() as u32 {
+    if year != billing.year_of(end) {
         return Ok(overs);
     }
-    let begin = start_of_the_year(year);
+    let begin = billing.start_of_the_year(year);
     let ServerCostForUser::Normal(cost) = calculate_server_cost_for_user(
         transaction,
+        billing,
         budget.user as u64,
         begin,
         end,
@@ -393,6 +415,7 @@ pub async fn calculate_user_budget_over_for_user_combined(
     let ServerCostForProject::Normal(project_cost) =
         calculate_server_cost_for_project(
             transaction,
+            billing,
             user.project as u64,
             begin,
             end,
@@ -423,11 +446,12 @@ pub async fn calculate_user_budget_over_for_user_combined(
 
 pub async fn calculate_user_budget_over_for_user_detail(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     user_id: u64,
     end: DateTime<Utc>,
 ) -> Result<Vec<UserBudgetOverDetail>, UnexpectedOnlyError> {
     let mut overs = vec![];
-    let year = end.year() as u32;
+    let year = billing.year_of(end);
     let Some(budget) = select_maybe_user_budget_by_user_and_year_from_db(
         transaction,
         user_id,
@@ -437,9 +461,10 @@ pub async fn calculate_user_budget_over_for_user_detail(
     else {
         return Ok(overs);
     };
-    let begin = start_of_the_year(year);
+    let begin = billing.start_of_the_year(year);
     let ServerCostForUser::Normal(cost) = calculate_server_cost_for_user(
         transaction,
+        billing,
         budget.user as u64,
         begin,
         end,
@@ -463,11 +488,12 @@ pub async fn calculate_user_budget_over_for_user_detail(
 
 pub async fn calculate_user_budget_over_for_user_combined_detail(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     user_id: u64,
     end: DateTime<Utc>,
 ) -> Result<Vec<UserBudgetOverCombinedDetail>, UnexpectedOnlyError> {
     let mut overs = vec![];
-    let year = end.year() as u32;
+    let year = billing.year_of(end);
     let Some(budget) = select_maybe_user_budget_by_user_and_year_from_db(
         transaction,
         user_id,
@@ -487,12 +513,13 @@ pub async fn calculate_user_budget_over_for_user_combined_detail(
             year,
         )
         .await?;
-    if year != end.year() as u32 {
+    if year != billing.year_of(end) {
         return Ok(overs);
     }
-    let begin = start_of_the_year(year);
+    let begin = billing.start_of_the_year(year);
     let ServerCostForUser::Normal(cost) = calculate_server_cost_for_user(
         transaction,
+        billing,
         budget.user as u64,
         begin,
         end,
@@ -505,6 +532,7 @@ pub async fn calculate_user_budget_over_for_user_combined_detail(
     let ServerCostForProject::Normal(project_cost) =
         calculate_server_cost_for_project(
             transaction,
+            billing,
             user.project as u64,
             begin,
             end,
@@ -539,6 +567,7 @@ pub async fn calculate_user_budget_over_for_user_combined_detail(
 
 pub async fn calculate_user_budget_over_for_user(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     user_id: u64,
     end: DateTime<Utc>,
     combined: Option<bool>,
@@ -548,6 +577,7 @@ pub async fn calculate_user_budget_over_for_user(
         (Some(true), Some(true)) => UserBudgetOver::CombinedDetail(
             calculate_user_budget_over_for_user_combined_detail(
                 transaction,
+                billing,
                 user_id,
                 end,
             )
@@ -556,6 +586,7 @@ pub async fn calculate_user_budget_over_for_user(
         (None | Some(false), Some(true)) => UserBudgetOver::Detail(
             calculate_user_budget_over_for_user_detail(
                 transaction,
+                billing,
                 user_id,
                 end,
             )
@@ -564,6 +595,7 @@ pub async fn calculate_user_budget_over_for_user(
         (Some(true), None | Some(false)) => UserBudgetOver::Combined(
             calculate_user_budget_over_for_user_combined(
                 transaction,
+                billing,
                 user_id,
                 end,
             )
@@ -572,6 +604,7 @@ pub async fn calculate_user_budget_over_for_user(
         (None | Some(false), None | Some(false)) => UserBudgetOver::Normal(
             calculate_user_budget_over_for_user_normal(
                 transaction,
+                billing,
                 user_id,
                 end,
             )
@@ -582,21 +615,23 @@ pub async fn calculate_user_budget_over_for_user(
 
 pub async fn calculate_user_budget_over_for_project_normal(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     project_id: u64,
     end: DateTime<Utc>,
 ) -> Result<Vec<UserBudgetOverSimple>, UnexpectedOnlyError> {
     let mut overs = vec![];
-    let year = end.year() as u32;
+    let year = billing.year_of(end);
     let budgets = select_user_budgets_by_project_and_year_from_db(
         transaction,
         project_id,
         year,
     )
     .await?;
-    let begin = start_of_the_year(year);
+    let begin = billing.start_of_the_year(year);
     for budget in budgets {
         let ServerCostForUser::Normal(cost) = calculate_server_cost_for_user(
             transaction,
+            billing,
             budget.user as u64,
             begin,
             end,
@@ -621,12 +656,13 @@ pub async fn calculate_user_budget_over_for_project_normal(
 
 pub async fn calculate_user_budget_over_for_project_combined(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     project_id: u64,
     end: DateTime<Utc>,
 ) -> Result<Vec<UserBudgetOverCombined>, UnexpectedOnlyError> {
     let mut overs = vec![];
-    let year = end.year() as u32;
-    if year != end.year() as u32 {
+    let year = billing.year_of(end);
+    if year != billing.year_of(end) {
         return Ok(overs);
     }
     let budgets = select_user_budgets_by_project_and_year_from_db(
@@ -647,9 +683,10 @@ pub async fn calculate_user_budget_over_for_project_combined(
                 year,
             )
             .await?;
-        let begin = start_of_the_year(year);
+        let begin = billing.start_of_the_year(year);
         let ServerCostForUser::Normal(cost) = calculate_server_cost_for_user(
             transaction,
+            billing,
             budget.user as u64,
             begin,
             end,
@@ -664,6 +701,7 @@ pub async fn calculate_user_budget_over_for_project_combined(
         let ServerCostForProject::Normal(project_cost) =
             calculate_server_cost_for_project(
                 transaction,
+                billing,
                 user.project as u64,
                 begin,
                 end,
@@ -697,21 +735,23 @@ pub async fn calculate_user_budget_over_for_project_combined(
 
 pub async fn calculate_user_budget_over_for_project_detail(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     project_id: u64,
     end: DateTime<Utc>,
 ) -> Result<Vec<UserBudgetOverDetail>, UnexpectedOnlyError> {
     let mut overs = vec![];
-    let year = end.year() as u32;
+    let year = billing.year_of(end);
     let budgets = select_user_budgets_by_project_and_year_from_db(
         transaction,
         project_id,
         year,
     )
     .await?;
-    let begin = start_of_the_year(year);
+    let begin = billing.start_of_the_year(year);
     for budget in budgets {
         let ServerCostForUser::Normal(cost) = calculate_server_cost_for_user(
             transaction,
+            billing,
             budget.user as u64,
             begin,
             end,
@@ -738,12 +778,13 @@ pub async fn calculate_user_budget_over_for_project_detail(
 
 pub async fn calculate_user_budget_over_for_project_combined_detail(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     project_id: u64,
     end: DateTime<Utc>,
 ) -> Result<Vec<UserBudgetOverCombinedDetail>, UnexpectedOnlyError> {
     let mut overs = vec![];
-    let year = end.year() as u32;
-    if year != end.year() as u32 {
+    let year = billing.year_of(end);
+    if year != billing.year_of(end) {
         return Ok(overs);
     }
     let budgets = select_user_budgets_by_project_and_year_from_db(
@@ -764,9 +805,10 @@ pub async fn calculate_user_budget_over_for_project_combined_detail(
                 year,
             )
             .await?;
-        let begin = start_of_the_year(year);
+        let begin = billing.start_of_the_year(year);
         let ServerCostForUser::Normal(cost) = calculate_server_cost_for_user(
             transaction,
+            billing,
             budget.user as u64,
             begin,
             end,
@@ -781,6 +823,7 @@ pub async fn calculate_user_budget_over_for_project_combined_detail(
         let ServerCostForProject::Normal(project_cost) =
             calculate_server_cost_for_project(
                 transaction,
+                billing,
                 user.project as u64,
                 begin,
                 end,
@@ -818,6 +861,7 @@ pub async fn calculate_user_budget_over_for_project_combined_detail(
 
 pub async fn calculate_user_budget_over_for_project(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     project_id: u64,
     end: DateTime<Utc>,
     combined: Option<bool>,
@@ -827,6 +871,7 @@ pub async fn calculate_user_budget_over_for_project(
         (Some(true), Some(true)) => UserBudgetOver::CombinedDetail(
             calculate_user_budget_over_for_project_combined_detail(
                 transaction,
+                billing,
                 project_id,
                 end,
             )
@@ -835,6 +880,7 @@ pub async fn calculate_user_budget_over_for_project(
         (None | Some(false), Some(true)) => UserBudgetOver::Detail(
             calculate_user_budget_over_for_project_detail(
                 transaction,
+                billing,
                 project_id,
                 end,
             )
@@ -843,6 +889,7 @@ pub async fn calculate_user_budget_over_for_project(
         (Some(true), None | Some(false)) => UserBudgetOver::Combined(
             calculate_user_budget_over_for_project_combined(
                 transaction,
+                billing,
                 project_id,
                 end,
             )
@@ -851,6 +898,7 @@ pub async fn calculate_user_budget_over_for_project(
         (None | Some(false), None | Some(false)) => UserBudgetOver::Normal(
             calculate_user_budget_over_for_project_normal(
                 transaction,
+                billing,
                 project_id,
                 end,
             )
@@ -861,16 +909,18 @@ pub async fn calculate_user_budget_over_for_project(
 
 pub async fn calculate_user_budget_over_for_all_normal(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     end: DateTime<Utc>,
 ) -> Result<Vec<UserBudgetOverSimple>, UnexpectedOnlyError> {
     let mut overs = vec![];
-    let year = end.year() as u32;
+    let year = billing.year_of(end);
     let budgets =
         select_user_budgets_by_year_from_db(transaction, year).await?;
-    let begin = start_of_the_year(year);
+    let begin = billing.start_of_the_year(year);
     for budget in budgets {
         let ServerCostForUser::Normal(cost) = calculate_server_cost_for_user(
             transaction,
+            billing,
             budget.user as u64,
             begin,
             end,
@@ -895,11 +945,12 @@ pub async fn calculate_user_budget_over_for_all_normal(
 
 pub async fn calculate_user_budget_over_for_all_combined(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     end: DateTime<Utc>,
 ) -> Result<Vec<UserBudgetOverCombined>, UnexpectedOnlyError> {
     let mut overs = vec![];
-    let year = end.year() as u32;
-    if year != end.year() as u32 {
+    let year = billing.year_of(end);
+    if year != billing.year_of(end) {
         return Ok(overs);
     }
     let budgets =
@@ -916,9 +967,10 @@ pub async fn calculate_user_budget_over_for_all_combined(
                 year,
             )
             .await?;
-        let begin = start_of_the_year(year);
+        let begin = billing.start_of_the_year(year);
         let ServerCostForUser::Normal(cost) = calculate_server_cost_for_user(
             transaction,
+            billing,
             budget.user as u64,
             begin,
             end,
@@ -933,6 +985,7 @@ pub async fn calculate_user_budget_over_for_all_combined(
         let ServerCostForProject::Normal(project_cost) =
             calculate_server_cost_for_project(
                 transaction,
+                billing,
                 user.project as u64,
                 begin,
                 end,
@@ -966,16 +1019,18 @@ pub async fn calculate_user_budget_over_for_all_combined(
 
 pub async fn calculate_user_budget_over_for_all_detail(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     end: DateTime<Utc>,
 ) -> Result<Vec<UserBudgetOverDetail>, UnexpectedOnlyError> {
     let mut overs = vec![];
-    let year = end.year() as u32;
+    let year = billing.year_of(end);
     let budgets =
         select_user_budgets_by_year_from_db(transaction, year).await?;
-    let begin = start_of_the_year(year);
+    let begin = billing.start_of_the_year(year);
     for budget in budgets {
         let ServerCostForUser::Normal(cost) = calculate_server_cost_for_user(
             transaction,
+            billing,
             budget.user as u64,
             begin,
             end,
@@ -1002,11 +1057,12 @@ pub async fn calculate_user_budget_over_for_all_detail(
 
 pub async fn calculate_user_budget_over_for_all_combined_detail(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     end: DateTime<Utc>,
 ) -> Result<Vec<UserBudgetOverCombinedDetail>, UnexpectedOnlyError> {
     let mut overs = vec![];
-    let year = end.year() as u32;
-    if year != end.year() as u32 {
+    let year = billing.year_of(end);
+    if year != billing.year_of(end) {
         return Ok(overs);
     }
     let budgets =
@@ -1023,9 +1079,10 @@ pub async fn calculate_user_budget_over_for_all_combined_detail(
                 year,
             )
             .await?;
-        let begin = start_of_the_year(year);
+        let begin = billing.start_of_the_year(year);
         let ServerCostForUser::Normal(cost) = calculate_server_cost_for_user(
             transaction,
+            billing,
             budget.user as u64,
             begin,
             end,
@@ -1040,6 +1097,7 @@ pub async fn calculate_user_budget_over_for_all_combined_detail(
         let ServerCostForProject::Normal(project_cost) =
             calculate_server_cost_for_project(
                 transaction,
+                billing,
                 user.project as u64,
                 begin,
                 end,
@@ -1077,6 +1135,7 @@ pub async fn calculate_user_budget_over_for_all_combined_detail(
 
 pub async fn calculate_user_budget_over_for_all(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     end: DateTime<Utc>,
     combined: Option<bool>,
     detail: Option<bool>,
@@ -1085,27 +1144,78 @@ pub async fn calculate_user_budget_over_for_all(
         (Some(true), Some(true)) => UserBudgetOver::CombinedDetail(
             calculate_user_budget_over_for_all_combined_detail(
                 transaction,
+                billing,
                 end,
             )
             .await?,
         ),
         (None | Some(false), Some(true)) => UserBudgetOver::Detail(
-            calculate_user_budget_over_for_all_detail(transaction, end).await?,
+            calculate_user_budget_over_for_all_detail(
+                transaction,
+                billing,
+                end,
+            )
+            .await?,
         ),
         (Some(true), None | Some(false)) => UserBudgetOver::Combined(
-            calculate_user_budget_over_for_all_combined(transaction, end)
-                .await?,
+            calculate_user_budget_over_for_all_combined(
+                transaction,
+                billing,
+                end,
+            )
+            .await?,
         ),
         (None | Some(false), None | Some(false)) => UserBudgetOver::Normal(
-            calculate_user_budget_over_for_all_normal(transaction, end).await?,
+            calculate_user_budget_over_for_all_normal(
+                transaction,
+                billing,
+                end,
+            )
+            .await?,
         ),
     })
 }
 
+// turns the detailed budget overs into forecasts, other variants are kept
+pub async fn forecast_user_budget_over(
+    transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
+    over: UserBudgetOver,
+    end: DateTime<Utc>,
+) -> Result<UserBudgetOver, UnexpectedOnlyError> {
+    let UserBudgetOver::Detail(details) = over else {
+        return Ok(over);
+    };
+    let forecaster = BudgetForecaster::new(transaction, billing, end).await?;
+    let mut forecasts = vec![];
+    for detail in details {
+        let forecast = forecaster.forecast_user(
+            detail.user_id,
+            detail.cost,
+            detail.budget,
+        );
+        forecasts.push(UserBudgetOverForecast {
+            budget_id: detail.budget_id,
+            user_id: detail.user_id,
+            user_name: detail.user_name,
+            over: detail.over,
+            cost: detail.cost,
+            budget: detail.budget,
+            rate: forecast.rate,
+            projected_cost: forecast.projected_cost,
+            exhausted_at: forecast
+                .exhausted_at
+                .map(|exhausted_at| exhausted_at.fixed_offset()),
+        });
+    }
+    Ok(UserBudgetOver::Forecast(forecasts))
+}
+
 #[tracing::instrument(name = "user_budget_over")]
 pub async fn user_budget_over(
     user: ReqData<User>,
     db_pool: Data<MySqlPool>,
+    billing: Data<BillingSettings>,
     params: Query<UserBudgetOverParams>,
     // TODO: is the ValidationError variant ever used?
 ) -> Result<HttpResponse, OptionApiError> {
@@ -1115,23 +1225,31 @@ pub async fn user_budget_over(
         .begin()
         .await
         .context("Failed to begin transaction")?;
-    let over = if params.all.unwrap_or(false) {
+    let forecast = params.forecast.unwrap_or(false);
+    let (combined, detail) = if forecast {
+        (None, Some(true))
+    } else {
+        (params.combined, params.detail)
+    };
+    let mut over = if params.all.unwrap_or(false) {
         require_admin_user(&user)?;
         calculate_user_budget_over_for_all(
             &mut transaction,
+            &billing,
             end.into(),
-            params.combined,
-            params.detail,
+            combined,
+            detail,
         )
         .await?
     } else if let Some(project_id) = params.project {
         require_master_user_or_return_not_found(&user, project_id)?;
         calculate_user_budget_over_for_project(
             &mut transaction,
+            &billing,
             project_id as u64,
             end.into(),
-            params.combined,
-            params.detail,
+            combined,
+            detail,
         )
         .await?
     } else if let Some(user_id) = params.user {
@@ -1144,10 +1262,11 @@ pub async fn user_budget_over(
         )?;
         calculate_user_budget_over_for_user(
             &mut transaction,
+            &billing,
             user_id as u64,
             end.into(),
-            params.combined,
-            params.detail,
+            combined,
+            detail,
         )
         .await?
     } else if let Some(budget_id) = params.budget {
@@ -1164,22 +1283,33 @@ pub async fn user_budget_over(
         )?;
         calculate_user_budget_over_for_budget(
             &mut transaction,
+            &billing,
             budget_id as u64,
             end.into(),
-            params.combined,
-            params.detail,
+            combined,
+            detail,
         )
         .await?
     } else {
         calculate_user_budget_over_for_user(
             &mut transaction,
+            &billing,
             user.id as u64,
             end.into(),
-            params.combined,
-            params.detail,
+            combined,
+            detail,
         )
         .await?
     };
+    if forecast {
+        over = forecast_user_budget_over(
+            &mut transaction,
+            &billing,
+            over,
+            end.into(),
+        )
+        .await?;
+    }
     transaction
         .commit()
         .await
@@ -10,7 +10,7 @@ use avina_wire::{
 use sqlx::MySqlPool;
 
 use crate::{
-    authorization::require_admin_user,
+    authorization::require_admin_user, configuration::BillingSettings,
     database::budgeting::user_budget::sync_user_budgets_in_db,
     error::NormalApiError,
 };
@@ -21,6 +21,7 @@ pub async fn user_budget_sync(
     user: ReqData<User>,
     project: ReqData<Project>,
     db_pool: Data<MySqlPool>,
+    billing: Data<BillingSettings>,
     // TODO: this can only be an authorization or unexpected error, we need a type for that
 ) -> Result<HttpResponse, NormalApiError> {
     require_admin_user(&user)?;
@@ -28,7 +29,7 @@ pub async fn user_budget_sync(
         .begin()
         .await
         .context("Failed to begin transaction")?;
-    let count = sync_user_budgets_in_db(&mut transaction).await?;
+    let count = sync_user_budgets_in_db(&mut transaction, &billing).await?;
     transaction
         .commit()
         .await
@@ -1,10 +1,10 @@
-mod accounting;
-mod budgeting;
+pub(crate) mod accounting;
+pub(crate) mod budgeting;
 mod health_check;
 mod hello;
 mod pricing;
 mod quota;
-mod resources;
+pub(crate) mod resources;
 pub mod user;
 
 pub use accounting::*;
@@ -17,14 +17,7 @@ pub use resources::*;
 pub use user::*;
 
 // TODO: missing endpoints
-// - resources::flavor::usage
-// - resources::flavor_group::initialize
-// - resources::flavor_group::usage
 // - resources::usage
-// - pricing::flavor_price::initialize
-// - quota::flavor_quota::check
-// - user::import
-// - budgeting::budget_over_tree
 
 // TODO: improve the following endpoints
 // - budgeting::project_budget::modify
@@ -11,17 +11,20 @@ use sqlx::MySqlPool;
 
 use crate::{
     authorization::require_admin_user,
+    configuration::BillingSettings,
     database::{
         pricing::flavor_price::{NewFlavorPrice, insert_flavor_price_into_db},
         resources::flavor::select_flavor_name_from_db,
     },
     error::{NormalApiError, OptionApiError},
+    routes::accounting::server_cost::rollup::refresh_server_cost_rollups,
 };
 
 #[tracing::instrument(name = "flavor_price_create")]
 pub async fn flavor_price_create(
     user: ReqData<User>,
     db_pool: Data<MySqlPool>,
+    billing: Data<BillingSettings>,
     data: Json<FlavorPriceCreateData>,
 ) -> Result<HttpResponse, OptionApiError> {
     require_admin_user(&user)?;
@@ -40,6 +43,12 @@ pub async fn flavor_price_create(
     .await?;
     let id = insert_flavor_price_into_db(&mut transaction, &new_flavor_price)
         .await?;
+    refresh_server_cost_rollups(
+        &mut transaction,
+        &billing,
+        new_flavor_price.start_time,
+    )
+    .await?;
     transaction
         .commit()
         .await
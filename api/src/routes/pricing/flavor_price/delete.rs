@@ -9,13 +9,17 @@ use sqlx::{Executor, MySql, MySqlPool, Transaction};
 use super::FlavorPriceIdParam;
 use crate::{
     authorization::require_admin_user,
+    configuration::BillingSettings,
+    database::pricing::flavor_price::select_maybe_flavor_price_from_db,
     error::{MinimalApiError, NormalApiError},
+    routes::accounting::server_cost::rollup::refresh_server_cost_rollups,
 };
 
 #[tracing::instrument(name = "flavor_price_delete")]
 pub async fn flavor_price_delete(
     user: ReqData<User>,
     db_pool: Data<MySqlPool>,
+    billing: Data<BillingSettings>,
     params: Path<FlavorPriceIdParam>,
 ) -> Result<HttpResponse, NormalApiError> {
     require_admin_user(&user)?;
@@ -23,11 +27,24 @@ pub async fn flavor_price_delete(
         .begin()
         .await
         .context("Failed to begin transaction")?;
+    let flavor_price = select_maybe_flavor_price_from_db(
+        &mut transaction,
+        params.flavor_price_id as u64,
+    )
+    .await?;
     delete_flavor_price_from_db(
         &mut transaction,
         params.flavor_price_id as u64,
     )
     .await?;
+    if let Some(flavor_price) = flavor_price {
+        refresh_server_cost_rollups(
+            &mut transaction,
+            &billing,
+            flavor_price.start_time.to_utc(),
+        )
+        .await?;
+    }
     transaction
         .commit()
         .await
@@ -0,0 +1,85 @@
+use std::collections::HashSet;
+
+use actix_web::{
+    HttpResponse,
+    web::{Data, ReqData},
+};
+use anyhow::Context;
+use avina_wire::{pricing::FlavorPriceInitialize, user::User};
+use chrono::DateTime;
+use sqlx::MySqlPool;
+use strum::IntoEnumIterator;
+
+use crate::{
+    authorization::require_admin_user,
+    configuration::{BillingSettings, PricingSettings},
+    database::{
+        pricing::flavor_price::{
+            NewFlavorPrice, insert_flavor_price_into_db,
+            select_all_flavor_prices_from_db,
+        },
+        resources::flavor::select_all_flavors_from_db,
+    },
+    error::OptionApiError,
+    routes::accounting::server_cost::{
+        get::UserClass, rollup::refresh_server_cost_rollups,
+    },
+};
+
+#[tracing::instrument(name = "flavor_price_initialize", skip(pricing))]
+pub async fn flavor_price_initialize(
+    user: ReqData<User>,
+    db_pool: Data<MySqlPool>,
+    billing: Data<BillingSettings>,
+    pricing: Data<PricingSettings>,
+) -> Result<HttpResponse, OptionApiError> {
+    require_admin_user(&user)?;
+    let mut transaction = db_pool
+        .begin()
+        .await
+        .context("Failed to begin transaction")?;
+    let existing_prices = select_all_flavor_prices_from_db(&mut transaction)
+        .await?
+        .into_iter()
+        .map(|p| (p.flavor, p.user_class))
+        .collect::<HashSet<_>>();
+    let flavors = select_all_flavors_from_db(&mut transaction).await?;
+    let mut new_flavor_price_count = 0;
+    for flavor in flavors {
+        for user_class in UserClass::iter() {
+            let user_class = user_class as u32;
+            if existing_prices.contains(&(flavor.id, user_class)) {
+                continue;
+            }
+            // the initial price is valid from the beginning of time, so it
+            // also covers servers that ran before the initialization
+            let new_flavor_price = NewFlavorPrice {
+                flavor_id: flavor.id as u64,
+                user_class,
+                unit_price: pricing.unit_price(flavor.weight, user_class),
+                start_time: DateTime::UNIX_EPOCH,
+            };
+            insert_flavor_price_into_db(&mut transaction, &new_flavor_price)
+                .await?;
+            new_flavor_price_count += 1;
+        }
+    }
+    if new_flavor_price_count > 0 {
+        refresh_server_cost_rollups(
+            &mut transaction,
+            &billing,
+            DateTime::UNIX_EPOCH,
+        )
+        .await?;
+    }
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit transaction")?;
+    let flavor_price_initialize = FlavorPriceInitialize {
+        new_flavor_price_count,
+    };
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(flavor_price_initialize))
+}
@@ -14,6 +14,8 @@ mod modify;
 use modify::flavor_price_modify;
 mod delete;
 use delete::flavor_price_delete;
+mod initialize;
+use initialize::flavor_price_initialize;
 
 pub fn flavor_prices_scope() -> Scope {
     scope("/flavorprices")
@@ -23,6 +25,7 @@ pub fn flavor_prices_scope() -> Scope {
         // TODO: what about PUT?
         .route("/{flavor_price_id}/", patch().to(flavor_price_modify))
         .route("/{flavor_price_id}/", delete().to(flavor_price_delete))
+        .route("/initialize/", get().to(flavor_price_initialize))
 }
 
 // TODO: wouldn't a general IdParam be better?
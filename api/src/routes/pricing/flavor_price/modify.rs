@@ -12,14 +12,17 @@ use sqlx::{Executor, MySql, MySqlPool, Transaction};
 use super::FlavorPriceIdParam;
 use crate::{
     authorization::require_admin_user,
+    configuration::BillingSettings,
     database::pricing::flavor_price::select_flavor_price_from_db,
     error::{NotFoundOrUnexpectedApiError, OptionApiError},
+    routes::accounting::server_cost::rollup::refresh_server_cost_rollups,
 };
 
 #[tracing::instrument(name = "flavor_price_modify")]
 pub async fn flavor_price_modify(
     user: ReqData<User>,
     db_pool: Data<MySqlPool>,
+    billing: Data<BillingSettings>,
     data: Json<FlavorPriceModifyData>,
     params: Path<FlavorPriceIdParam>,
 ) -> Result<HttpResponse, OptionApiError> {
@@ -34,8 +37,19 @@ pub async fn flavor_price_modify(
         .begin()
         .await
         .context("Failed to begin transaction")?;
+    let previous_flavor_price =
+        select_flavor_price_from_db(&mut transaction, data.id as u64).await?;
     let flavor_price =
         update_flavor_price_in_db(&mut transaction, &data).await?;
+    refresh_server_cost_rollups(
+        &mut transaction,
+        &billing,
+        previous_flavor_price
+            .start_time
+            .min(flavor_price.start_time)
+            .to_utc(),
+    )
+    .await?;
     transaction
         .commit()
         .await
@@ -0,0 +1,88 @@
+use actix_web::{
+    HttpResponse,
+    web::{Data, Query, ReqData},
+};
+use anyhow::Context;
+use avina_wire::{
+    quota::{FlavorQuotaCheck, FlavorQuotaCheckParams},
+    user::User,
+};
+use sqlx::{MySql, MySqlPool, Transaction};
+
+use crate::{
+    authorization::require_user_or_project_master_or_not_found,
+    database::{
+        quota::flavor_quota::select_flavor_quotas_by_user_from_db,
+        resources::flavor::select_flavor_from_db,
+        user::user::select_user_from_db,
+    },
+    error::{NotFoundOrUnexpectedApiError, OptionApiError},
+    routes::resources::flavor_group::usage::calculate_flavor_group_usage_for_user,
+};
+
+/// Check whether the user can start `count` more servers of the given flavor.
+///
+/// Flavors without a group are not restricted, for grouped flavors the user
+/// needs a quota for the group, where a quota of -1 means unlimited.
+pub async fn check_flavor_quota_for_user(
+    transaction: &mut Transaction<'_, MySql>,
+    user_id: u64,
+    flavor_id: u64,
+    count: u32,
+) -> Result<bool, NotFoundOrUnexpectedApiError> {
+    let flavor = select_flavor_from_db(transaction, flavor_id).await?;
+    let Some(flavor_group_id) = flavor.group else {
+        return Ok(true);
+    };
+    let quotas = select_flavor_quotas_by_user_from_db(transaction, user_id)
+        .await?
+        .into_iter()
+        .filter(|q| q.flavor_group == flavor_group_id)
+        .collect::<Vec<_>>();
+    if quotas.is_empty() {
+        return Ok(false);
+    }
+    let usage = calculate_flavor_group_usage_for_user(transaction, user_id)
+        .await?
+        .into_iter()
+        .filter(|u| u.flavorgroup_id == flavor_group_id)
+        .map(|u| u.usage as i64)
+        .sum::<i64>();
+    // widen before multiplying, huge counts simply exceed every quota
+    let requested = usage
+        .saturating_add((count as i64).saturating_mul(flavor.weight as i64));
+    Ok(quotas.iter().all(|q| q.quota == -1 || requested <= q.quota))
+}
+
+#[tracing::instrument(name = "flavor_quota_check")]
+pub async fn flavor_quota_check(
+    user: ReqData<User>,
+    db_pool: Data<MySqlPool>,
+    params: Query<FlavorQuotaCheckParams>,
+) -> Result<HttpResponse, OptionApiError> {
+    let mut transaction = db_pool
+        .begin()
+        .await
+        .context("Failed to begin transaction")?;
+    let user_queried =
+        select_user_from_db(&mut transaction, params.user as u64).await?;
+    require_user_or_project_master_or_not_found(
+        &user,
+        params.user,
+        user_queried.project,
+    )?;
+    let underquota = check_flavor_quota_for_user(
+        &mut transaction,
+        params.user as u64,
+        params.flavor as u64,
+        params.flavorcount.unwrap_or(1),
+    )
+    .await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit transaction")?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(FlavorQuotaCheck { underquota }))
+}
@@ -1,13 +1,16 @@
+use std::collections::HashSet;
+
 use actix_web::{
     HttpResponse,
-    web::{Data, ReqData},
+    web::{Data, Query, ReqData},
 };
-use anyhow::Context;
 use avina_wire::{
-    resources::{FlavorCreateData, FlavorImport},
+    resources::{
+        FlavorCreateData, FlavorImport, FlavorImportChange, FlavorImportParams,
+    },
     user::User,
 };
-use sqlx::MySqlPool;
+use sqlx::{MySql, MySqlPool, Transaction};
 
 use crate::{
     authorization::require_admin_user,
@@ -16,47 +19,64 @@ use crate::{
     },
     error::NormalApiError,
     openstack::OpenStack,
+    scheduler::ImportScheduler,
 };
 
-#[tracing::instrument(name = "flavor_import", skip(openstack))]
+#[tracing::instrument(
+    name = "flavor_import",
+    skip(openstack, import_scheduler)
+)]
 pub async fn flavor_import(
     user: ReqData<User>,
     db_pool: Data<MySqlPool>,
     openstack: Data<OpenStack>,
+    import_scheduler: Data<ImportScheduler>,
+    params: Query<FlavorImportParams>,
     // TODO: is the ValidationError variant ever used?
 ) -> Result<HttpResponse, NormalApiError> {
     require_admin_user(&user)?;
-    let mut transaction = db_pool
-        .begin()
-        .await
-        .context("Failed to begin transaction")?;
-    let existing_flavor_names = select_all_flavors_from_db(&mut transaction)
-        .await?
-        .iter()
-        .map(|f| f.name.clone())
-        .collect::<Vec<_>>();
-    let new_flavors = openstack
-        .get_flavors()
+    let flavor_import = import_scheduler
+        .run_flavor_import(
+            &db_pool,
+            &openstack,
+            params.dry_run.unwrap_or(false),
+        )
+        .await?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(flavor_import))
+}
+
+#[tracing::instrument(name = "import_flavors", skip(transaction, openstack))]
+pub async fn import_flavors(
+    transaction: &mut Transaction<'_, MySql>,
+    openstack: &OpenStack,
+) -> Result<FlavorImport, NormalApiError> {
+    let existing_flavor_names = select_all_flavors_from_db(transaction)
         .await?
         .into_iter()
-        .filter(|f| !existing_flavor_names.contains(&f.name))
-        .collect::<Vec<_>>();
-    let new_flavor_count = new_flavors.len() as u32;
-    for flavor in new_flavors {
+        .map(|f| f.name)
+        .collect::<HashSet<_>>();
+    let mut new_flavors = Vec::new();
+    for flavor in openstack.get_flavors().await? {
+        if existing_flavor_names.contains(&flavor.name) {
+            continue;
+        }
         let data = FlavorCreateData {
             name: flavor.name.clone(),
             openstack_id: flavor.id.clone(),
             group: None,
             weight: None,
         };
-        let _ = insert_flavor_into_db(&mut transaction, &data).await?;
+        let _ = insert_flavor_into_db(transaction, &data).await?;
+        new_flavors.push(FlavorImportChange {
+            name: flavor.name,
+            openstack_id: flavor.id,
+        });
     }
-    transaction
-        .commit()
-        .await
-        .context("Failed to commit transaction")?;
-    let flavor_import = FlavorImport { new_flavor_count };
-    Ok(HttpResponse::Ok()
-        .content_type("application/json")
-        .json(flavor_import))
+    Ok(FlavorImport {
+        new_flavor_count: new_flavors.len() as u32,
+        dry_run: false,
+        new_flavors,
+    })
 }
@@ -10,12 +10,14 @@ mod list;
 use list::flavor_list;
 mod get;
 use get::flavor_get;
-mod modify;
+pub(crate) mod modify;
 use modify::flavor_modify;
 mod delete;
 use delete::flavor_delete;
-mod import;
+pub(crate) mod import;
 use import::flavor_import;
+pub(crate) mod usage;
+use usage::flavor_usage;
 
 pub fn flavors_scope() -> Scope {
     scope("/flavors")
@@ -26,6 +28,7 @@ pub fn flavors_scope() -> Scope {
         .route("/{flavor_id}/", patch().to(flavor_modify))
         .route("/{flavor_id}/", delete().to(flavor_delete))
         .route("/import/", get().to(flavor_import))
+        .route("/usage/", get().to(flavor_usage))
 }
 
 // TODO: wouldn't a general IdParam be better?
@@ -0,0 +1,165 @@
+use std::collections::{BTreeMap, HashMap};
+
+use actix_web::{
+    HttpResponse,
+    web::{Data, Query, ReqData},
+};
+use anyhow::Context;
+use avina_wire::{
+    accounting::ServerState,
+    resources::{Flavor, FlavorUsage, FlavorUsageAggregate, FlavorUsageParams},
+    user::User,
+};
+use sqlx::{MySql, MySqlPool, Transaction};
+
+use crate::{
+    authorization::{
+        require_admin_user, require_master_user_or_return_not_found,
+        require_user_or_project_master_or_not_found,
+    },
+    database::{
+        accounting::server_state::{
+            select_unfinished_server_states_by_project_from_db,
+            select_unfinished_server_states_by_user_from_db,
+            select_unfinished_server_states_from_db,
+        },
+        resources::flavor::select_all_flavors_from_db,
+        user::user::select_user_from_db,
+    },
+    error::{OptionApiError, UnexpectedOnlyError},
+};
+
+async fn calculate_flavor_usage_for_states(
+    transaction: &mut Transaction<'_, MySql>,
+    states: Vec<ServerState>,
+) -> Result<Vec<FlavorUsage>, UnexpectedOnlyError> {
+    let flavors = select_all_flavors_from_db(transaction)
+        .await?
+        .into_iter()
+        .map(|f| (f.id, f))
+        .collect::<HashMap<u32, Flavor>>();
+
+    let mut counts: BTreeMap<(u32, u32), (String, u32)> = BTreeMap::new();
+    for state in states {
+        counts
+            .entry((state.user, state.flavor))
+            .or_insert((state.username, 0))
+            .1 += 1;
+    }
+
+    let mut usages = Vec::new();
+    for ((user_id, flavor_id), (user_name, count)) in counts {
+        let Some(flavor) = flavors.get(&flavor_id) else {
+            continue;
+        };
+        usages.push(FlavorUsage {
+            user_id,
+            user_name,
+            flavor_id,
+            flavor_name: flavor.name.clone(),
+            flavorgroup_id: flavor.group,
+            flavorgroup_name: flavor.group_name.clone(),
+            count,
+            usage: count * flavor.weight,
+        });
+    }
+    Ok(usages)
+}
+
+pub async fn calculate_flavor_usage_for_user(
+    transaction: &mut Transaction<'_, MySql>,
+    user_id: u64,
+) -> Result<Vec<FlavorUsage>, UnexpectedOnlyError> {
+    let states =
+        select_unfinished_server_states_by_user_from_db(transaction, user_id)
+            .await?;
+    calculate_flavor_usage_for_states(transaction, states).await
+}
+
+pub async fn calculate_flavor_usage_for_project(
+    transaction: &mut Transaction<'_, MySql>,
+    project_id: u64,
+) -> Result<Vec<FlavorUsage>, UnexpectedOnlyError> {
+    let states = select_unfinished_server_states_by_project_from_db(
+        transaction,
+        project_id,
+    )
+    .await?;
+    calculate_flavor_usage_for_states(transaction, states).await
+}
+
+pub async fn calculate_flavor_usage_for_all(
+    transaction: &mut Transaction<'_, MySql>,
+) -> Result<Vec<FlavorUsage>, UnexpectedOnlyError> {
+    let states = select_unfinished_server_states_from_db(transaction).await?;
+    calculate_flavor_usage_for_states(transaction, states).await
+}
+
+pub fn aggregate_flavor_usage(
+    usages: Vec<FlavorUsage>,
+) -> Vec<FlavorUsageAggregate> {
+    let mut aggregates: BTreeMap<u32, FlavorUsageAggregate> = BTreeMap::new();
+    for usage in usages {
+        let aggregate =
+            aggregates
+                .entry(usage.flavor_id)
+                .or_insert(FlavorUsageAggregate {
+                    flavor_id: usage.flavor_id,
+                    flavor_name: usage.flavor_name,
+                    flavorgroup_id: usage.flavorgroup_id,
+                    flavorgroup_name: usage.flavorgroup_name,
+                    count: 0,
+                    usage: 0,
+                });
+        aggregate.count += usage.count;
+        aggregate.usage += usage.usage;
+    }
+    aggregates.into_values().collect()
+}
+
+#[tracing::instrument(name = "flavor_usage")]
+pub async fn flavor_usage(
+    user: ReqData<User>,
+    db_pool: Data<MySqlPool>,
+    params: Query<FlavorUsageParams>,
+    // TODO: is the ValidationError variant ever used?
+) -> Result<HttpResponse, OptionApiError> {
+    let mut transaction = db_pool
+        .begin()
+        .await
+        .context("Failed to begin transaction")?;
+    let usages = if let Some(user_id) = params.user {
+        let user_queried =
+            select_user_from_db(&mut transaction, user_id as u64).await?;
+        require_user_or_project_master_or_not_found(
+            &user,
+            user_id,
+            user_queried.project,
+        )?;
+        calculate_flavor_usage_for_user(&mut transaction, user_id as u64)
+            .await?
+    } else if let Some(project_id) = params.project {
+        require_master_user_or_return_not_found(&user, project_id)?;
+        calculate_flavor_usage_for_project(&mut transaction, project_id as u64)
+            .await?
+    } else if params.all.unwrap_or(false) {
+        require_admin_user(&user)?;
+        calculate_flavor_usage_for_all(&mut transaction).await?
+    } else {
+        calculate_flavor_usage_for_user(&mut transaction, user.id as u64)
+            .await?
+    };
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit transaction")?;
+    if params.aggregate.unwrap_or(false) {
+        Ok(HttpResponse::Ok()
+            .content_type("application/json")
+            .json(aggregate_flavor_usage(usages)))
+    } else {
+        Ok(HttpResponse::Ok()
+            .content_type("application/json")
+            .json(usages))
+    }
+}
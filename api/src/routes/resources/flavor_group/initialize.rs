@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+use actix_web::{
+    HttpResponse,
+    web::{Data, ReqData},
+};
+use anyhow::Context;
+use avina_wire::{
+    resources::{
+        FlavorGroupCreateData, FlavorGroupInitialize, FlavorModifyData,
+    },
+    user::User,
+};
+use sqlx::MySqlPool;
+
+use crate::{
+    authorization::require_admin_user,
+    database::resources::{
+        flavor::select_all_flavors_from_db,
+        flavor_group::{
+            insert_flavor_group_into_db,
+            select_maybe_flavor_group_id_by_name_from_db,
+        },
+    },
+    error::OptionApiError,
+    openstack::OpenStack,
+    routes::resources::flavor::modify::update_flavor_in_db,
+};
+
+// one weight unit corresponds to one vCPU or this much RAM, whichever is more
+const RAM_MIB_PER_WEIGHT: u32 = 4096;
+
+/// Derive the default flavor group name from a flavor name, i.e. the prefix
+/// up to and including the first dot, e.g. `lrz.` for `lrz.medium`.
+fn flavor_group_name(flavor_name: &str) -> Option<String> {
+    let (prefix, _) = flavor_name.split_once('.')?;
+    if prefix.is_empty() {
+        return None;
+    }
+    Some(format!("{prefix}."))
+}
+
+fn flavor_weight(vcpus: u32, ram: u32) -> u32 {
+    vcpus.max(ram.div_ceil(RAM_MIB_PER_WEIGHT)).max(1)
+}
+
+#[tracing::instrument(name = "flavor_group_initialize", skip(openstack))]
+pub async fn flavor_group_initialize(
+    user: ReqData<User>,
+    db_pool: Data<MySqlPool>,
+    openstack: Data<OpenStack>,
+) -> Result<HttpResponse, OptionApiError> {
+    require_admin_user(&user)?;
+    let openstack_flavors = openstack
+        .get_flavors()
+        .await?
+        .into_iter()
+        .map(|f| (f.id.clone(), f))
+        .collect::<HashMap<_, _>>();
+    let mut transaction = db_pool
+        .begin()
+        .await
+        .context("Failed to begin transaction")?;
+    let ungrouped_flavors = select_all_flavors_from_db(&mut transaction)
+        .await?
+        .into_iter()
+        .filter(|f| f.group.is_none())
+        .collect::<Vec<_>>();
+    let mut group_ids: HashMap<String, u32> = HashMap::new();
+    let mut new_flavor_group_count = 0;
+    let mut new_flavor_count = 0;
+    for flavor in ungrouped_flavors {
+        let Some(group_name) = flavor_group_name(&flavor.name) else {
+            continue;
+        };
+        let Some(openstack_flavor) =
+            openstack_flavors.get(&flavor.openstack_id)
+        else {
+            continue;
+        };
+        let group_id = match group_ids.get(&group_name) {
+            Some(group_id) => *group_id,
+            None => {
+                let group_id =
+                    match select_maybe_flavor_group_id_by_name_from_db(
+                        &mut transaction,
+                        &group_name,
+                    )
+                    .await?
+                    {
+                        Some(group_id) => group_id,
+                        None => {
+                            new_flavor_group_count += 1;
+                            insert_flavor_group_into_db(
+                                &mut transaction,
+                                &FlavorGroupCreateData::new(group_name.clone()),
+                                user.project as u64,
+                            )
+                            .await? as u32
+                        }
+                    };
+                group_ids.insert(group_name, group_id);
+                group_id
+            }
+        };
+        let mut data = FlavorModifyData::new(flavor.id);
+        data.group = Some(Some(group_id));
+        data.weight =
+            Some(flavor_weight(openstack_flavor.vcpus, openstack_flavor.ram));
+        update_flavor_in_db(&mut transaction, &data).await?;
+        new_flavor_count += 1;
+    }
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit transaction")?;
+    let flavor_group_initialize = FlavorGroupInitialize {
+        new_flavor_group_count,
+        new_flavor_count,
+    };
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(flavor_group_initialize))
+}
@@ -0,0 +1,145 @@
+use std::collections::BTreeMap;
+
+use actix_web::{
+    HttpResponse,
+    web::{Data, Query, ReqData},
+};
+use anyhow::Context;
+use avina_wire::{
+    resources::{
+        FlavorGroupUsage, FlavorGroupUsageAggregate, FlavorGroupUsageParams,
+        FlavorUsage,
+    },
+    user::User,
+};
+use sqlx::{MySql, MySqlPool, Transaction};
+
+use crate::{
+    authorization::{
+        require_admin_user, require_master_user_or_return_not_found,
+        require_user_or_project_master_or_not_found,
+    },
+    database::user::user::select_user_from_db,
+    error::{OptionApiError, UnexpectedOnlyError},
+    routes::resources::flavor::usage::{
+        calculate_flavor_usage_for_all, calculate_flavor_usage_for_project,
+        calculate_flavor_usage_for_user,
+    },
+};
+
+fn flavor_group_usage_from_flavor_usage(
+    usages: Vec<FlavorUsage>,
+) -> Vec<FlavorGroupUsage> {
+    let mut group_usages: BTreeMap<(u32, u32), FlavorGroupUsage> =
+        BTreeMap::new();
+    for usage in usages {
+        // flavors without a group do not count towards any group
+        let (Some(flavorgroup_id), Some(flavorgroup_name)) =
+            (usage.flavorgroup_id, usage.flavorgroup_name)
+        else {
+            continue;
+        };
+        group_usages
+            .entry((usage.user_id, flavorgroup_id))
+            .or_insert(FlavorGroupUsage {
+                user_id: usage.user_id,
+                user_name: usage.user_name,
+                flavorgroup_id,
+                flavorgroup_name,
+                usage: 0,
+            })
+            .usage += usage.usage;
+    }
+    group_usages.into_values().collect()
+}
+
+pub async fn calculate_flavor_group_usage_for_user(
+    transaction: &mut Transaction<'_, MySql>,
+    user_id: u64,
+) -> Result<Vec<FlavorGroupUsage>, UnexpectedOnlyError> {
+    let usages = calculate_flavor_usage_for_user(transaction, user_id).await?;
+    Ok(flavor_group_usage_from_flavor_usage(usages))
+}
+
+pub async fn calculate_flavor_group_usage_for_project(
+    transaction: &mut Transaction<'_, MySql>,
+    project_id: u64,
+) -> Result<Vec<FlavorGroupUsage>, UnexpectedOnlyError> {
+    let usages =
+        calculate_flavor_usage_for_project(transaction, project_id).await?;
+    Ok(flavor_group_usage_from_flavor_usage(usages))
+}
+
+pub async fn calculate_flavor_group_usage_for_all(
+    transaction: &mut Transaction<'_, MySql>,
+) -> Result<Vec<FlavorGroupUsage>, UnexpectedOnlyError> {
+    let usages = calculate_flavor_usage_for_all(transaction).await?;
+    Ok(flavor_group_usage_from_flavor_usage(usages))
+}
+
+pub fn aggregate_flavor_group_usage(
+    usages: Vec<FlavorGroupUsage>,
+) -> Vec<FlavorGroupUsageAggregate> {
+    let mut aggregates: BTreeMap<u32, FlavorGroupUsageAggregate> =
+        BTreeMap::new();
+    for usage in usages {
+        aggregates
+            .entry(usage.flavorgroup_id)
+            .or_insert(FlavorGroupUsageAggregate {
+                flavorgroup_id: usage.flavorgroup_id,
+                flavorgroup_name: usage.flavorgroup_name,
+                usage: 0,
+            })
+            .usage += usage.usage;
+    }
+    aggregates.into_values().collect()
+}
+
+#[tracing::instrument(name = "flavor_group_usage")]
+pub async fn flavor_group_usage(
+    user: ReqData<User>,
+    db_pool: Data<MySqlPool>,
+    params: Query<FlavorGroupUsageParams>,
+) -> Result<HttpResponse, OptionApiError> {
+    let mut transaction = db_pool
+        .begin()
+        .await
+        .context("Failed to begin transaction")?;
+    let usages = if let Some(user_id) = params.user {
+        let user_queried =
+            select_user_from_db(&mut transaction, user_id as u64).await?;
+        require_user_or_project_master_or_not_found(
+            &user,
+            user_id,
+            user_queried.project,
+        )?;
+        calculate_flavor_group_usage_for_user(&mut transaction, user_id as u64)
+            .await?
+    } else if let Some(project_id) = params.project {
+        require_master_user_or_return_not_found(&user, project_id)?;
+        calculate_flavor_group_usage_for_project(
+            &mut transaction,
+            project_id as u64,
+        )
+        .await?
+    } else if params.all.unwrap_or(false) {
+        require_admin_user(&user)?;
+        calculate_flavor_group_usage_for_all(&mut transaction).await?
+    } else {
+        calculate_flavor_group_usage_for_user(&mut transaction, user.id as u64)
+            .await?
+    };
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit transaction")?;
+    if params.aggregate.unwrap_or(false) {
+        Ok(HttpResponse::Ok()
+            .content_type("application/json")
+            .json(aggregate_flavor_group_usage(usages)))
+    } else {
+        Ok(HttpResponse::Ok()
+            .content_type("application/json")
+            .json(usages))
+    }
+}
@@ -1,8 +1,8 @@
 use actix_web::{Scope, web::scope};
 
-mod flavor_group;
+pub(crate) mod flavor_group;
 use flavor_group::flavor_groups_scope;
-mod flavor;
+pub(crate) mod flavor;
 use flavor::flavors_scope;
 
 pub fn resources_scope() -> Scope {
@@ -0,0 +1,150 @@
+use std::collections::{HashMap, HashSet};
+
+use actix_web::{
+    HttpResponse,
+    web::{Data, ReqData},
+};
+use anyhow::Context;
+use avina_wire::user::{ProjectModifyData, User, UserImport, UserModifyData};
+use sqlx::MySqlPool;
+
+use crate::{
+    authorization::require_admin_user,
+    configuration::BillingSettings,
+    database::user::{
+        project::{
+            select_maybe_project_by_openstack_id_from_db,
+            select_project_from_db,
+        },
+        user::{select_all_users_from_db, select_users_by_project_from_db},
+    },
+    error::OptionApiError,
+    openstack::OpenStack,
+    routes::user::{
+        project::{
+            create::{NewProject, insert_project_into_db},
+            modify::update_project_in_db,
+        },
+        user::{
+            create::{NewUser, insert_user_into_db},
+            modify::update_user_in_db,
+        },
+    },
+};
+
+#[tracing::instrument(name = "user_import", skip(openstack))]
+pub async fn user_import(
+    user: ReqData<User>,
+    db_pool: Data<MySqlPool>,
+    billing: Data<BillingSettings>,
+    openstack: Data<OpenStack>,
+) -> Result<HttpResponse, OptionApiError> {
+    require_admin_user(&user)?;
+    // the configured domain corresponds to a project, and the OpenStack
+    // projects within that domain correspond to the users of that project
+    let domain = openstack.get_domain().await?;
+    let os_projects = openstack
+        .get_projects()
+        .await?
+        .into_iter()
+        .filter(|p| !p.is_domain && p.enabled)
+        .collect::<Vec<_>>();
+    let mut transaction = db_pool
+        .begin()
+        .await
+        .context("Failed to begin transaction")?;
+
+    let mut user_import = UserImport {
+        new_project_count: 0,
+        new_user_count: 0,
+        updated_project_count: 0,
+        updated_user_count: 0,
+        deactivated_user_count: 0,
+    };
+
+    let project = match select_maybe_project_by_openstack_id_from_db(
+        &mut transaction,
+        &domain.id,
+    )
+    .await?
+    {
+        Some(project) if project.name != domain.name => {
+            user_import.updated_project_count += 1;
+            let mut data = ProjectModifyData::new(project.id);
+            data.name = Some(domain.name.clone());
+            update_project_in_db(&mut transaction, &billing, &data).await?
+        }
+        Some(project) => project,
+        None => {
+            user_import.new_project_count += 1;
+            let new_project = NewProject {
+                name: domain.name.clone(),
+                openstack_id: domain.id.clone(),
+                user_class: 1,
+            };
+            let project_id =
+                insert_project_into_db(&mut transaction, &new_project).await?;
+            select_project_from_db(&mut transaction, project_id).await?
+        }
+    };
+
+    let users = select_all_users_from_db(&mut transaction)
+        .await?
+        .into_iter()
+        .map(|u| (u.openstack_id.clone(), u))
+        .collect::<HashMap<_, _>>();
+    for os_project in os_projects.iter() {
+        match users.get(&os_project.id) {
+            Some(existing) => {
+                if existing.name == os_project.name
+                    && existing.project == project.id
+                    && existing.is_active
+                {
+                    continue;
+                }
+                user_import.updated_user_count += 1;
+                let mut data = UserModifyData::new(existing.id);
+                data.name = Some(os_project.name.clone());
+                data.project = Some(project.id);
+                data.is_active = Some(true);
+                update_user_in_db(&mut transaction, &billing, &data).await?;
+            }
+            None => {
+                user_import.new_user_count += 1;
+                let new_user = NewUser {
+                    name: os_project.name.clone(),
+                    openstack_id: os_project.id.clone(),
+                    project_id: project.id,
+                    role: 1,
+                    is_staff: false,
+                    is_active: true,
+                };
+                insert_user_into_db(&mut transaction, &new_user).await?;
+            }
+        }
+    }
+
+    let os_project_ids =
+        os_projects.iter().map(|p| &p.id).collect::<HashSet<_>>();
+    for existing in
+        select_users_by_project_from_db(&mut transaction, project.id as u64)
+            .await?
+    {
+        if existing.is_active
+            && !os_project_ids.contains(&existing.openstack_id)
+        {
+            user_import.deactivated_user_count += 1;
+            let mut data = UserModifyData::new(existing.id);
+            data.is_active = Some(false);
+            update_user_in_db(&mut transaction, &billing, &data).await?;
+        }
+    }
+
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit transaction")?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(user_import))
+}
@@ -10,7 +10,7 @@ mod list;
 use list::project_list;
 pub mod get;
 use get::project_get;
-mod modify;
+pub mod modify;
 use modify::project_modify;
 mod delete;
 use delete::project_delete;
@@ -9,14 +9,20 @@ use sqlx::{Executor, MySql, MySqlPool, Transaction};
 use super::ProjectIdParam;
 use crate::{
     authorization::require_admin_user,
-    database::user::project::select_project_from_db,
+    configuration::BillingSettings,
+    database::{
+        accounting::server_cost_rollup::ServerCostRollupFilter,
+        user::project::select_project_from_db,
+    },
     error::{NotFoundOrUnexpectedApiError, OptionApiError},
+    routes::accounting::server_cost::rollup::refresh_server_cost_rollups_by_filter,
 };
 
 #[tracing::instrument(name = "project_modify")]
 pub async fn project_modify(
     user: ReqData<User>,
     db_pool: Data<MySqlPool>,
+    billing: Data<BillingSettings>,
     data: Json<ProjectModifyData>,
     params: Path<ProjectIdParam>,
 ) -> Result<HttpResponse, OptionApiError> {
@@ -31,7 +37,8 @@ pub async fn project_modify(
         .begin()
         .await
         .context("Failed to begin transaction")?;
-    let project = update_project_in_db(&mut transaction, &data).await?;
+    let project =
+        update_project_in_db(&mut transaction, &billing, &data).await?;
     transaction
         .commit()
         .await
@@ -44,6 +51,7 @@ pub async fn project_modify(
 #[tracing::instrument(name = "update_project_in_db", skip(data, transaction))]
 pub async fn update_project_in_db(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     data: &ProjectModifyData,
 ) -> Result<Project, NotFoundOrUnexpectedApiError> {
     let row = select_project_from_db(transaction, data.id as u64).await?;
@@ -65,6 +73,15 @@ pub async fn update_project_in_db(
         .execute(query)
         .await
         .context("Failed to execute update query")?;
+    // rolled up costs were calculated with the prices of the old user class
+    if user_class != row.user_class {
+        refresh_server_cost_rollups_by_filter(
+            transaction,
+            billing,
+            ServerCostRollupFilter::Project(data.id as u64),
+        )
+        .await?;
+    }
     let project = Project {
         id: data.id,
         name,
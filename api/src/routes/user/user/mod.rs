@@ -10,7 +10,7 @@ mod list;
 use list::user_list;
 mod get;
 use get::user_get;
-mod modify;
+pub mod modify;
 use modify::user_modify;
 mod delete;
 use delete::user_delete;
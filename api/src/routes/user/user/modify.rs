@@ -9,14 +9,20 @@ use sqlx::{Executor, MySql, MySqlPool, Transaction};
 use super::UserIdParam;
 use crate::{
     authorization::require_admin_user,
-    database::user::user::select_user_from_db,
+    configuration::BillingSettings,
+    database::{
+        accounting::server_cost_rollup::ServerCostRollupFilter,
+        user::user::select_user_from_db,
+    },
     error::{NotFoundOrUnexpectedApiError, OptionApiError},
+    routes::accounting::server_cost::rollup::refresh_server_cost_rollups_by_filter,
 };
 
 #[tracing::instrument(name = "user_modify")]
 pub async fn user_modify(
     user: ReqData<User>,
     db_pool: Data<MySqlPool>,
+    billing: Data<BillingSettings>,
     data: Json<UserModifyData>,
     params: Path<UserIdParam>,
 ) -> Result<HttpResponse, OptionApiError> {
@@ -31,7 +37,7 @@ pub async fn user_modify(
         .begin()
         .await
         .context("Failed to begin transaction")?;
-    let project = update_user_in_db(&mut transaction, &data).await?;
+    let project = update_user_in_db(&mut transaction, &billing, &data).await?;
     transaction
         .commit()
         .await
@@ -44,6 +50,7 @@ pub async fn user_modify(
 #[tracing::instrument(name = "update_user_in_db", skip(data, transaction))]
 pub async fn update_user_in_db(
     transaction: &mut Transaction<'_, MySql>,
+    billing: &BillingSettings,
     data: &UserModifyData,
 ) -> Result<User, NotFoundOrUnexpectedApiError> {
     let row = select_user_from_db(transaction, data.id as u64).await?;
@@ -71,6 +78,15 @@ pub async fn update_user_in_db(
         .execute(query)
         .await
         .context("Failed to execute update query")?;
+    // rolled up costs of the user still belong to the old project
+    if project_id != row.project {
+        refresh_server_cost_rollups_by_filter(
+            transaction,
+            billing,
+            ServerCostRollupFilter::User(data.id as u64),
+        )
+        .await?;
+    }
     let user = User {
         id: data.id,
         name,
@@ -0,0 +1,423 @@
+use std::{sync::Mutex, time::Duration};
+
+use actix_web::web::Data;
+use anyhow::Context;
+use avina_wire::{
+    accounting::{ServerStateImport, ServerStateImportStatus},
+    budgeting::BudgetEnforcementAction,
+    resources::FlavorImport,
+};
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use sqlx::MySqlPool;
+
+use crate::{
+    configuration::{BillingSettings, SchedulerSettings},
+    database::accounting::server_state_import_run::{
+        NewServerStateImportRun, insert_server_state_import_run_into_db,
+        select_maybe_last_successful_import_begin_from_db,
+    },
+    error::{NormalApiError, OptionApiError, UnexpectedOnlyError},
+    openstack::OpenStack,
+    routes::{
+        accounting::{
+            server_cost::rollup::update_server_cost_rollups,
+            server_state::import::import_server_states,
+        },
+        budgeting::{
+            alert::BudgetAlerter, enforcement::enforce::BudgetEnforcer,
+        },
+        resources::flavor::import::import_flavors,
+    },
+};
+
+// The earliest point in time the import changed, rolled up days from then on
+// need to be recalculated.
+fn rollup_refresh_since(
+    server_state_import: &ServerStateImport,
+) -> Option<DateTime<Utc>> {
+    server_state_import
+        .new_states
+        .iter()
+        .map(|state| state.begin)
+        .chain(
+            server_state_import
+                .ended_states
+                .iter()
+                .map(|state| state.end),
+        )
+        .min()
+        .map(|since| since.to_utc())
+}
+
+// Serializes all imports, so that a scheduled and a manually triggered
+// import never run at the same time, and records every run. Budget alerts
+// and, when enabled, budget enforcement are run after each successful
+// import.
+pub struct ImportScheduler {
+    lock: tokio::sync::Mutex<()>,
+    status: Mutex<ServerStateImportStatus>,
+    full_import_every: u32,
+    // number of scheduled imports so far
+    scheduled_import_count: Mutex<u32>,
+    billing: Data<BillingSettings>,
+    budget_alerter: BudgetAlerter,
+    budget_enforcer: BudgetEnforcer,
+}
+
+impl ImportScheduler {
+    pub fn new(
+        settings: &SchedulerSettings,
+        billing: Data<BillingSettings>,
+        budget_alerter: BudgetAlerter,
+        budget_enforcer: BudgetEnforcer,
+    ) -> Self {
+        Self {
+            lock: tokio::sync::Mutex::new(()),
+            full_import_every: settings.full_import_every.max(1),
+            scheduled_import_count: Mutex::new(0),
+            billing,
+            budget_alerter,
+            budget_enforcer,
+            status: Mutex::new(ServerStateImportStatus {
+                scheduled: settings.enabled,
+                running: false,
+                last_begin: None,
+                last_end: None,
+                new_state_count: None,
+                end_state_count: None,
+                new_flavor_count: None,
+                error: None,
+            }),
+        }
+    }
+
+    pub fn status(&self) -> ServerStateImportStatus {
+        let mut status = self.status.lock().unwrap().clone();
+        status.running = self.lock.try_lock().is_err();
+        status
+    }
+
+    async fn record(
+        &self,
+        db_pool: &MySqlPool,
+        begin: DateTime<Utc>,
+        flavor_import: Option<&FlavorImport>,
+        server_state_import: Option<&ServerStateImport>,
+        error: Option<String>,
+    ) {
+        let end = Utc::now();
+        {
+            let mut status = self.status.lock().unwrap();
+            status.last_begin = Some(begin.fixed_offset());
+            status.last_end = Some(end.fixed_offset());
+            status.new_flavor_count = flavor_import.map(|f| f.new_flavor_count);
+            status.new_state_count =
+                server_state_import.map(|s| s.new_state_count);
+            status.end_state_count =
+                server_state_import.map(|s| s.end_state_count);
+            status.error.clone_from(&error);
+        }
+        let import_run = NewServerStateImportRun {
+            begin,
+            end,
+            new_state_count: server_state_import
+                .map(|s| s.new_state_count)
+                .unwrap_or(0),
+            end_state_count: server_state_import
+                .map(|s| s.end_state_count)
+                .unwrap_or(0),
+            new_flavor_count: flavor_import.map(|f| f.new_flavor_count),
+            error,
+            skipped: server_state_import
+                .map(|s| s.skipped.clone())
+                .unwrap_or_default(),
+        };
+        let result = async {
+            let mut transaction = db_pool
+                .begin()
+                .await
+                .context("Failed to begin transaction")?;
+            insert_server_state_import_run_into_db(
+                &mut transaction,
+                &import_run,
+            )
+            .await?;
+            transaction
+                .commit()
+                .await
+                .context("Failed to commit transaction")?;
+            Ok::<_, UnexpectedOnlyError>(())
+        }
+        .await;
+        if let Err(error) = result {
+            tracing::error!(
+                "Failed to record server state import run: {error:?}"
+            );
+        }
+    }
+
+    async fn evaluate_budget_alerts(&self, db_pool: &MySqlPool) {
+        match self.budget_alerter.evaluate(db_pool, &self.billing).await {
+            Ok(alerts) if !alerts.is_empty() => {
+                tracing::info!("Sent {} budget alerts.", alerts.len())
+            }
+            Ok(_) => {}
+            Err(error) => {
+                tracing::error!("Failed to evaluate budget alerts: {error:?}")
+            }
+        }
+    }
+
+    async fn enforce_budgets(
+        &self,
+        db_pool: &MySqlPool,
+        openstack: &OpenStack,
+    ) {
+        if !self.budget_enforcer.is_enabled() {
+            return;
+        }
+        let dry_run = self.budget_enforcer.is_dry_run(None);
+        match self
+            .budget_enforcer
+            .enforce(db_pool, openstack, &self.billing, dry_run)
+            .await
+        {
+            Ok(actions) => {
+                for action in actions.iter() {
+                    tracing::info!(
+                        "Budget enforcement{}: {} server {} of user {}{}",
+                        if action.dry_run { " (dry run)" } else { "" },
+                        action.action,
+                        action.server_id,
+                        action.user_name,
+                        match &action.error {
+                            Some(error) => format!(" failed: {error}"),
+                            None => "".to_string(),
+                        }
+                    );
+                }
+            }
+            Err(error) => {
+                tracing::error!("Failed to enforce budgets: {error:?}")
+            }
+        }
+    }
+
+    // a real run requires the enforcement to be enabled, a dry run does not
+    pub async fn run_budget_enforcement(
+        &self,
+        db_pool: &MySqlPool,
+        openstack: &OpenStack,
+        dry_run: Option<bool>,
+    ) -> Result<Vec<BudgetEnforcementAction>, OptionApiError> {
+        let dry_run = self.budget_enforcer.is_dry_run(dry_run);
+        if !dry_run && !self.budget_enforcer.is_enabled() {
+            return Err(OptionApiError::ValidationError(
+                "Budget enforcement is disabled".to_string(),
+            ));
+        }
+        let _guard = self.lock.lock().await;
+        Ok(self
+            .budget_enforcer
+            .enforce(db_pool, openstack, &self.billing, dry_run)
+            .await?)
+    }
+
+    // manual flavor imports are not recorded in the import history, but
+    // still must not run at the same time as a scheduled import
+    pub async fn run_flavor_import(
+        &self,
+        db_pool: &MySqlPool,
+        openstack: &OpenStack,
+        dry_run: bool,
+    ) -> Result<FlavorImport, NormalApiError> {
+        let _guard = self.lock.lock().await;
+        let mut transaction = db_pool
+            .begin()
+            .await
+            .context("Failed to begin transaction")?;
+        let mut flavor_import =
+            import_flavors(&mut transaction, openstack).await?;
+        if dry_run {
+            flavor_import.dry_run = true;
+            transaction
+                .rollback()
+                .await
+                .context("Failed to rollback transaction")?;
+        } else {
+            transaction
+                .commit()
+                .await
+                .context("Failed to commit transaction")?;
+        }
+        Ok(flavor_import)
+    }
+
+    pub async fn run_server_state_import(
+        &self,
+        db_pool: &MySqlPool,
+        openstack: &OpenStack,
+        dry_run: bool,
+    ) -> Result<ServerStateImport, OptionApiError> {
+        let _guard = self.lock.lock().await;
+        let begin = Utc::now();
+        let result = async {
+            let mut transaction = db_pool
+                .begin()
+                .await
+                .context("Failed to begin transaction")?;
+            let mut server_state_import =
+                import_server_states(&mut transaction, openstack, None).await?;
+            if dry_run {
+                server_state_import.dry_run = true;
+                transaction
+                    .rollback()
+                    .await
+                    .context("Failed to rollback transaction")?;
+            } else {
+                update_server_cost_rollups(
+                    &mut transaction,
+                    &self.billing,
+                    rollup_refresh_since(&server_state_import),
+                )
+                .await?;
+                transaction
+                    .commit()
+                    .await
+                    .context("Failed to commit transaction")?;
+            }
+            Ok::<_, OptionApiError>(server_state_import)
+        }
+        .await;
+        // a dry run changes nothing, so it is not part of the history
+        if dry_run {
+            return result;
+        }
+        match &result {
+            Ok(server_state_import) => {
+                self.record(
+                    db_pool,
+                    begin,
+                    None,
+                    Some(server_state_import),
+                    None,
+                )
+                .await;
+                self.evaluate_budget_alerts(db_pool).await;
+                self.enforce_budgets(db_pool, openstack).await;
+            }
+            Err(error) => {
+                self.record(db_pool, begin, None, None, Some(error.to_string()))
+                    .await
+            }
+        }
+        result
+    }
+
+    async fn run_scheduled_import(
+        &self,
+        db_pool: &MySqlPool,
+        openstack: &OpenStack,
+    ) {
+        let Ok(_guard) = self.lock.try_lock() else {
+            tracing::info!("Import already running, skipping scheduled run.");
+            return;
+        };
+        let begin = Utc::now();
+        let full_import = {
+            let mut count = self.scheduled_import_count.lock().unwrap();
+            let full_import = count.is_multiple_of(self.full_import_every);
+            *count += 1;
+            full_import
+        };
+        let result = async {
+            let mut transaction = db_pool
+                .begin()
+                .await
+                .context("Failed to begin transaction")?;
+            let flavor_import = import_flavors(&mut transaction, openstack)
+                .await
+                .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+            let changes_since = if full_import {
+                None
+            } else {
+                select_maybe_last_successful_import_begin_from_db(
+                    &mut transaction,
+                )
+                .await?
+            };
+            let server_state_import = import_server_states(
+                &mut transaction,
+                openstack,
+                changes_since,
+            )
+            .await
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+            update_server_cost_rollups(
+                &mut transaction,
+                &self.billing,
+                rollup_refresh_since(&server_state_import),
+            )
+            .await
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+            transaction
+                .commit()
+                .await
+                .context("Failed to commit transaction")?;
+            Ok::<_, anyhow::Error>((flavor_import, server_state_import))
+        }
+        .await;
+        match result {
+            Ok((flavor_import, server_state_import)) => {
+                tracing::info!(
+                    "Scheduled import finished: {} new flavors, {} new and {} ended server states.",
+                    flavor_import.new_flavor_count,
+                    server_state_import.new_state_count,
+                    server_state_import.end_state_count
+                );
+                self.record(
+                    db_pool,
+                    begin,
+                    Some(&flavor_import),
+                    Some(&server_state_import),
+                    None,
+                )
+                .await;
+                self.evaluate_budget_alerts(db_pool).await;
+                self.enforce_budgets(db_pool, openstack).await;
+            }
+            Err(error) => {
+                tracing::error!("Scheduled import failed: {error:?}");
+                self.record(
+                    db_pool,
+                    begin,
+                    None,
+                    None,
+                    Some(error.to_string()),
+                )
+                .await;
+            }
+        }
+    }
+}
+
+pub fn spawn_import_scheduler(
+    import_scheduler: Data<ImportScheduler>,
+    db_pool: MySqlPool,
+    openstack: Data<OpenStack>,
+    settings: SchedulerSettings,
+) {
+    tokio::spawn(async move {
+        loop {
+            let jitter = rand::rng().random_range(0..=settings.jitter_seconds);
+            tokio::time::sleep(Duration::from_secs(
+                settings.interval_seconds + jitter,
+            ))
+            .await;
+            import_scheduler
+                .run_scheduled_import(&db_pool, &openstack)
+                .await;
+        }
+    });
+}
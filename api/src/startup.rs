@@ -10,18 +10,25 @@ use tracing_actix_web::TracingLogger;
 
 use crate::{
     authentication::{extract_user_and_project, require_valid_token},
-    configuration::{DatabaseSettings, Settings},
+    configuration::{
+        BillingSettings, DatabaseSettings, PricingSettings, Settings,
+    },
     error::{MinimalApiError, not_found},
     openstack::OpenStack,
     routes::{
-        accounting_scope, budgeting_scope, health_check, hello_scope,
-        pricing_scope, quota_scope, resources_scope,
+        accounting_scope,
+        budgeting::{
+            alert::BudgetAlerter, enforcement::enforce::BudgetEnforcer,
+        },
+        budgeting_scope, health_check, hello_scope, pricing_scope, quota_scope,
+        resources_scope,
         user::{
             project::create::{NewProject, insert_project_into_db},
             user::create::{NewUser, insert_user_into_db},
         },
         user_scope,
     },
+    scheduler::{ImportScheduler, spawn_import_scheduler},
 };
 
 pub struct Application {
@@ -43,13 +50,34 @@ impl Application {
             Self::insert_admin_user(&connection_pool, &configuration).await?;
         }
 
-        let openstack = OpenStack::new(configuration.openstack).await?;
+        let openstack =
+            Data::new(OpenStack::new(configuration.openstack).await?);
+        let budget_alerter = BudgetAlerter::new(&configuration.alerts)?;
+        let budget_enforcer = BudgetEnforcer::new(configuration.enforcement);
+        let billing = Data::new(configuration.billing);
+        let import_scheduler = Data::new(ImportScheduler::new(
+            &configuration.scheduler,
+            billing.clone(),
+            budget_alerter,
+            budget_enforcer,
+        ));
+        if configuration.scheduler.enabled {
+            spawn_import_scheduler(
+                import_scheduler.clone(),
+                connection_pool.clone(),
+                openstack.clone(),
+                configuration.scheduler,
+            );
+        }
 
         let server = run(
             listener,
             connection_pool,
             configuration.application.base_url,
             openstack,
+            configuration.pricing,
+            billing,
+            import_scheduler,
         )
         .await?;
 
@@ -121,11 +149,14 @@ async fn run(
     listener: TcpListener,
     db_pool: MySqlPool,
     base_url: String,
-    openstack: OpenStack,
+    openstack: Data<OpenStack>,
+    pricing: PricingSettings,
+    billing: Data<BillingSettings>,
+    import_scheduler: Data<ImportScheduler>,
 ) -> Result<Server, anyhow::Error> {
     let db_pool = Data::new(db_pool);
     let base_url = Data::new(ApplicationBaseUrl(base_url));
-    let openstack = Data::new(openstack);
+    let pricing = Data::new(pricing);
     let server = HttpServer::new(move || {
         // TODO: this should be configurable
         let cors = Cors::default()
@@ -139,6 +170,9 @@ async fn run(
             .app_data(db_pool.clone())
             .app_data(base_url.clone())
             .app_data(openstack.clone())
+            .app_data(pricing.clone())
+            .app_data(billing.clone())
+            .app_data(import_scheduler.clone())
             .route("/health_check", web::get().to(health_check))
             .service(
                 web::scope("/api")
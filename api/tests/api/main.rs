@@ -1,2 +1,3 @@
 mod health_check;
 mod hello;
+mod openstack;
@@ -0,0 +1,188 @@
+use avina_api::{configuration::get_configuration, openstack::OpenStack};
+use avina_test::{nova_server_json, random_uuid};
+use avina_wire::{resources::Flavor, user::User};
+use chrono::{TimeDelta, Utc};
+use serde_json::json;
+use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{method, path, query_param, query_param_is_missing},
+};
+
+async fn setup_openstack(page_size: u32) -> (MockServer, OpenStack) {
+    let nova_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/auth/tokens/"))
+        .respond_with(
+            ResponseTemplate::new(201)
+                .append_header("X-Subject-Token", random_uuid()),
+        )
+        .mount(&nova_server)
+        .await;
+    let mut settings = get_configuration()
+        .expect("Failed to read configuration.")
+        .openstack;
+    settings.keystone_endpoint = nova_server.uri();
+    settings.nova_endpoint = nova_server.uri();
+    settings.server_page_size = page_size;
+    let openstack = OpenStack::new(settings)
+        .await
+        .expect("Failed to create OpenStack client.");
+    (nova_server, openstack)
+}
+
+fn test_flavor_and_user() -> (Flavor, User) {
+    let flavor = Flavor {
+        id: 1,
+        name: "lrz.small".to_string(),
+        openstack_id: random_uuid(),
+        group: None,
+        group_name: None,
+        weight: 1,
+    };
+    let user = User {
+        id: 1,
+        name: "user".to_string(),
+        openstack_id: random_uuid().replace('-', ""),
+        project: 1,
+        project_name: "project".to_string(),
+        role: 1,
+        is_staff: false,
+        is_active: true,
+    };
+    (flavor, user)
+}
+
+#[tokio::test]
+async fn get_servers_follows_servers_links() {
+    // arrange
+    let (nova_server, openstack) = setup_openstack(2).await;
+    let (flavor, user) = test_flavor_and_user();
+    let ids = (0..3).map(|_| random_uuid()).collect::<Vec<_>>();
+    let server_json =
+        |id: &str| nova_server_json(id, "test", "ACTIVE", &flavor, &user, None);
+    Mock::given(method("GET"))
+        .and(path("/v2.1/servers/detail"))
+        .and(query_param("limit", "2"))
+        .and(query_param_is_missing("marker"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "servers": [server_json(&ids[0]), server_json(&ids[1])],
+            "servers_links": [{
+                "href": format!(
+                    "https://nova.example.com/v2.1/servers/detail?all_tenants=True&limit=2&marker={}",
+                    ids[1]
+                ),
+                "rel": "next",
+            }],
+        })))
+        .expect(1)
+        .mount(&nova_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v2.1/servers/detail"))
+        .and(query_param("limit", "2"))
+        .and(query_param("marker", ids[1].as_str()))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "servers": [server_json(&ids[2])],
+        })))
+        .expect(1)
+        .mount(&nova_server)
+        .await;
+
+    // act
+    let servers = openstack.get_servers().await.unwrap();
+
+    // assert
+    assert_eq!(servers.into_iter().map(|s| s.id).collect::<Vec<_>>(), ids);
+}
+
+#[tokio::test]
+async fn get_servers_stops_on_empty_page() {
+    // arrange
+    let (nova_server, openstack) = setup_openstack(2).await;
+    Mock::given(method("GET"))
+        .and(path("/v2.1/servers/detail"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "servers": [],
+            "servers_links": [{
+                "href": "https://nova.example.com/v2.1/servers/detail?marker=x",
+                "rel": "next",
+            }],
+        })))
+        .expect(1)
+        .mount(&nova_server)
+        .await;
+
+    // act
+    let servers = openstack.get_servers().await.unwrap();
+
+    // assert
+    assert!(servers.is_empty());
+}
+
+#[tokio::test]
+async fn get_servers_changed_since_sends_changes_since() {
+    // arrange
+    let (nova_server, openstack) = setup_openstack(1000).await;
+    let (flavor, user) = test_flavor_and_user();
+    let id = random_uuid();
+    let since = Utc::now() - TimeDelta::hours(1);
+    Mock::given(method("GET"))
+        .and(path("/v2.1/servers/detail"))
+        .and(query_param(
+            "changes-since",
+            since.format("%Y-%m-%dT%H:%M:%SZ").to_string().as_str(),
+        ))
+        .and(query_param_is_missing("deleted"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "servers": [
+                nova_server_json(&id, "test", "ACTIVE", &flavor, &user, None),
+            ],
+        })))
+        .expect(1)
+        .mount(&nova_server)
+        .await;
+
+    // act
+    let servers = openstack.get_servers_changed_since(since).await.unwrap();
+
+    // assert
+    assert_eq!(servers.len(), 1);
+    assert_eq!(servers[0].id, id);
+    assert!(servers[0].launched_at.is_some());
+}
+
+#[tokio::test]
+async fn get_deleted_servers_parses_terminated_at() {
+    // arrange
+    let (nova_server, openstack) = setup_openstack(1000).await;
+    let (flavor, user) = test_flavor_and_user();
+    let id = random_uuid();
+    let terminated_at = Utc::now() - TimeDelta::minutes(5);
+    Mock::given(method("GET"))
+        .and(path("/v2.1/servers/detail"))
+        .and(query_param("deleted", "True"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "servers": [nova_server_json(
+                &id,
+                "test",
+                "DELETED",
+                &flavor,
+                &user,
+                Some(terminated_at),
+            )],
+        })))
+        .expect(1)
+        .mount(&nova_server)
+        .await;
+
+    // act
+    let servers = openstack
+        .get_deleted_servers(terminated_at - TimeDelta::hours(1))
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(servers.len(), 1);
+    let parsed = servers[0].terminated_at.unwrap();
+    assert!((parsed - terminated_at).abs() < TimeDelta::milliseconds(1));
+}
@@ -5,5 +5,7 @@ mod server_state;
 pub(crate) use server_consumption::{
     ServerConsumptionFilter, server_consumption,
 };
-pub(crate) use server_cost::{ServerCostFilter, server_cost};
+pub(crate) use server_cost::{
+    ServerCostCommand, ServerCostFilter, server_cost,
+};
 pub(crate) use server_state::ServerStateCommand;
@@ -1,9 +1,11 @@
 use std::error::Error;
 
 use chrono::{DateTime, FixedOffset};
-use clap::Args;
+use clap::{Args, Subcommand};
 
-use crate::common::{Format, print_json, print_single_object};
+use crate::common::{
+    Execute, Format, print_json, print_object_list, print_single_object,
+};
 #[cfg(not(feature = "user"))]
 use crate::common::{find_id as project_find_id, find_id as user_find_id};
 #[cfg(feature = "user")]
@@ -45,6 +47,73 @@ pub(crate) struct ServerCostFilter {
     all: bool,
 }
 
+#[derive(Args, Debug)]
+#[group(multiple = false)]
+pub(crate) struct ServerCostRateBreakdown {
+    #[clap(long, help = "Show cost rate of each running server")]
+    servers: bool,
+
+    #[clap(long, help = "Show cost rate of each flavor")]
+    flavors: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum ServerCostCommand {
+    #[clap(
+        about = "Show current cost rate of running servers per hour, day, and month"
+    )]
+    Rate {
+        #[clap(flatten)]
+        filter: ServerCostFilter,
+
+        #[clap(flatten)]
+        breakdown: ServerCostRateBreakdown,
+    },
+}
+
+impl Execute for ServerCostCommand {
+    async fn execute(
+        &self,
+        api: avina::Api,
+        format: Format,
+    ) -> Result<(), Box<dyn Error>> {
+        match self {
+            ServerCostCommand::Rate { filter, breakdown } => {
+                server_cost_rate(api, format, filter, breakdown).await
+            }
+        }
+    }
+}
+
+async fn server_cost_rate(
+    api: avina::Api,
+    format: Format,
+    filter: &ServerCostFilter,
+    breakdown: &ServerCostRateBreakdown,
+) -> Result<(), Box<dyn Error>> {
+    let mut request = api.server_cost.rate();
+    let rate = if let Some(server) = &filter.server {
+        request.server(server).await?
+    } else if let Some(user) = &filter.user {
+        let user_id = user_find_id(&api, user).await?;
+        request.user(user_id).await?
+    } else if let Some(project) = &filter.project {
+        let project_id = project_find_id(&api, project).await?;
+        request.project(project_id).await?
+    } else if filter.all {
+        request.all().await?
+    } else {
+        request.mine().await?
+    };
+    if breakdown.servers {
+        print_object_list(rate.servers, format)
+    } else if breakdown.flavors {
+        print_object_list(rate.flavors, format)
+    } else {
+        print_single_object(rate, format)
+    }
+}
+
 pub(crate) async fn server_cost(
     api: avina::Api,
     format: Format,
@@ -144,6 +144,56 @@ pub(crate) enum ServerStateCommand {
             help = "Suppress output if nothing is imported"
         )]
         quiet: bool,
+
+        #[clap(
+            long,
+            action,
+            help = "Only show the changes, without applying them"
+        )]
+        dry_run: bool,
+    },
+
+    #[clap(about = "Check server states for overlaps and other issues")]
+    Check {
+        #[clap(
+            long,
+            action,
+            help = "Close duplicate open states and merge identical adjacent states"
+        )]
+        repair: bool,
+    },
+
+    #[clap(about = "Merge adjacent server states with identical attributes")]
+    Compact,
+
+    #[clap(about = "Move finished server states of old years into the archive")]
+    Archive {
+        #[clap(
+            long,
+            short,
+            default_value_t = 2,
+            help = "Number of past years to keep besides the current one"
+        )]
+        years: u32,
+    },
+
+    #[clap(about = "Show status of the last server state import")]
+    ImportStatus,
+
+    #[clap(about = "Show history of server state imports")]
+    ImportHistory {
+        #[clap(help = "ID of a single import run to show in detail")]
+        id: Option<u32>,
+
+        #[clap(
+            long,
+            short,
+            help = "Maximum number of most recent import runs to show [default: 100]"
+        )]
+        limit: Option<u32>,
+
+        #[clap(long, short, help = "Only show import runs begun since then")]
+        since: Option<DateTime<FixedOffset>>,
     },
 }
 pub(crate) use ServerStateCommand::*;
@@ -204,7 +254,16 @@ impl Execute for ServerStateCommand {
                 .await
             }
             Delete { id } => delete(api, id).await,
-            Import { quiet } => import(api, format, *quiet).await,
+            Import { quiet, dry_run } => {
+                import(api, format, *quiet, *dry_run).await
+            }
+            ImportStatus => import_status(api, format).await,
+            Check { repair } => check(api, format, *repair).await,
+            Compact => compact(api, format).await,
+            Archive { years } => archive(api, format, *years).await,
+            ImportHistory { id, limit, since } => {
+                import_history(api, format, *id, *limit, *since).await
+            }
         }
     }
 }
@@ -316,10 +375,102 @@ async fn import(
     api: avina::Api,
     format: Format,
     quiet: bool,
+    dry_run: bool,
+) -> Result<(), Box<dyn Error>> {
+    let result = if dry_run {
+        api.server_state.import_dry_run().await?
+    } else {
+        api.server_state.import().await?
+    };
+    if quiet && result.new_state_count == 0 && result.end_state_count == 0 {
+        return Ok(());
+    }
+    let new_states = result.new_states.clone();
+    let ended_states = result.ended_states.clone();
+    let skipped = result.skipped.clone();
+    print_single_object(result, format.clone())?;
+    if matches!(format, Format::Table(_)) && dry_run {
+        if !new_states.is_empty() {
+            print_object_list(new_states, format.clone())?;
+        }
+        if !ended_states.is_empty() {
+            print_object_list(ended_states, format.clone())?;
+        }
+        if !skipped.is_empty() {
+            print_object_list(skipped, format)?;
+        }
+    }
+    Ok(())
+}
+
+async fn check(
+    api: avina::Api,
+    format: Format,
+    repair: bool,
 ) -> Result<(), Box<dyn Error>> {
-    let result = api.server_state.import().await?;
-    if !quiet || result.new_state_count > 0 || result.end_state_count > 0 {
-        return print_single_object(result, format);
+    let mut request = api.server_state.check();
+    if repair {
+        ask_for_confirmation()?;
+        request.repair();
+    }
+    let result = request.send().await?;
+    let issues = result.issues.clone();
+    print_single_object(result, format.clone())?;
+    if matches!(format, Format::Table(_)) && !issues.is_empty() {
+        print_object_list(issues, format)?;
     }
     Ok(())
 }
+
+async fn compact(
+    api: avina::Api,
+    format: Format,
+) -> Result<(), Box<dyn Error>> {
+    print_single_object(api.server_state.compact().await?, format)
+}
+
+async fn archive(
+    api: avina::Api,
+    format: Format,
+    years: u32,
+) -> Result<(), Box<dyn Error>> {
+    ask_for_confirmation()?;
+    print_single_object(api.server_state.archive(years).await?, format)
+}
+
+async fn import_status(
+    api: avina::Api,
+    format: Format,
+) -> Result<(), Box<dyn Error>> {
+    print_single_object(api.server_state.import_status().await?, format)
+}
+
+async fn import_history(
+    api: avina::Api,
+    format: Format,
+    id: Option<u32>,
+    limit: Option<u32>,
+    since: Option<DateTime<FixedOffset>>,
+) -> Result<(), Box<dyn Error>> {
+    match id {
+        Some(id) => {
+            let import_run = api.server_state.import_run(id).await?;
+            let skipped = import_run.skipped.clone();
+            print_single_object(import_run, format.clone())?;
+            if matches!(format, Format::Table(_)) && !skipped.is_empty() {
+                print_object_list(skipped, format)?;
+            }
+            Ok(())
+        }
+        None => {
+            let mut request = api.server_state.import_runs();
+            if let Some(limit) = limit {
+                request.limit(limit);
+            }
+            if let Some(since) = since {
+                request.since(since);
+            }
+            print_object_list(request.send().await?, format)
+        }
+    }
+}
@@ -237,8 +237,14 @@ enum Command {
     },
 
     #[cfg(feature = "accounting")]
-    #[clap(about = "Server cost command")]
+    #[clap(
+        about = "Server cost command",
+        args_conflicts_with_subcommands = true
+    )]
     ServerCost {
+        #[clap(subcommand)]
+        command: Option<accounting::ServerCostCommand>,
+
         #[clap(
             long,
             short,
@@ -370,7 +376,8 @@ async fn main() -> ExitCode {
                 | UserCommand::Create { .. }
                 | UserCommand::Modify { .. }
                 | UserCommand::Delete { .. }
-                | UserCommand::Me,
+                | UserCommand::Me
+                | UserCommand::Import { .. },
         } => {
             if cli.rust {
                 cli.rust_url
@@ -384,7 +391,12 @@ async fn main() -> ExitCode {
                 | ServerStateCommand::Get { .. }
                 | ServerStateCommand::Create { .. }
                 | ServerStateCommand::Modify { .. }
-                | ServerStateCommand::Delete { .. },
+                | ServerStateCommand::Delete { .. }
+                | ServerStateCommand::ImportStatus
+                | ServerStateCommand::ImportHistory { .. }
+                | ServerStateCommand::Check { .. }
+                | ServerStateCommand::Compact
+                | ServerStateCommand::Archive { .. },
         } => {
             if cli.rust {
                 cli.rust_url
@@ -398,19 +410,27 @@ async fn main() -> ExitCode {
         | Command::ProjectBudget {
             command: ProjectBudgetCommand::Delete { .. },
         }
+        | Command::BudgetOverTree { .. }
         | Command::Flavor {
-            command: FlavorCommand::Delete { .. } | FlavorCommand::Modify { .. },
+            command:
+                FlavorCommand::Delete { .. }
+                | FlavorCommand::Modify { .. }
+                | FlavorCommand::Usage { .. },
         }
         | Command::FlavorGroup {
             command:
                 FlavorGroupCommand::Delete { .. }
-                | FlavorGroupCommand::Modify { .. },
+                | FlavorGroupCommand::Modify { .. }
+                | FlavorGroupCommand::Initialize
+                | FlavorGroupCommand::Usage { .. },
         }
         | Command::FlavorPrice {
-            command: FlavorPriceCommand::Delete { .. },
+            command:
+                FlavorPriceCommand::Delete { .. } | FlavorPriceCommand::Initialize,
         }
         | Command::FlavorQuota {
-            command: FlavorQuotaCommand::Delete { .. },
+            command:
+                FlavorQuotaCommand::Delete { .. } | FlavorQuotaCommand::Check { .. },
         } => {
             if cli.rust {
                 cli.rust_url
@@ -418,6 +438,18 @@ async fn main() -> ExitCode {
                 cli.url
             }
         }
+        // only the Rust API supports dry runs and the cost rate, so never
+        // send them elsewhere
+        Command::ServerState {
+            command: ServerStateCommand::Import { dry_run: true, .. },
+        }
+        | Command::Flavor {
+            command: FlavorCommand::Import { dry_run: true, .. },
+        }
+        | Command::ServerCost {
+            command: Some(accounting::ServerCostCommand::Rate { .. }),
+            ..
+        } => cli.rust_url,
         _ => cli.url,
     };
     let api = match Api::new(url, token, cli.impersonate, cli.timeout) {
@@ -470,6 +502,12 @@ async fn main() -> ExitCode {
         }
         #[cfg(feature = "accounting")]
         Command::ServerCost {
+            command: Some(ref command),
+            ..
+        } => command.execute(api, cli.format).await,
+        #[cfg(feature = "accounting")]
+        Command::ServerCost {
+            command: None,
             begin,
             end,
             filter,
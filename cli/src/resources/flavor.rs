@@ -127,6 +127,13 @@ pub(crate) enum FlavorCommand {
             help = "Suppress output if nothing is imported"
         )]
         quiet: bool,
+
+        #[clap(
+            long,
+            action,
+            help = "Only show the changes, without applying them"
+        )]
+        dry_run: bool,
     },
 
     #[clap(about = "Flavor usage command")]
@@ -184,7 +191,9 @@ impl Execute for FlavorCommand {
                 .await
             }
             Delete { name_or_id } => delete(api, name_or_id).await,
-            Import { quiet } => import(api, format, *quiet).await,
+            Import { quiet, dry_run } => {
+                import(api, format, *quiet, *dry_run).await
+            }
             Usage { filter, aggregate } => {
                 usage(api, format, filter, *aggregate).await
             }
@@ -275,10 +284,20 @@ async fn import(
     api: avina::Api,
     format: Format,
     quiet: bool,
+    dry_run: bool,
 ) -> Result<(), Box<dyn Error>> {
-    let result = api.flavor.import().await?;
-    if !quiet || result.new_flavor_count > 0 {
-        return print_single_object(result, format);
+    let result = if dry_run {
+        api.flavor.import_dry_run().await?
+    } else {
+        api.flavor.import().await?
+    };
+    if quiet && result.new_flavor_count == 0 {
+        return Ok(());
+    }
+    let changes = result.new_flavors.clone();
+    print_single_object(result, format.clone())?;
+    if matches!(format, Format::Table(_)) && dry_run && !changes.is_empty() {
+        print_object_list(changes, format)?;
     }
     Ok(())
 }
@@ -301,7 +320,6 @@ async fn usage(
             } else if filter.all {
                 request.all_aggregate().await?
             } else {
-                // TODO this causes a http 500 error
                 request.mine_aggregate().await?
             },
             format,
@@ -216,7 +216,6 @@ async fn usage(
             } else if filter.all {
                 request.all_aggregate().await?
             } else {
-                // TODO this causes a http 500 error
                 request.mine_aggregate().await?
             },
             format,
@@ -274,7 +274,13 @@ async fn import(
     quiet: bool,
 ) -> Result<(), Box<dyn Error>> {
     let result = api.user.import().await?;
-    if !quiet || result.new_project_count > 0 || result.new_user_count > 0 {
+    if !quiet
+        || result.new_project_count > 0
+        || result.new_user_count > 0
+        || result.updated_project_count > 0
+        || result.updated_user_count > 0
+        || result.deactivated_user_count > 0
+    {
         return print_single_object(result, format);
     }
     Ok(())
@@ -2,8 +2,9 @@ use std::{fmt::Debug, rc::Rc};
 
 use anyhow::Context;
 use avina_wire::accounting::{
-    ServerConsumptionAll, ServerConsumptionFlavors, ServerConsumptionParams,
-    ServerConsumptionProject, ServerConsumptionServer, ServerConsumptionUser,
+    ServerConsumptionAll, ServerConsumptionBucket, ServerConsumptionFlavors,
+    ServerConsumptionParams, ServerConsumptionProject, ServerConsumptionServer,
+    ServerConsumptionUser, TimeGrouping,
 };
 use chrono::{DateTime, FixedOffset};
 use reqwest::{Client, Method, StatusCode};
@@ -35,6 +36,7 @@ impl ServerConsumptionRequest {
                 project: None,
                 all: None,
                 detail: None,
+                by: None,
             },
         }
     }
@@ -263,6 +265,128 @@ impl ServerConsumptionRequest {
         )
         .await
     }
+
+    pub async fn server_by(
+        &mut self,
+        server: &str,
+        by: TimeGrouping,
+    ) -> Result<Vec<ServerConsumptionBucket<ServerConsumptionServer>>, ApiError>
+    {
+        self.params.server = Some(server.to_string());
+        self.params.by = Some(by);
+        let params = serde_urlencoded::to_string(&self.params)
+            .context("Failed to encode URL parameters")?;
+        let url = if params.is_empty() {
+            self.url.clone()
+        } else {
+            format!("{}?{}", self.url, params)
+        };
+        request(
+            &self.client,
+            Method::GET,
+            url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
+
+    pub async fn user_by(
+        &mut self,
+        user: u32,
+        by: TimeGrouping,
+    ) -> Result<Vec<ServerConsumptionBucket<ServerConsumptionFlavors>>, ApiError>
+    {
+        self.params.user = Some(user);
+        self.params.by = Some(by);
+        let params = serde_urlencoded::to_string(&self.params)
+            .context("Failed to encode URL parameters")?;
+        let url = if params.is_empty() {
+            self.url.clone()
+        } else {
+            format!("{}?{}", self.url, params)
+        };
+        request(
+            &self.client,
+            Method::GET,
+            url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
+
+    pub async fn project_by(
+        &mut self,
+        project: u32,
+        by: TimeGrouping,
+    ) -> Result<Vec<ServerConsumptionBucket<ServerConsumptionFlavors>>, ApiError>
+    {
+        self.params.project = Some(project);
+        self.params.by = Some(by);
+        let params = serde_urlencoded::to_string(&self.params)
+            .context("Failed to encode URL parameters")?;
+        let url = if params.is_empty() {
+            self.url.clone()
+        } else {
+            format!("{}?{}", self.url, params)
+        };
+        request(
+            &self.client,
+            Method::GET,
+            url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
+
+    pub async fn all_by(
+        &mut self,
+        by: TimeGrouping,
+    ) -> Result<Vec<ServerConsumptionBucket<ServerConsumptionFlavors>>, ApiError>
+    {
+        self.params.all = Some(true);
+        self.params.by = Some(by);
+        let params = serde_urlencoded::to_string(&self.params)
+            .context("Failed to encode URL parameters")?;
+        let url = if params.is_empty() {
+            self.url.clone()
+        } else {
+            format!("{}?{}", self.url, params)
+        };
+        request(
+            &self.client,
+            Method::GET,
+            url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
+
+    pub async fn mine_by(
+        &mut self,
+        by: TimeGrouping,
+    ) -> Result<Vec<ServerConsumptionBucket<ServerConsumptionFlavors>>, ApiError>
+    {
+        self.params.by = Some(by);
+        let params = serde_urlencoded::to_string(&self.params)
+            .context("Failed to encode URL parameters")?;
+        let url = if params.is_empty() {
+            self.url.clone()
+        } else {
+            format!("{}?{}", self.url, params)
+        };
+        request(
+            &self.client,
+            Method::GET,
+            url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
 }
 
 #[derive(Debug)]
@@ -2,8 +2,10 @@ use std::{fmt::Debug, rc::Rc};
 
 use anyhow::Context;
 use avina_wire::accounting::{
-    ServerCostAll, ServerCostParams, ServerCostProject, ServerCostServer,
-    ServerCostSimple, ServerCostUser,
+    ServerCostAll, ServerCostBucket, ServerCostParams, ServerCostPoint,
+    ServerCostProject, ServerCostRate, ServerCostRateParams, ServerCostRollup,
+    ServerCostRollupParams, ServerCostSeriesParams, ServerCostServer,
+    ServerCostSimple, ServerCostUser, TimeGrouping,
 };
 use chrono::{DateTime, FixedOffset};
 use reqwest::{Client, Method, StatusCode};
@@ -36,6 +38,7 @@ impl ServerCostRequest {
                 project: None,
                 all: None,
                 detail: None,
+                by: None,
             },
         }
     }
@@ -260,6 +263,445 @@ impl ServerCostRequest {
         )
         .await
     }
+
+    pub async fn server_by(
+        &mut self,
+        server: &str,
+        by: TimeGrouping,
+    ) -> Result<Vec<ServerCostBucket<ServerCostSimple>>, ApiError> {
+        self.params.server = Some(server.to_string());
+        self.params.by = Some(by);
+        let params = serde_urlencoded::to_string(&self.params)
+            .context("Failed to encode URL parameters")?;
+        let url = if params.is_empty() {
+            self.url.clone()
+        } else {
+            format!("{}?{}", self.url, params)
+        };
+        request(
+            &self.client,
+            Method::GET,
+            url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
+
+    pub async fn user_by(
+        &mut self,
+        user: u32,
+        by: TimeGrouping,
+    ) -> Result<Vec<ServerCostBucket<ServerCostSimple>>, ApiError> {
+        self.params.user = Some(user);
+        self.params.by = Some(by);
+        let params = serde_urlencoded::to_string(&self.params)
+            .context("Failed to encode URL parameters")?;
+        let url = if params.is_empty() {
+            self.url.clone()
+        } else {
+            format!("{}?{}", self.url, params)
+        };
+        request(
+            &self.client,
+            Method::GET,
+            url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
+
+    pub async fn project_by(
+        &mut self,
+        project: u32,
+        by: TimeGrouping,
+    ) -> Result<Vec<ServerCostBucket<ServerCostSimple>>, ApiError> {
+        self.params.project = Some(project);
+        self.params.by = Some(by);
+        let params = serde_urlencoded::to_string(&self.params)
+            .context("Failed to encode URL parameters")?;
+        let url = if params.is_empty() {
+            self.url.clone()
+        } else {
+            format!("{}?{}", self.url, params)
+        };
+        request(
+            &self.client,
+            Method::GET,
+            url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
+
+    pub async fn all_by(
+        &mut self,
+        by: TimeGrouping,
+    ) -> Result<Vec<ServerCostBucket<ServerCostSimple>>, ApiError> {
+        self.params.all = Some(true);
+        self.params.by = Some(by);
+        let params = serde_urlencoded::to_string(&self.params)
+            .context("Failed to encode URL parameters")?;
+        let url = if params.is_empty() {
+            self.url.clone()
+        } else {
+            format!("{}?{}", self.url, params)
+        };
+        request(
+            &self.client,
+            Method::GET,
+            url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
+
+    pub async fn mine_by(
+        &mut self,
+        by: TimeGrouping,
+    ) -> Result<Vec<ServerCostBucket<ServerCostSimple>>, ApiError> {
+        self.params.by = Some(by);
+        let params = serde_urlencoded::to_string(&self.params)
+            .context("Failed to encode URL parameters")?;
+        let url = if params.is_empty() {
+            self.url.clone()
+        } else {
+            format!("{}?{}", self.url, params)
+        };
+        request(
+            &self.client,
+            Method::GET,
+            url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
+}
+
+#[derive(Debug)]
+pub struct ServerCostRollupRequest {
+    url: String,
+    client: Rc<Client>,
+
+    params: ServerCostRollupParams,
+}
+
+impl ServerCostRollupRequest {
+    pub fn new(url: &str, client: &Rc<Client>) -> Self {
+        Self {
+            url: url.to_string(),
+            client: Rc::clone(client),
+
+            params: ServerCostRollupParams {
+                begin: None,
+                end: None,
+            },
+        }
+    }
+
+    pub fn begin(&mut self, begin: DateTime<FixedOffset>) -> &mut Self {
+        self.params.begin = Some(begin);
+        self
+    }
+
+    pub fn end(&mut self, end: DateTime<FixedOffset>) -> &mut Self {
+        self.params.end = Some(end);
+        self
+    }
+
+    pub async fn send(&self) -> Result<ServerCostRollup, ApiError> {
+        let params = serde_urlencoded::to_string(&self.params)
+            .context("Failed to encode URL parameters")?;
+        let url = if params.is_empty() {
+            self.url.clone()
+        } else {
+            format!("{}?{}", self.url, params)
+        };
+        request(
+            &self.client,
+            Method::GET,
+            url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
+}
+
+#[derive(Debug)]
+pub struct ServerCostRateRequest {
+    url: String,
+    client: Rc<Client>,
+
+    params: ServerCostRateParams,
+}
+
+impl ServerCostRateRequest {
+    pub fn new(url: &str, client: &Rc<Client>) -> Self {
+        Self {
+            url: url.to_string(),
+            client: Rc::clone(client),
+
+            params: ServerCostRateParams {
+                server: None,
+                user: None,
+                project: None,
+                all: None,
+            },
+        }
+    }
+
+    pub async fn server(
+        &mut self,
+        server: &str,
+    ) -> Result<ServerCostRate, ApiError> {
+        self.params.server = Some(server.to_string());
+        let params = serde_urlencoded::to_string(&self.params)
+            .context("Failed to encode URL parameters")?;
+        let url = if params.is_empty() {
+            self.url.clone()
+        } else {
+            format!("{}?{}", self.url, params)
+        };
+        request(
+            &self.client,
+            Method::GET,
+            url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
+
+    pub async fn user(
+        &mut self,
+        user: u32,
+    ) -> Result<ServerCostRate, ApiError> {
+        self.params.user = Some(user);
+        let params = serde_urlencoded::to_string(&self.params)
+            .context("Failed to encode URL parameters")?;
+        let url = if params.is_empty() {
+            self.url.clone()
+        } else {
+            format!("{}?{}", self.url, params)
+        };
+        request(
+            &self.client,
+            Method::GET,
+            url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
+
+    pub async fn project(
+        &mut self,
+        project: u32,
+    ) -> Result<ServerCostRate, ApiError> {
+        self.params.project = Some(project);
+        let params = serde_urlencoded::to_string(&self.params)
+            .context("Failed to encode URL parameters")?;
+        let url = if params.is_empty() {
+            self.url.clone()
+        } else {
+            format!("{}?{}", self.url, params)
+        };
+        request(
+            &self.client,
+            Method::GET,
+            url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
+
+    pub async fn all(&mut self) -> Result<ServerCostRate, ApiError> {
+        self.params.all = Some(true);
+        let params = serde_urlencoded::to_string(&self.params)
+            .context("Failed to encode URL parameters")?;
+        let url = if params.is_empty() {
+            self.url.clone()
+        } else {
+            format!("{}?{}", self.url, params)
+        };
+        request(
+            &self.client,
+            Method::GET,
+            url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
+
+    pub async fn mine(&mut self) -> Result<ServerCostRate, ApiError> {
+        let params = serde_urlencoded::to_string(&self.params)
+            .context("Failed to encode URL parameters")?;
+        let url = if params.is_empty() {
+            self.url.clone()
+        } else {
+            format!("{}?{}", self.url, params)
+        };
+        request(
+            &self.client,
+            Method::GET,
+            url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
+}
+
+#[derive(Debug)]
+pub struct ServerCostSeriesRequest {
+    url: String,
+    client: Rc<Client>,
+
+    params: ServerCostSeriesParams,
+}
+
+impl ServerCostSeriesRequest {
+    pub fn new(url: &str, client: &Rc<Client>) -> Self {
+        Self {
+            url: url.to_string(),
+            client: Rc::clone(client),
+
+            params: ServerCostSeriesParams {
+                begin: None,
+                end: None,
+                server: None,
+                user: None,
+                project: None,
+                all: None,
+                by: None,
+            },
+        }
+    }
+
+    pub fn begin(&mut self, begin: DateTime<FixedOffset>) -> &mut Self {
+        self.params.begin = Some(begin);
+        self
+    }
+
+    pub fn end(&mut self, end: DateTime<FixedOffset>) -> &mut Self {
+        self.params.end = Some(end);
+        self
+    }
+
+    pub fn by(&mut self, by: TimeGrouping) -> &mut Self {
+        self.params.by = Some(by);
+        self
+    }
+
+    pub async fn server(
+        &mut self,
+        server: &str,
+    ) -> Result<Vec<ServerCostPoint>, ApiError> {
+        self.params.server = Some(server.to_string());
+        let params = serde_urlencoded::to_string(&self.params)
+            .context("Failed to encode URL parameters")?;
+        let url = if params.is_empty() {
+            self.url.clone()
+        } else {
+            format!("{}?{}", self.url, params)
+        };
+        request(
+            &self.client,
+            Method::GET,
+            url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
+
+    pub async fn user(
+        &mut self,
+        user: u32,
+    ) -> Result<Vec<ServerCostPoint>, ApiError> {
+        self.params.user = Some(user);
+        let params = serde_urlencoded::to_string(&self.params)
+            .context("Failed to encode URL parameters")?;
+        let url = if params.is_empty() {
+            self.url.clone()
+        } else {
+            format!("{}?{}", self.url, params)
+        };
+        request(
+            &self.client,
+            Method::GET,
+            url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
+
+    pub async fn project(
+        &mut self,
+        project: u32,
+    ) -> Result<Vec<ServerCostPoint>, ApiError> {
+        self.params.project = Some(project);
+        let params = serde_urlencoded::to_string(&self.params)
+            .context("Failed to encode URL parameters")?;
+        let url = if params.is_empty() {
+            self.url.clone()
+        } else {
+            format!("{}?{}", self.url, params)
+        };
+        request(
+            &self.client,
+            Method::GET,
+            url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
+
+    pub async fn all(&mut self) -> Result<Vec<ServerCostPoint>, ApiError> {
+        self.params.all = Some(true);
+        let params = serde_urlencoded::to_string(&self.params)
+            .context("Failed to encode URL parameters")?;
+        let url = if params.is_empty() {
+            self.url.clone()
+        } else {
+            format!("{}?{}", self.url, params)
+        };
+        request(
+            &self.client,
+            Method::GET,
+            url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
+
+    pub async fn mine(&mut self) -> Result<Vec<ServerCostPoint>, ApiError> {
+        let params = serde_urlencoded::to_string(&self.params)
+            .context("Failed to encode URL parameters")?;
+        let url = if params.is_empty() {
+            self.url.clone()
+        } else {
+            format!("{}?{}", self.url, params)
+        };
+        request(
+            &self.client,
+            Method::GET,
+            url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
 }
 
 #[derive(Debug)]
@@ -279,4 +721,22 @@ impl ServerCostApi {
     pub fn get(&self) -> ServerCostRequest {
         ServerCostRequest::new(self.url.as_str(), &self.client)
     }
+
+    pub fn rate(&self) -> ServerCostRateRequest {
+        // TODO use Url.join
+        let url = format!("{}rate/", self.url);
+        ServerCostRateRequest::new(url.as_ref(), &self.client)
+    }
+
+    pub fn rollup(&self) -> ServerCostRollupRequest {
+        // TODO use Url.join
+        let url = format!("{}rollup/", self.url);
+        ServerCostRollupRequest::new(url.as_ref(), &self.client)
+    }
+
+    pub fn series(&self) -> ServerCostSeriesRequest {
+        // TODO use Url.join
+        let url = format!("{}series/", self.url);
+        ServerCostSeriesRequest::new(url.as_ref(), &self.client)
+    }
 }
@@ -2,8 +2,11 @@ use std::rc::Rc;
 
 use anyhow::Context;
 use avina_wire::accounting::{
-    ServerState, ServerStateCreateData, ServerStateImport,
-    ServerStateListParams, ServerStateModifyData,
+    ServerState, ServerStateArchive, ServerStateArchiveParams,
+    ServerStateCheck, ServerStateCheckParams, ServerStateCompaction,
+    ServerStateCreateData, ServerStateImport, ServerStateImportParams,
+    ServerStateImportRun, ServerStateImportRunListParams,
+    ServerStateImportStatus, ServerStateListParams, ServerStateModifyData,
 };
 use chrono::{DateTime, FixedOffset};
 use reqwest::{Client, Method, StatusCode};
@@ -81,6 +84,98 @@ impl ServerStateListRequest {
     }
 }
 
+#[derive(Debug)]
+pub struct ServerStateImportRunListRequest {
+    url: String,
+    client: Rc<Client>,
+
+    params: ServerStateImportRunListParams,
+}
+
+impl ServerStateImportRunListRequest {
+    pub fn new(url: &str, client: &Rc<Client>) -> Self {
+        Self {
+            url: url.to_string(),
+            client: Rc::clone(client),
+
+            params: ServerStateImportRunListParams {
+                limit: None,
+                since: None,
+            },
+        }
+    }
+
+    pub async fn send(&self) -> Result<Vec<ServerStateImportRun>, ApiError> {
+        let params = serde_urlencoded::to_string(&self.params)
+            .context("Failed to encode URL parameters")?;
+        let url = if params.is_empty() {
+            self.url.clone()
+        } else {
+            format!("{}?{}", self.url, params)
+        };
+        request(
+            &self.client,
+            Method::GET,
+            url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
+
+    pub fn limit(&mut self, limit: u32) -> &mut Self {
+        self.params.limit = Some(limit);
+        self
+    }
+
+    pub fn since(&mut self, since: DateTime<FixedOffset>) -> &mut Self {
+        self.params.since = Some(since);
+        self
+    }
+}
+
+#[derive(Debug)]
+pub struct ServerStateCheckRequest {
+    url: String,
+    client: Rc<Client>,
+
+    params: ServerStateCheckParams,
+}
+
+impl ServerStateCheckRequest {
+    pub fn new(url: &str, client: &Rc<Client>) -> Self {
+        Self {
+            url: url.to_string(),
+            client: Rc::clone(client),
+
+            params: ServerStateCheckParams { repair: None },
+        }
+    }
+
+    pub async fn send(&self) -> Result<ServerStateCheck, ApiError> {
+        let params = serde_urlencoded::to_string(&self.params)
+            .context("Failed to encode URL parameters")?;
+        let url = if params.is_empty() {
+            self.url.clone()
+        } else {
+            format!("{}?{}", self.url, params)
+        };
+        request(
+            &self.client,
+            Method::GET,
+            url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
+
+    pub fn repair(&mut self) -> &mut Self {
+        self.params.repair = Some(true);
+        self
+    }
+}
+
 pub struct ServerStateCreateRequest {
     url: String,
     client: Rc<Client>,
@@ -242,6 +337,44 @@ impl ServerStateApi {
         )
     }
 
+    pub fn check(&self) -> ServerStateCheckRequest {
+        // TODO use Url.join
+        let url = format!("{}/check/", self.url);
+        ServerStateCheckRequest::new(url.as_ref(), &self.client)
+    }
+
+    pub async fn compact(&self) -> Result<ServerStateCompaction, ApiError> {
+        // TODO use Url.join
+        let url = format!("{}/compact/", self.url);
+        request(
+            &self.client,
+            Method::GET,
+            url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
+
+    pub async fn archive(
+        &self,
+        years: u32,
+    ) -> Result<ServerStateArchive, ApiError> {
+        let params =
+            serde_urlencoded::to_string(ServerStateArchiveParams { years })
+                .context("Failed to encode URL parameters")?;
+        // TODO use Url.join
+        let url = format!("{}/archive/?{}", self.url, params);
+        request(
+            &self.client,
+            Method::GET,
+            url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
+
     pub fn modify(&self, id: u32) -> ServerStateModifyRequest {
         // TODO use Url.join
         let url = format!("{}/{}/", self.url, id);
@@ -263,8 +396,63 @@ impl ServerStateApi {
     }
 
     pub async fn import(&self) -> Result<ServerStateImport, ApiError> {
+        self.send_import(false).await
+    }
+
+    pub async fn import_dry_run(&self) -> Result<ServerStateImport, ApiError> {
+        self.send_import(true).await
+    }
+
+    async fn send_import(
+        &self,
+        dry_run: bool,
+    ) -> Result<ServerStateImport, ApiError> {
+        // TODO use Url.join
+        let mut url = format!("{}/import/", self.url);
+        if dry_run {
+            let params = serde_urlencoded::to_string(ServerStateImportParams {
+                dry_run: Some(true),
+            })
+            .context("Failed to encode URL parameters")?;
+            url = format!("{}?{}", url, params);
+        }
+        request(
+            &self.client,
+            Method::GET,
+            url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
+
+    pub fn import_runs(&self) -> ServerStateImportRunListRequest {
+        // TODO use Url.join
+        let url = format!("{}/importruns/", self.url);
+        ServerStateImportRunListRequest::new(url.as_ref(), &self.client)
+    }
+
+    pub async fn import_run(
+        &self,
+        id: u32,
+    ) -> Result<ServerStateImportRun, ApiError> {
+        // TODO use Url.join
+        let url = format!("{}/importruns/{}", self.url, id);
+        request(
+            &self.client,
+            Method::GET,
+            url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
+
+    pub async fn import_status(
+        &self,
+    ) -> Result<ServerStateImportStatus, ApiError> {
         // TODO use Url.join
-        let url = format!("{}/import/", self.url);
+        let url = format!("{}/import/status/", self.url);
         request(
             &self.client,
             Method::GET,
@@ -0,0 +1,67 @@
+use std::rc::Rc;
+
+use avina_wire::budgeting::{BudgetEnforcementAction, BudgetEnforcementData};
+use reqwest::{Client, Method, StatusCode};
+
+use crate::{
+    common::{SerializableNone, request},
+    error::ApiError,
+};
+
+#[derive(Debug)]
+pub struct BudgetEnforcementApi {
+    pub url: String,
+    pub client: Rc<Client>,
+}
+
+impl BudgetEnforcementApi {
+    pub fn new(base_url: &str, client: &Rc<Client>) -> BudgetEnforcementApi {
+        BudgetEnforcementApi {
+            url: format!("{base_url}/budgeting/budgetenforcement/"),
+            client: Rc::clone(client),
+        }
+    }
+
+    // recorded actions of all previous runs
+    pub async fn list(&self) -> Result<Vec<BudgetEnforcementAction>, ApiError> {
+        request(
+            &self.client,
+            Method::GET,
+            self.url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
+
+    // dry run or not as configured on the server
+    pub async fn run(&self) -> Result<Vec<BudgetEnforcementAction>, ApiError> {
+        self.run_with(None).await
+    }
+
+    pub async fn dry_run(
+        &self,
+    ) -> Result<Vec<BudgetEnforcementAction>, ApiError> {
+        self.run_with(Some(true)).await
+    }
+
+    pub async fn force_run(
+        &self,
+    ) -> Result<Vec<BudgetEnforcementAction>, ApiError> {
+        self.run_with(Some(false)).await
+    }
+
+    async fn run_with(
+        &self,
+        dry_run: Option<bool>,
+    ) -> Result<Vec<BudgetEnforcementAction>, ApiError> {
+        request(
+            &self.client,
+            Method::POST,
+            self.url.as_str(),
+            Some(&BudgetEnforcementData { dry_run }),
+            StatusCode::OK,
+        )
+        .await
+    }
+}
@@ -3,7 +3,8 @@ use std::rc::Rc;
 use anyhow::Context;
 use avina_wire::budgeting::{
     ProjectBudget, ProjectBudgetCreateData, ProjectBudgetListParams,
-    ProjectBudgetModifyData, ProjectBudgetOverDetail, ProjectBudgetOverParams,
+    ProjectBudgetModifyData, ProjectBudgetOverDetail,
+    ProjectBudgetOverForecast, ProjectBudgetOverParams,
     ProjectBudgetOverSimple,
 };
 use chrono::{DateTime, FixedOffset};
@@ -178,6 +179,7 @@ impl ProjectBudgetOverRequest {
                 project: None,
                 all: None,
                 detail: None,
+                forecast: None,
             },
         }
     }
@@ -261,6 +263,27 @@ impl ProjectBudgetOverRequest {
         )
         .await
     }
+
+    pub async fn forecast(
+        &mut self,
+    ) -> Result<Vec<ProjectBudgetOverForecast>, ApiError> {
+        self.params.forecast = Some(true);
+        let params = serde_urlencoded::to_string(&self.params)
+            .context("Failed to encode URL parameters")?;
+        let url = if params.is_empty() {
+            self.url.clone()
+        } else {
+            format!("{}?{}", self.url, params)
+        };
+        request(
+            &self.client,
+            Method::GET,
+            url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
 }
 
 impl ProjectBudgetApi {
@@ -4,8 +4,8 @@ use anyhow::Context;
 use avina_wire::budgeting::{
     UserBudget, UserBudgetCreateData, UserBudgetListParams,
     UserBudgetModifyData, UserBudgetOverCombined, UserBudgetOverCombinedDetail,
-    UserBudgetOverDetail, UserBudgetOverParams, UserBudgetOverSimple,
-    UserBudgetSync,
+    UserBudgetOverDetail, UserBudgetOverForecast, UserBudgetOverParams,
+    UserBudgetOverSimple, UserBudgetSync,
 };
 use chrono::{DateTime, FixedOffset};
 use reqwest::{Client, Method, StatusCode};
@@ -181,6 +181,7 @@ impl UserBudgetOverRequest {
                 all: None,
                 combined: None,
                 detail: None,
+                forecast: None,
             },
         }
     }
@@ -311,6 +312,27 @@ impl UserBudgetOverRequest {
         )
         .await
     }
+
+    pub async fn forecast(
+        &mut self,
+    ) -> Result<Vec<UserBudgetOverForecast>, ApiError> {
+        self.params.forecast = Some(true);
+        let params = serde_urlencoded::to_string(&self.params)
+            .context("Failed to encode URL parameters")?;
+        let url = if params.is_empty() {
+            self.url.clone()
+        } else {
+            format!("{}?{}", self.url, params)
+        };
+        request(
+            &self.client,
+            Method::GET,
+            url.as_str(),
+            SerializableNone!(),
+            StatusCode::OK,
+        )
+        .await
+    }
 }
 
 impl UserBudgetApi {
@@ -37,6 +37,8 @@ use accounting::ServerStateApi;
 #[cfg(feature = "budgeting")]
 use budgeting::BudgetBulkCreateApi;
 #[cfg(feature = "budgeting")]
+use budgeting::BudgetEnforcementApi;
+#[cfg(feature = "budgeting")]
 use budgeting::BudgetOverTreeApi;
 #[cfg(feature = "budgeting")]
 use budgeting::ProjectBudgetApi;
@@ -97,6 +99,8 @@ pub struct Api {
     pub budget_over_tree: BudgetOverTreeApi,
     #[cfg(feature = "budgeting")]
     pub budget_bulk_create: BudgetBulkCreateApi,
+    #[cfg(feature = "budgeting")]
+    pub budget_enforcement: BudgetEnforcementApi,
 }
 
 impl Api {
@@ -171,6 +175,8 @@ impl Api {
             budget_over_tree: BudgetOverTreeApi::new(&url, &client),
             #[cfg(feature = "budgeting")]
             budget_bulk_create: BudgetBulkCreateApi::new(&url, &client),
+            #[cfg(feature = "budgeting")]
+            budget_enforcement: BudgetEnforcementApi::new(&url, &client),
         })
     }
 }
@@ -2,8 +2,8 @@ use std::rc::Rc;
 
 use anyhow::Context;
 use avina_wire::resources::{
-    Flavor, FlavorCreateData, FlavorDetailed, FlavorImport, FlavorListParams,
-    FlavorModifyData, FlavorUsage, FlavorUsageAggregate,
+    Flavor, FlavorCreateData, FlavorDetailed, FlavorImport, FlavorImportParams,
+    FlavorListParams, FlavorModifyData, FlavorUsage, FlavorUsageAggregate,
 };
 use reqwest::{Client, Method, StatusCode, Url};
 
@@ -320,7 +320,6 @@ impl FlavorUsageRequest {
         .await
     }
 
-    // TODO this causes a http 500 error
     pub async fn mine_aggregate(
         &mut self,
     ) -> Result<Vec<FlavorUsageAggregate>, ApiError> {
@@ -395,8 +394,26 @@ impl FlavorApi {
     }
 
     pub async fn import(&self) -> Result<FlavorImport, ApiError> {
+        self.send_import(false).await
+    }
+
+    pub async fn import_dry_run(&self) -> Result<FlavorImport, ApiError> {
+        self.send_import(true).await
+    }
+
+    async fn send_import(
+        &self,
+        dry_run: bool,
+    ) -> Result<FlavorImport, ApiError> {
         // TODO use Url.join
-        let url = format!("{}/import/", self.url);
+        let mut url = format!("{}/import/", self.url);
+        if dry_run {
+            let params = serde_urlencoded::to_string(FlavorImportParams {
+                dry_run: Some(true),
+            })
+            .context("Failed to encode URL parameters")?;
+            url = format!("{}?{}", url, params);
+        }
         request(
             &self.client,
             Method::GET,
@@ -1,11 +1,31 @@
 #[macro_use]
 extern crate bencher;
 
-use std::{env, str::FromStr};
+use std::{env, str::FromStr, sync::OnceLock};
 
 use avina::{Api, Token};
+use avina_api::{
+    database::{
+        accounting::server_state::{
+            NewServerState, insert_server_state_into_db,
+        },
+        pricing::flavor_price::{NewFlavorPrice, insert_flavor_price_into_db},
+    },
+    routes::server_consumption::get::{
+        calculate_server_consumption_for_all,
+        calculate_server_consumption_for_project,
+    },
+};
+use avina_test::{TestApp, random_alphanumeric_string, random_uuid, spawn_app};
 use bencher::Bencher;
+use chrono::{DateTime, TimeDelta, TimeZone, Utc};
 use futures::executor::block_on;
+use tokio::runtime::Runtime;
+
+const PROJECT_COUNT: usize = 20;
+const USERS_PER_PROJECT: usize = 5;
+const SERVERS_PER_USER: usize = 3;
+const STATES_PER_SERVER: i64 = 4;
 
 fn bench_hello_user(b: &mut Bencher) {
     let token =
@@ -19,5 +39,173 @@ fn bench_hello_user(b: &mut Bencher) {
     });
 }
 
-benchmark_group!(benches, bench_hello_user);
+// Seeds a fresh test database with projects, users, prices, and server
+// states, like the e2e tests this needs a running MariaDB.
+async fn seed_server_cost_dataset(server: &TestApp) -> Vec<u32> {
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let mut transaction = server
+        .db_pool
+        .begin()
+        .await
+        .expect("Failed to begin transaction");
+    for user_class in 1..=6 {
+        insert_flavor_price_into_db(
+            &mut transaction,
+            &NewFlavorPrice {
+                flavor_id: flavor.id as u64,
+                user_class,
+                unit_price: 100.0 * user_class as f64,
+                start_time: Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap(),
+            },
+        )
+        .await
+        .expect("Failed to insert flavor price");
+    }
+    transaction
+        .commit()
+        .await
+        .expect("Failed to commit transaction");
+
+    let mut projects = Vec::new();
+    for _ in 0..PROJECT_COUNT {
+        let test_project = server
+            .setup_test_project(1, 0, USERS_PER_PROJECT - 1)
+            .await
+            .expect("Failed to setup test project");
+        let mut transaction = server
+            .db_pool
+            .begin()
+            .await
+            .expect("Failed to begin transaction");
+        for test_user in test_project
+            .admins
+            .iter()
+            .chain(test_project.normals.iter())
+        {
+            for _ in 0..SERVERS_PER_USER {
+                let instance_id = random_uuid();
+                let mut begin =
+                    Utc.with_ymd_and_hms(2020, 2, 1, 0, 0, 0).unwrap();
+                for i in 0..STATES_PER_SERVER {
+                    let end = begin + TimeDelta::days(30 + i);
+                    insert_server_state_into_db(
+                        &mut transaction,
+                        &NewServerState {
+                            begin,
+                            end: (i + 1 < STATES_PER_SERVER).then_some(end),
+                            instance_id: instance_id.clone(),
+                            instance_name: random_alphanumeric_string(10),
+                            flavor: flavor.id,
+                            status: if i % 2 == 0 {
+                                "ACTIVE"
+                            } else {
+                                "SHUTOFF"
+                            }
+                            .to_string(),
+                            user: test_user.user.id,
+                        },
+                    )
+                    .await
+                    .expect("Failed to insert server state");
+                    begin = end;
+                }
+            }
+        }
+        transaction
+            .commit()
+            .await
+            .expect("Failed to commit transaction");
+        projects.push(test_project.project.id);
+    }
+    projects
+}
+
+struct ServerCostDataset {
+    runtime: Runtime,
+    server: TestApp,
+    projects: Vec<u32>,
+}
+
+// both server cost benchmarks run on the same seeded database
+fn server_cost_dataset() -> &'static ServerCostDataset {
+    static DATASET: OnceLock<ServerCostDataset> = OnceLock::new();
+    DATASET.get_or_init(|| {
+        let runtime = Runtime::new().unwrap();
+        let server = runtime.block_on(spawn_app());
+        let projects = runtime.block_on(seed_server_cost_dataset(&server));
+        ServerCostDataset {
+            runtime,
+            server,
+            projects,
+        }
+    })
+}
+
+// The seeded prices do not change within the period, so the cost
+// calculation multiplies the consumption of a single price period.
+fn server_cost_period() -> (Option<DateTime<Utc>>, Option<DateTime<Utc>>) {
+    (
+        Some(Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap()),
+        Some(Utc.with_ymd_and_hms(2021, 1, 1, 0, 0, 0).unwrap()),
+    )
+}
+
+// consumption of all projects from a few bulk queries, as calculated for
+// every price period of the cost of all projects
+fn bench_server_consumption_all(b: &mut Bencher) {
+    let dataset = server_cost_dataset();
+    let (begin, end) = server_cost_period();
+    let mut transaction = dataset
+        .runtime
+        .block_on(dataset.server.db_pool.begin())
+        .expect("Failed to begin transaction");
+
+    b.iter(|| {
+        dataset
+            .runtime
+            .block_on(calculate_server_consumption_for_all(
+                &mut transaction,
+                begin,
+                end,
+                Some(true),
+            ))
+            .unwrap();
+    });
+}
+
+// the same consumption project by project, which queries the users per
+// project and the states per user like the cost of all projects used to
+fn bench_server_consumption_per_project(b: &mut Bencher) {
+    let dataset = server_cost_dataset();
+    let (begin, end) = server_cost_period();
+    let mut transaction = dataset
+        .runtime
+        .block_on(dataset.server.db_pool.begin())
+        .expect("Failed to begin transaction");
+
+    b.iter(|| {
+        for project in &dataset.projects {
+            dataset
+                .runtime
+                .block_on(calculate_server_consumption_for_project(
+                    &mut transaction,
+                    *project as u64,
+                    begin,
+                    end,
+                    Some(true),
+                ))
+                .unwrap();
+        }
+    });
+}
+
+benchmark_group!(
+    benches,
+    bench_hello_user,
+    bench_server_consumption_all,
+    bench_server_consumption_per_project
+);
 benchmark_main!(benches);
@@ -1,8 +1,9 @@
 use std::ops::Range;
 
+mod smtp;
 use anyhow::Context;
 use avina_api::{
-    configuration::{DatabaseSettings, get_configuration},
+    configuration::{DatabaseSettings, Settings, get_configuration},
     database::{
         accounting::server_state::{
             NewServerState, insert_server_state_into_db,
@@ -34,13 +35,16 @@ use chrono::{DateTime, Datelike, FixedOffset, Utc};
 use once_cell::sync::Lazy;
 use rand::{Rng, distr::Alphanumeric, rng};
 use serde_json::json;
+pub use smtp::MockSmtpServer;
 use sqlx::{
     Connection, Executor, MySql, MySqlConnection, MySqlPool, Transaction,
 };
 use uuid::Uuid;
 use wiremock::{
     Mock, MockServer, ResponseTemplate,
-    matchers::{header, method, path},
+    matchers::{
+        header, method, path, path_regex, query_param, query_param_is_missing,
+    },
 };
 
 static TRACING: Lazy<()> = Lazy::new(|| {
@@ -77,6 +81,52 @@ pub struct TestUser {
     pub token: String,
 }
 
+pub fn nova_server_json(
+    id: &str,
+    name: &str,
+    status: &str,
+    flavor: &Flavor,
+    user: &User,
+    terminated_at: Option<DateTime<Utc>>,
+) -> serde_json::Value {
+    // like Nova, the usage timestamps come without a UTC offset
+    let usage_format = "%Y-%m-%dT%H:%M:%S%.6f";
+    let now = Utc::now();
+    json!({
+        "id": id,
+        "name": name,
+        "description": null,
+        "status": status,
+        "tenant_id": user.openstack_id,
+        "user_id": user.openstack_id,
+        "metadata": {},
+        "hostId": "",
+        "image": "",
+        "flavor": { "id": flavor.openstack_id, "links": [] },
+        "created": now.to_rfc3339(),
+        "updated": terminated_at.unwrap_or(now).to_rfc3339(),
+        "addresses": {},
+        "accessIPv4": "",
+        "accessIPv6": "",
+        "links": [],
+        "OS-DCF:diskConfig": "MANUAL",
+        "OS-EXT-AZ:availability_zone": "nova",
+        "config_drive": "",
+        "key_name": null,
+        "OS-SRV-USG:launched_at": now.format(usage_format).to_string(),
+        "OS-SRV-USG:terminated_at":
+            terminated_at.map(|t| t.format(usage_format).to_string()),
+        "OS-EXT-SRV-ATTR:host": null,
+        "OS-EXT-SRV-ATTR:instance_name": "",
+        "OS-EXT-SRV-ATTR:hypervisor_hostname": null,
+        "OS-EXT-STS:task_state": null,
+        "OS-EXT-STS:vm_state": "active",
+        "OS-EXT-STS:power_state": 1,
+        "os-extended-volumes:volumes_attached": [],
+        "security_groups": null,
+    })
+}
+
 pub struct TestProject {
     pub project: Project,
     pub admins: Vec<TestUser>,
@@ -108,6 +158,116 @@ impl TestApp {
             )
     }
 
+    pub fn mock_nova_flavors(&self, flavors: &[(&Flavor, u32, u32)]) -> Mock {
+        let flavors = flavors
+            .iter()
+            .map(|(flavor, vcpus, ram)| {
+                json!({
+                    "OS-FLV-DISABLED:disabled": false,
+                    "disk": 20,
+                    "os-flavor-access:is_public": true,
+                    "id": flavor.openstack_id,
+                    "links": [],
+                    "name": flavor.name,
+                    "ram": ram,
+                    "vcpus": vcpus,
+                    "rxtx_factor": 1.0,
+                    "description": null,
+                })
+            })
+            .collect::<Vec<_>>();
+        Mock::given(method("GET"))
+            .and(path("/v2.1/flavors/detail"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "flavors": flavors })),
+            )
+    }
+
+    pub fn mock_nova_servers(
+        &self,
+        servers: &[(&str, &str, &str, &Flavor, &User)],
+    ) -> Mock {
+        let servers = servers
+            .iter()
+            .map(|(id, name, status, flavor, user)| {
+                nova_server_json(id, name, status, flavor, user, None)
+            })
+            .collect::<Vec<_>>();
+        Mock::given(method("GET"))
+            .and(path("/v2.1/servers/detail"))
+            .and(query_param_is_missing("deleted"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "servers": servers })),
+            )
+    }
+
+    pub fn mock_nova_deleted_servers(
+        &self,
+        servers: &[(&str, &str, &Flavor, &User, DateTime<Utc>)],
+    ) -> Mock {
+        let servers = servers
+            .iter()
+            .map(|(id, name, flavor, user, terminated_at)| {
+                nova_server_json(
+                    id,
+                    name,
+                    "DELETED",
+                    flavor,
+                    user,
+                    Some(*terminated_at),
+                )
+            })
+            .collect::<Vec<_>>();
+        Mock::given(method("GET"))
+            .and(path("/v2.1/servers/detail"))
+            .and(query_param("deleted", "True"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "servers": servers })),
+            )
+    }
+
+    pub fn mock_keystone_domain(&self, domain: &Project) -> Mock {
+        Mock::given(method("GET"))
+            .and(path_regex(r"^/domains/[^/]+$"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "domain": {
+                    "id": domain.openstack_id,
+                    "name": domain.name,
+                    "enabled": true,
+                }
+            })))
+    }
+
+    pub fn mock_keystone_projects(
+        &self,
+        domain: &Project,
+        projects: &[(&str, &str)],
+    ) -> Mock {
+        let projects = projects
+            .iter()
+            .map(|(id, name)| {
+                json!({
+                    "id": id,
+                    "name": name,
+                    "domain_id": domain.openstack_id,
+                    "enabled": true,
+                    "is_domain": false,
+                    "parent_id": domain.openstack_id,
+                    "description": null,
+                })
+            })
+            .collect::<Vec<_>>();
+        Mock::given(method("GET"))
+            .and(path("/projects"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "projects": projects })),
+            )
+    }
+
     pub async fn setup_test_user_and_project(
         &self,
         admin: bool,
@@ -498,6 +658,14 @@ impl TestApp {
 }
 
 pub async fn spawn_app() -> TestApp {
+    spawn_app_with_configuration(|_| {}).await
+}
+
+// like spawn_app, but the configuration can be adjusted before the
+// application is built, e.g. to point the alerts at local stand-ins
+pub async fn spawn_app_with_configuration(
+    configure: impl FnOnce(&mut Settings),
+) -> TestApp {
     Lazy::force(&TRACING);
 
     let keystone_server = MockServer::start().await;
@@ -508,7 +676,9 @@ pub async fn spawn_app() -> TestApp {
         c.database.database_name = Uuid::new_v4().simple().to_string();
         c.application.port = 0;
         c.openstack.keystone_endpoint = keystone_server.uri();
+        c.openstack.nova_endpoint = keystone_server.uri();
         c.application.insert_admin = false;
+        configure(&mut c);
         c
     };
 
@@ -646,3 +816,11 @@ pub fn random_number(range: Range<u32>) -> u32 {
 pub fn random_bool() -> bool {
     rng().random_bool(0.5)
 }
+
+// costs are sums of floating point fractions, so compare them with a margin
+pub fn assert_cost_eq(left: f64, right: f64) {
+    assert!(
+        (left - right).abs() < 1e-6,
+        "cost {left} differs from {right}"
+    );
+}
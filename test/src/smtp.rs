@@ -0,0 +1,77 @@
+use std::sync::{Arc, Mutex};
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+
+// Minimal plain SMTP server that accepts every message and keeps its
+// content, standing in for a mail relay in the tests.
+pub struct MockSmtpServer {
+    port: u16,
+    messages: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockSmtpServer {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind mock SMTP server.");
+        let port = listener.local_addr().unwrap().port();
+        let messages = Arc::new(Mutex::new(vec![]));
+        let received = Arc::clone(&messages);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve_session(stream, Arc::clone(&received)));
+            }
+        });
+        Self { port, messages }
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    // headers and body of each received message
+    pub fn messages(&self) -> Vec<String> {
+        self.messages.lock().unwrap().clone()
+    }
+}
+
+async fn serve_session(
+    stream: TcpStream,
+    messages: Arc<Mutex<Vec<String>>>,
+) -> std::io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    writer.write_all(b"220 localhost ESMTP\r\n").await?;
+    let mut data: Option<String> = None;
+    while let Some(line) = lines.next_line().await? {
+        if let Some(message) = data.as_mut() {
+            if line == "." {
+                messages.lock().unwrap().push(data.take().unwrap());
+                writer.write_all(b"250 OK\r\n").await?;
+            } else {
+                message.push_str(&line);
+                message.push('\n');
+            }
+            continue;
+        }
+        let command = line.to_uppercase();
+        if command.starts_with("QUIT") {
+            writer.write_all(b"221 Bye\r\n").await?;
+            break;
+        }
+        let reply: &[u8] =
+            if command.starts_with("EHLO") || command.starts_with("HELO") {
+                b"250 localhost\r\n"
+            } else if command.starts_with("DATA") {
+                data = Some(String::new());
+                b"354 End data with <CR><LF>.<CR><LF>\r\n"
+            } else {
+                b"250 OK\r\n"
+            };
+        writer.write_all(reply).await?;
+    }
+    Ok(())
+}
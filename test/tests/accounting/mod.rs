@@ -1 +1,3 @@
+mod server_consumption;
+mod server_cost;
 mod server_state;
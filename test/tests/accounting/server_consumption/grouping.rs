@@ -0,0 +1,115 @@
+use std::str::FromStr;
+
+use avina::{Api, Token};
+use avina_test::{random_alphanumeric_string, random_uuid, spawn_app};
+use avina_wire::accounting::TimeGrouping;
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+
+fn utc(month: u32, day: u32, hour: u32) -> DateTime<FixedOffset> {
+    Utc.with_ymd_and_hms(2024, month, day, hour, 0, 0)
+        .unwrap()
+        .fixed_offset()
+}
+
+#[tokio::test]
+async fn e2e_lib_server_consumption_by_month_splits_state_at_month_start() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 0)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+    let server_id = random_uuid();
+    client
+        .server_state
+        .create(
+            utc(1, 31, 12),
+            server_id.clone(),
+            random_alphanumeric_string(10),
+            flavor.id,
+            "ACTIVE".to_string(),
+            user.id,
+        )
+        .end(utc(2, 1, 12))
+        .send()
+        .await
+        .unwrap();
+
+    // act
+    let buckets = client
+        .server_consumption
+        .get()
+        .begin(utc(1, 31, 0))
+        .end(utc(2, 2, 0))
+        .server_by(&server_id, TimeGrouping::Month)
+        .await
+        .unwrap();
+
+    // assert
+    // February begins at 23:00 UTC in Europe/Berlin
+    assert_eq!(buckets.len(), 2);
+    assert_eq!(buckets[0].end, utc(1, 31, 23));
+    assert_eq!(buckets[1].begin, utc(1, 31, 23));
+    assert_eq!(buckets[0].consumption[&flavor.name], 11.0 * 3600.0);
+    assert_eq!(buckets[1].consumption[&flavor.name], 13.0 * 3600.0);
+}
+
+#[tokio::test]
+async fn e2e_lib_server_consumption_by_day_refuses_too_many_buckets() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 0)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let buckets = client
+        .server_consumption
+        .get()
+        .begin(
+            Utc.with_ymd_and_hms(1970, 1, 1, 0, 0, 0)
+                .unwrap()
+                .fixed_offset(),
+        )
+        .end(utc(1, 1, 0))
+        .user_by(user.id, TimeGrouping::Day)
+        .await;
+
+    // assert
+    assert!(buckets.is_err());
+    assert_eq!(
+        buckets.unwrap_err().to_string(),
+        "Too many buckets, at most 1000 are allowed per request".to_string()
+    );
+}
@@ -0,0 +1,131 @@
+use std::str::FromStr;
+
+use avina::{Api, Token};
+use avina_test::{random_alphanumeric_string, random_uuid, spawn_app};
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+
+fn time(month: u32, day: u32) -> DateTime<FixedOffset> {
+    Utc.with_ymd_and_hms(2020, month, day, 0, 0, 0)
+        .unwrap()
+        .fixed_offset()
+}
+
+#[tokio::test]
+async fn e2e_lib_server_cost_all_matches_sum_of_projects() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 1)
+        .await
+        .expect("Failed to setup test project");
+    let other_project = server
+        .setup_test_project(0, 1, 1)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+    for user_class in 1..=6 {
+        let price = 100.0 * user_class as f64;
+        client
+            .flavor_price
+            .create(flavor.id, user_class)
+            .price(price)
+            .start_time(time(1, 1))
+            .send()
+            .await
+            .unwrap();
+        client
+            .flavor_price
+            .create(flavor.id, user_class)
+            .price(price * 2.0)
+            .start_time(time(3, 1))
+            .send()
+            .await
+            .unwrap();
+    }
+    let users = [
+        test_project.admins[0].user.clone(),
+        test_project.normals[0].user.clone(),
+        other_project.masters[0].user.clone(),
+        other_project.normals[0].user.clone(),
+    ];
+    for (i, user) in users.iter().enumerate() {
+        let instance_id = random_uuid();
+        let month = i as u32 + 1;
+        for (begin, end, status) in [
+            (time(month, 5), Some(time(month + 1, 10)), "ACTIVE"),
+            (time(month + 1, 10), Some(time(month + 2, 1)), "SHUTOFF"),
+            (time(month + 2, 1), None, "SHELVED"),
+        ] {
+            let mut request = client.server_state.create(
+                begin,
+                instance_id.clone(),
+                random_alphanumeric_string(10),
+                flavor.id,
+                status.to_string(),
+                user.id,
+            );
+            if let Some(end) = end {
+                request.end(end);
+            }
+            request.send().await.unwrap();
+        }
+    }
+    let request = || {
+        let mut request = client.server_cost.get();
+        request.begin(time(2, 15)).end(time(6, 1));
+        request
+    };
+
+    // act
+    let all = request().all().await.unwrap();
+    let all_detail = request().all_detail().await.unwrap();
+
+    // assert
+    let test_project_cost = request()
+        .project_detail(test_project.project.id)
+        .await
+        .unwrap();
+    let other_project_cost = request()
+        .project_detail(other_project.project.id)
+        .await
+        .unwrap();
+    assert!(test_project_cost.total > 0.0);
+    assert!(other_project_cost.total > 0.0);
+    let sum = test_project_cost.total + other_project_cost.total;
+    assert!((all.total - sum).abs() < 1e-6);
+    assert!((all_detail.total - sum).abs() < 1e-6);
+    for project_cost in [test_project_cost, other_project_cost] {
+        let project_name = if project_cost.users.contains_key(&user.name) {
+            &test_project.project.name
+        } else {
+            &other_project.project.name
+        };
+        let all_project_cost = &all_detail.projects[project_name];
+        assert!((all_project_cost.total - project_cost.total).abs() < 1e-6);
+        assert_eq!(all_project_cost.users.len(), project_cost.users.len());
+        for (user_name, user_cost) in project_cost.users {
+            let all_user_cost = &all_project_cost.users[&user_name];
+            assert!((all_user_cost.total - user_cost.total).abs() < 1e-6);
+            assert_eq!(
+                all_user_cost.servers.keys().collect::<Vec<_>>(),
+                user_cost.servers.keys().collect::<Vec<_>>()
+            );
+        }
+    }
+}
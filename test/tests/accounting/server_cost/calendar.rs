@@ -0,0 +1,184 @@
+use std::str::FromStr;
+
+use avina::{Api, Token};
+use avina_api::configuration::{Settings, YearLength};
+use avina_test::{
+    TestApp, assert_cost_eq, random_alphanumeric_string, random_uuid,
+    spawn_app, spawn_app_with_configuration,
+};
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+
+// unless configured otherwise, the tests rely on the billing calendar of the
+// base configuration, which uses Europe/Berlin with calendar year lengths
+
+fn utc(year: i32, month: u32, day: u32, hour: u32) -> DateTime<FixedOffset> {
+    Utc.with_ymd_and_hms(year, month, day, hour, 0, 0)
+        .unwrap()
+        .fixed_offset()
+}
+
+// sets up an admin client, a flavor with the given yearly price since 2019
+// and a server of the admin that is active between begin and end
+async fn setup_active_server(
+    server: &TestApp,
+    price: f64,
+    begin: DateTime<FixedOffset>,
+    end: DateTime<FixedOffset>,
+) -> (Api, u32) {
+    let test_project = server
+        .setup_test_project(1, 0, 0)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+    client
+        .flavor_price
+        .create(flavor.id, test_project.project.user_class)
+        .price(price)
+        .start_time(utc(2019, 1, 1, 0))
+        .send()
+        .await
+        .unwrap();
+    client
+        .server_state
+        .create(
+            begin,
+            random_uuid(),
+            random_alphanumeric_string(10),
+            flavor.id,
+            "ACTIVE".to_string(),
+            user.id,
+        )
+        .end(end)
+        .send()
+        .await
+        .unwrap();
+    (client, user.id)
+}
+
+#[tokio::test]
+async fn e2e_lib_server_cost_uses_366_days_in_leap_years() {
+    // arrange
+    let server = spawn_app().await;
+    let (client, user_id) = setup_active_server(
+        &server,
+        366.0,
+        utc(2024, 2, 20, 0),
+        utc(2024, 3, 1, 0),
+    )
+    .await;
+
+    // act
+    let cost = client
+        .server_cost
+        .get()
+        .begin(utc(2024, 2, 20, 0))
+        .end(utc(2024, 3, 1, 0))
+        .user(user_id)
+        .await
+        .unwrap();
+
+    // assert
+    assert_cost_eq(cost.total, 10.0);
+}
+
+#[tokio::test]
+async fn e2e_lib_server_cost_splits_at_the_start_of_the_billing_year() {
+    // arrange
+    let server = spawn_app().await;
+    // one unit per hour in 2023, the hour after 23:00 UTC on new year's eve
+    // already belongs to the leap year 2024 in Europe/Berlin
+    let (client, user_id) = setup_active_server(
+        &server,
+        365.0 * 24.0,
+        utc(2023, 12, 31, 22),
+        utc(2024, 1, 1, 0),
+    )
+    .await;
+
+    // act
+    let cost = client
+        .server_cost
+        .get()
+        .begin(utc(2023, 12, 31, 22))
+        .end(utc(2024, 1, 1, 0))
+        .user(user_id)
+        .await
+        .unwrap();
+
+    // assert
+    assert_cost_eq(cost.total, 1.0 + 365.0 / 366.0);
+}
+
+#[tokio::test]
+async fn e2e_lib_server_cost_across_daylight_saving_transition() {
+    // arrange
+    let server = spawn_app().await;
+    // clocks in Europe/Berlin go forward on 2024-03-31 at 01:00 UTC, which
+    // must not change the number of billed hours
+    let (client, user_id) = setup_active_server(
+        &server,
+        366.0 * 24.0,
+        utc(2024, 3, 30, 22),
+        utc(2024, 3, 31, 4),
+    )
+    .await;
+
+    // act
+    let cost = client
+        .server_cost
+        .get()
+        .begin(utc(2024, 3, 30, 22))
+        .end(utc(2024, 3, 31, 4))
+        .user(user_id)
+        .await
+        .unwrap();
+
+    // assert
+    assert_cost_eq(cost.total, 6.0);
+}
+
+#[tokio::test]
+async fn e2e_lib_server_cost_uses_the_billing_calendar_of_the_app() {
+    // arrange
+    let server = spawn_app_with_configuration(|c: &mut Settings| {
+        c.billing.timezone = "UTC".parse().unwrap();
+        c.billing.year_length = YearLength::Fixed;
+    })
+    .await;
+    let (client, user_id) = setup_active_server(
+        &server,
+        365.0,
+        utc(2024, 2, 20, 0),
+        utc(2024, 3, 1, 0),
+    )
+    .await;
+
+    // act
+    let cost = client
+        .server_cost
+        .get()
+        .begin(utc(2024, 2, 20, 0))
+        .end(utc(2024, 3, 1, 0))
+        .user(user_id)
+        .await
+        .unwrap();
+
+    // assert
+    assert_cost_eq(cost.total, 10.0);
+}
@@ -0,0 +1,176 @@
+use std::str::FromStr;
+
+use avina::{Api, Token};
+use avina_test::{
+    assert_cost_eq, random_alphanumeric_string, random_uuid, spawn_app,
+};
+use avina_wire::accounting::TimeGrouping;
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+
+fn utc(year: i32, month: u32, day: u32) -> DateTime<FixedOffset> {
+    Utc.with_ymd_and_hms(year, month, day, 0, 0, 0)
+        .unwrap()
+        .fixed_offset()
+}
+
+#[tokio::test]
+async fn e2e_lib_server_cost_by_year_splits_states_and_price_changes() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 0)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+    let user_class = test_project.project.user_class;
+    for (price, start_time) in
+        [(365.0, utc(2019, 1, 1)), (730.0, utc(2024, 7, 1))]
+    {
+        client
+            .flavor_price
+            .create(flavor.id, user_class)
+            .price(price)
+            .start_time(start_time)
+            .send()
+            .await
+            .unwrap();
+    }
+    // the server runs through both year boundaries and the price change
+    client
+        .server_state
+        .create(
+            utc(2023, 7, 1),
+            random_uuid(),
+            random_alphanumeric_string(10),
+            flavor.id,
+            "ACTIVE".to_string(),
+            user.id,
+        )
+        .end(utc(2025, 7, 1))
+        .send()
+        .await
+        .unwrap();
+    let (begin, end) = (utc(2023, 7, 1), utc(2025, 6, 1));
+    let total = client
+        .server_cost
+        .get()
+        .begin(begin)
+        .end(end)
+        .user(user.id)
+        .await
+        .unwrap()
+        .total;
+
+    // act
+    let buckets = client
+        .server_cost
+        .get()
+        .begin(begin)
+        .end(end)
+        .user_by(user.id, TimeGrouping::Year)
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(buckets.len(), 3);
+    assert_eq!(buckets[0].begin, begin);
+    assert_eq!(buckets[2].end, end);
+    for (previous, next) in buckets.iter().zip(buckets.iter().skip(1)) {
+        assert_eq!(previous.end, next.begin);
+    }
+    assert!(buckets.iter().all(|bucket| bucket.cost.total > 0.0));
+    assert_cost_eq(
+        buckets.iter().map(|bucket| bucket.cost.total).sum::<f64>(),
+        total,
+    );
+    // 2024 is a leap year with half a year at each price
+    assert!(buckets[1].cost.total > 365.0 && buckets[1].cost.total < 730.0);
+}
+
+#[tokio::test]
+async fn e2e_lib_server_cost_by_month_denies_all_to_normal_user() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(0, 0, 1)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.normals[0].user.clone();
+    let token = test_project.normals[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let buckets = client.server_cost.get().all_by(TimeGrouping::Month).await;
+
+    // assert
+    assert!(buckets.is_err());
+    assert_eq!(
+        buckets.unwrap_err().to_string(),
+        "Admin privileges required".to_string()
+    );
+}
+
+#[tokio::test]
+async fn e2e_lib_server_cost_by_day_refuses_too_many_buckets() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 0)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let buckets = client
+        .server_cost
+        .get()
+        .begin(utc(1970, 1, 1))
+        .end(utc(2024, 1, 1))
+        .user_by(user.id, TimeGrouping::Day)
+        .await;
+
+    // assert
+    assert!(buckets.is_err());
+    assert_eq!(
+        buckets.unwrap_err().to_string(),
+        "Too many buckets, at most 1000 are allowed per request".to_string()
+    );
+}
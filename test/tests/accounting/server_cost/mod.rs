@@ -0,0 +1,6 @@
+mod all;
+mod calendar;
+mod grouping;
+mod rate;
+mod rollup;
+mod series;
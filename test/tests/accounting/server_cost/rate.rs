@@ -0,0 +1,128 @@
+use std::str::FromStr;
+
+use avina::{Api, Token};
+use avina_test::{
+    assert_cost_eq, random_alphanumeric_string, random_uuid, spawn_app,
+};
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+
+fn utc(year: i32, month: u32, day: u32) -> DateTime<FixedOffset> {
+    Utc.with_ymd_and_hms(year, month, day, 0, 0, 0)
+        .unwrap()
+        .fixed_offset()
+}
+
+#[tokio::test]
+async fn e2e_lib_server_cost_rate_denies_all_to_normal_user() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(0, 0, 1)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.normals[0].user.clone();
+    let token = test_project.normals[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let rate = client.server_cost.rate().all().await;
+
+    // assert
+    assert!(rate.is_err());
+    assert_eq!(
+        rate.unwrap_err().to_string(),
+        "Admin privileges required".to_string()
+    );
+}
+
+#[tokio::test]
+async fn e2e_lib_server_cost_rate_only_counts_running_servers() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 0)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+    // about one unit per day
+    client
+        .flavor_price
+        .create(flavor.id, test_project.project.user_class)
+        .price(365.0)
+        .start_time(utc(2019, 1, 1))
+        .send()
+        .await
+        .unwrap();
+    let running = [random_uuid(), random_uuid()];
+    for (instance_id, status, end) in [
+        (running[0].clone(), "ACTIVE", None),
+        (running[1].clone(), "ACTIVE", None),
+        (random_uuid(), "SHUTOFF", None),
+        (random_uuid(), "ACTIVE", Some(utc(2021, 1, 1))),
+    ] {
+        let mut request = client.server_state.create(
+            utc(2020, 1, 1),
+            instance_id,
+            random_alphanumeric_string(10),
+            flavor.id,
+            status.to_string(),
+            user.id,
+        );
+        if let Some(end) = end {
+            request.end(end);
+        }
+        request.send().await.unwrap();
+    }
+
+    // act
+    let rate = client
+        .server_cost
+        .rate()
+        .project(test_project.project.id)
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(rate.servers.len(), 2);
+    for server in rate.servers.iter() {
+        assert!(running.contains(&server.server_id));
+        assert_eq!(server.flavor, flavor.name);
+        assert_eq!(server.user_id, user.id);
+        assert_cost_eq(server.hourly * 24.0, server.daily);
+        // a month is a twelfth of the year regardless of its length
+        assert_cost_eq(server.monthly, 365.0 / 12.0);
+        assert!(server.daily > 365.0 / 366.0 - 1e-6);
+        assert!(server.daily < 1.0 + 1e-6);
+    }
+    assert_eq!(rate.flavors.len(), 1);
+    assert_eq!(rate.flavors[0].server_count, 2);
+    assert_cost_eq(rate.flavors[0].monthly, 2.0 * 365.0 / 12.0);
+    assert_cost_eq(rate.monthly, 2.0 * 365.0 / 12.0);
+    assert_cost_eq(rate.daily, rate.servers[0].daily * 2.0);
+}
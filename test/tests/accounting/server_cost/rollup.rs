@@ -0,0 +1,534 @@
+use std::str::FromStr;
+
+use avina::{Api, Token};
+use avina_test::{
+    assert_cost_eq, random_alphanumeric_string, random_uuid, spawn_app,
+};
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+
+fn time(day: u32, hour: u32, minute: u32) -> DateTime<FixedOffset> {
+    Utc.with_ymd_and_hms(2020, 6, day, hour, minute, 0)
+        .unwrap()
+        .fixed_offset()
+}
+
+// the test configuration bills in Europe/Berlin, which is two hours ahead
+// of UTC in June
+fn local_midnight(day: u32) -> DateTime<FixedOffset> {
+    FixedOffset::east_opt(2 * 60 * 60)
+        .unwrap()
+        .with_ymd_and_hms(2020, 6, day, 0, 0, 0)
+        .unwrap()
+}
+
+#[tokio::test]
+async fn e2e_lib_server_cost_rollup_denies_access_to_normal_user() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(0, 0, 1)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.normals[0].user.clone();
+    let token = test_project.normals[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let rollup = client.server_cost.rollup().send().await;
+
+    // assert
+    assert!(rollup.is_err());
+    assert_eq!(
+        rollup.unwrap_err().to_string(),
+        "Admin privileges required".to_string()
+    );
+}
+
+#[tokio::test]
+async fn e2e_lib_server_cost_rollup_matches_raw_calculation() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(2, 0, 0)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    let other_user = test_project.admins[1].user.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+    let user_class = test_project.project.user_class;
+    client
+        .flavor_price
+        .create(flavor.id, user_class)
+        .price(1000.0)
+        .start_time(Utc.with_ymd_and_hms(2019, 1, 1, 0, 0, 0).unwrap().into())
+        .send()
+        .await
+        .unwrap();
+    // the price changes in the middle of a rolled up day
+    client
+        .flavor_price
+        .create(flavor.id, user_class)
+        .price(2500.0)
+        .start_time(time(12, 13, 14))
+        .send()
+        .await
+        .unwrap();
+    let instance_id = random_uuid();
+    let other_instance_id = random_uuid();
+    let states = [
+        (
+            &instance_id,
+            user.id,
+            time(3, 8, 30),
+            Some(time(9, 17, 45)),
+            "ACTIVE",
+        ),
+        (
+            &instance_id,
+            user.id,
+            time(9, 17, 45),
+            Some(time(11, 3, 0)),
+            "SHUTOFF",
+        ),
+        (
+            &instance_id,
+            user.id,
+            time(11, 3, 0),
+            Some(time(14, 0, 0)),
+            "SHELVED",
+        ),
+        (&instance_id, user.id, time(14, 0, 0), None, "ACTIVE"),
+        (
+            &other_instance_id,
+            other_user.id,
+            time(6, 23, 59),
+            Some(time(22, 12, 0)),
+            "ACTIVE",
+        ),
+    ];
+    for (instance_id, user_id, begin, end, status) in states {
+        let mut request = client.server_state.create(
+            begin,
+            instance_id.clone(),
+            random_alphanumeric_string(10),
+            flavor.id,
+            status.to_string(),
+            user_id,
+        );
+        if let Some(end) = end {
+            request.end(end);
+        }
+        request.send().await.unwrap();
+    }
+    let (begin, end) = (time(5, 6, 0), time(25, 18, 0));
+    macro_rules! costs {
+        () => {{
+            let request = || {
+                let mut request = client.server_cost.get();
+                request.begin(begin).end(end);
+                request
+            };
+            (
+                request().server(&instance_id).await.unwrap().total,
+                request().user(user.id).await.unwrap().total,
+                request().user_detail(other_user.id).await.unwrap(),
+                request()
+                    .project(test_project.project.id)
+                    .await
+                    .unwrap()
+                    .total,
+                request().all().await.unwrap().total,
+            )
+        }};
+    }
+    let raw = costs!();
+
+    // act
+    let rollup = client
+        .server_cost
+        .rollup()
+        .begin(time(1, 0, 0))
+        .end(time(20, 0, 0))
+        .send()
+        .await
+        .unwrap();
+
+    // assert
+    // the rolled up days are the local days covering begin and end
+    assert_eq!(rollup.day_count, 20);
+    assert_eq!(rollup.begin, local_midnight(1));
+    assert_eq!(rollup.end, local_midnight(21));
+    let rolled_up = costs!();
+    assert!(raw.0 > 0.0);
+    assert_cost_eq(raw.0, rolled_up.0);
+    assert_cost_eq(raw.1, rolled_up.1);
+    assert_cost_eq(raw.2.total, rolled_up.2.total);
+    assert_eq!(raw.2.servers.len(), rolled_up.2.servers.len());
+    assert_cost_eq(
+        raw.2.flavors[&flavor.name],
+        rolled_up.2.flavors[&flavor.name],
+    );
+    assert_cost_eq(raw.3, rolled_up.3);
+    assert_cost_eq(raw.4, rolled_up.4);
+    assert_cost_eq(raw.1 + raw.2.total, rolled_up.3);
+}
+
+#[tokio::test]
+async fn e2e_lib_server_cost_rollup_is_refreshed_on_price_change() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 0)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+    let user_class = test_project.project.user_class;
+    client
+        .flavor_price
+        .create(flavor.id, user_class)
+        // 2020 is a leap year, so one unit per day
+        .price(366.0)
+        .start_time(Utc.with_ymd_and_hms(2019, 1, 1, 0, 0, 0).unwrap().into())
+        .send()
+        .await
+        .unwrap();
+    client
+        .server_state
+        .create(
+            time(1, 0, 0),
+            random_uuid(),
+            random_alphanumeric_string(10),
+            flavor.id,
+            "ACTIVE".to_string(),
+            user.id,
+        )
+        .end(time(11, 0, 0))
+        .send()
+        .await
+        .unwrap();
+    client
+        .server_cost
+        .rollup()
+        .begin(time(1, 0, 0))
+        .end(time(11, 0, 0))
+        .send()
+        .await
+        .unwrap();
+    let cost_before = client
+        .server_cost
+        .get()
+        .begin(time(1, 0, 0))
+        .end(time(11, 0, 0))
+        .user(user.id)
+        .await
+        .unwrap();
+
+    // act
+    client
+        .flavor_price
+        .create(flavor.id, user_class)
+        .price(732.0)
+        .start_time(time(6, 0, 0))
+        .send()
+        .await
+        .unwrap();
+
+    // assert
+    let cost_after = client
+        .server_cost
+        .get()
+        .begin(time(1, 0, 0))
+        .end(time(11, 0, 0))
+        .user(user.id)
+        .await
+        .unwrap();
+    assert_cost_eq(cost_before.total, 10.0);
+    assert_cost_eq(cost_after.total, 15.0);
+}
+
+#[tokio::test]
+async fn e2e_lib_server_cost_rollup_is_refreshed_on_user_class_change() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 0)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+    let user_class = test_project.project.user_class;
+    let other_user_class = user_class % 5 + 1;
+    // 2020 is a leap year, so one and two units per day
+    for (user_class, price) in [(user_class, 366.0), (other_user_class, 732.0)]
+    {
+        client
+            .flavor_price
+            .create(flavor.id, user_class)
+            .price(price)
+            .start_time(
+                Utc.with_ymd_and_hms(2019, 1, 1, 0, 0, 0).unwrap().into(),
+            )
+            .send()
+            .await
+            .unwrap();
+    }
+    client
+        .server_state
+        .create(
+            time(1, 0, 0),
+            random_uuid(),
+            random_alphanumeric_string(10),
+            flavor.id,
+            "ACTIVE".to_string(),
+            user.id,
+        )
+        .end(time(11, 0, 0))
+        .send()
+        .await
+        .unwrap();
+    client
+        .server_cost
+        .rollup()
+        .begin(time(1, 0, 0))
+        .end(time(11, 0, 0))
+        .send()
+        .await
+        .unwrap();
+
+    // act
+    client
+        .project
+        .modify(test_project.project.id)
+        .user_class(other_user_class)
+        .send()
+        .await
+        .unwrap();
+
+    // assert
+    let cost = client
+        .server_cost
+        .get()
+        .begin(time(1, 0, 0))
+        .end(time(11, 0, 0))
+        .user(user.id)
+        .await
+        .unwrap();
+    assert_cost_eq(cost.total, 20.0);
+}
+
+#[tokio::test]
+async fn e2e_lib_server_cost_rollup_is_refreshed_on_project_change() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 1)
+        .await
+        .expect("Failed to setup test project");
+    let other_project = server
+        .setup_test_project(0, 0, 0)
+        .await
+        .expect("Failed to setup test project");
+    let admin = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    let user = test_project.normals[0].user.clone();
+    server
+        .mock_keystone_auth(&token, &admin.openstack_id, &admin.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+    for user_class in 1..=6 {
+        client
+            .flavor_price
+            .create(flavor.id, user_class)
+            // 2020 is a leap year, so one unit per day
+            .price(366.0)
+            .start_time(
+                Utc.with_ymd_and_hms(2019, 1, 1, 0, 0, 0).unwrap().into(),
+            )
+            .send()
+            .await
+            .unwrap();
+    }
+    client
+        .server_state
+        .create(
+            time(1, 0, 0),
+            random_uuid(),
+            random_alphanumeric_string(10),
+            flavor.id,
+            "ACTIVE".to_string(),
+            user.id,
+        )
+        .end(time(11, 0, 0))
+        .send()
+        .await
+        .unwrap();
+    client
+        .server_cost
+        .rollup()
+        .begin(time(1, 0, 0))
+        .end(time(11, 0, 0))
+        .send()
+        .await
+        .unwrap();
+
+    // act
+    client
+        .user
+        .modify(user.id)
+        .project(other_project.project.id)
+        .send()
+        .await
+        .unwrap();
+
+    // assert
+    let cost = |project_id| {
+        let mut request = client.server_cost.get();
+        request.begin(time(1, 0, 0)).end(time(11, 0, 0));
+        async move { request.project(project_id).await.unwrap().total }
+    };
+    assert_cost_eq(cost(test_project.project.id).await, 0.0);
+    assert_cost_eq(cost(other_project.project.id).await, 10.0);
+}
+
+#[tokio::test]
+async fn e2e_lib_server_cost_rollup_is_refreshed_on_server_state_repair() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 0)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+    client
+        .flavor_price
+        .create(flavor.id, test_project.project.user_class)
+        // 2020 is a leap year, so one unit per day
+        .price(366.0)
+        .start_time(Utc.with_ymd_and_hms(2019, 1, 1, 0, 0, 0).unwrap().into())
+        .send()
+        .await
+        .unwrap();
+    // two open states of the same server, the first one is closed when
+    // the second one begins by the repair
+    let instance_id = random_uuid();
+    let instance_name = random_alphanumeric_string(10);
+    for begin in [time(1, 0, 0), time(6, 0, 0)] {
+        client
+            .server_state
+            .create(
+                begin,
+                instance_id.clone(),
+                instance_name.clone(),
+                flavor.id,
+                "ACTIVE".to_string(),
+                user.id,
+            )
+            .send()
+            .await
+            .unwrap();
+    }
+    client
+        .server_cost
+        .rollup()
+        .begin(time(1, 0, 0))
+        .end(time(11, 0, 0))
+        .send()
+        .await
+        .unwrap();
+    let cost = || {
+        let mut request = client.server_cost.get();
+        request.begin(time(1, 0, 0)).end(time(11, 0, 0));
+        async move { request.user(user.id).await.unwrap().total }
+    };
+    let cost_before = cost().await;
+
+    // act
+    client.server_state.check().repair().send().await.unwrap();
+
+    // assert
+    assert_cost_eq(cost_before, 15.0);
+    assert_cost_eq(cost().await, 10.0);
+}
@@ -0,0 +1,178 @@
+use std::str::FromStr;
+
+use avina::{Api, Token};
+use avina_test::{
+    assert_cost_eq, random_alphanumeric_string, random_uuid, spawn_app,
+};
+use avina_wire::accounting::TimeGrouping;
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+
+// midnight in Europe/Berlin during winter time, which the billing calendar
+// of the base configuration uses
+fn midnight(month: u32, day: u32) -> DateTime<FixedOffset> {
+    FixedOffset::east_opt(3600)
+        .unwrap()
+        .with_ymd_and_hms(2021, month, day, 0, 0, 0)
+        .unwrap()
+}
+
+#[tokio::test]
+async fn e2e_lib_server_cost_series_denies_all_to_normal_user() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(0, 0, 1)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.normals[0].user.clone();
+    let token = test_project.normals[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let series = client.server_cost.series().all().await;
+
+    // assert
+    assert!(series.is_err());
+    assert_eq!(
+        series.unwrap_err().to_string(),
+        "Admin privileges required".to_string()
+    );
+}
+
+#[tokio::test]
+async fn e2e_lib_server_cost_series_returns_points_per_month_and_day() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 0)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+    // one unit per day in 2021
+    client
+        .flavor_price
+        .create(flavor.id, test_project.project.user_class)
+        .price(365.0)
+        .start_time(Utc.with_ymd_and_hms(2019, 1, 1, 0, 0, 0).unwrap().into())
+        .send()
+        .await
+        .unwrap();
+    client
+        .server_state
+        .create(
+            midnight(1, 1),
+            random_uuid(),
+            random_alphanumeric_string(10),
+            flavor.id,
+            "ACTIVE".to_string(),
+            user.id,
+        )
+        .end(midnight(3, 1))
+        .send()
+        .await
+        .unwrap();
+
+    // act
+    let months = client
+        .server_cost
+        .series()
+        .begin(midnight(1, 1))
+        .end(midnight(3, 1))
+        .by(TimeGrouping::Month)
+        .project(test_project.project.id)
+        .await
+        .unwrap();
+    let days = client
+        .server_cost
+        .series()
+        .begin(midnight(2, 26))
+        .end(midnight(3, 3))
+        .by(TimeGrouping::Day)
+        .user(user.id)
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(months.len(), 2);
+    assert_eq!(months[0].begin, midnight(1, 1));
+    assert_eq!(months[0].end, midnight(2, 1));
+    assert_cost_eq(months[0].cost, 31.0);
+    assert_cost_eq(months[1].cost, 28.0);
+    assert_cost_eq(months[1].cumulative, 59.0);
+    assert_eq!(days.len(), 5);
+    for (i, day) in days.iter().enumerate() {
+        let expected = if i < 3 { 1.0 } else { 0.0 };
+        assert_cost_eq(day.cost, expected);
+    }
+    assert_cost_eq(days[4].cumulative, 3.0);
+}
+
+#[tokio::test]
+async fn e2e_lib_server_cost_series_refuses_too_many_points() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 0)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let series = client
+        .server_cost
+        .series()
+        .begin(
+            Utc.with_ymd_and_hms(1970, 1, 1, 0, 0, 0)
+                .unwrap()
+                .fixed_offset(),
+        )
+        .end(midnight(1, 1))
+        .by(TimeGrouping::Day)
+        .user(user.id)
+        .await;
+
+    // assert
+    assert!(series.is_err());
+    assert_eq!(
+        series.unwrap_err().to_string(),
+        "Too many buckets, at most 1000 are allowed per request".to_string()
+    );
+}
@@ -0,0 +1,157 @@
+use std::str::FromStr;
+
+use avina::{Api, Token};
+use avina_test::{random_alphanumeric_string, random_uuid, spawn_app};
+use chrono::{DateTime, Datelike, FixedOffset, TimeZone, Utc};
+
+fn date(year: i32, month: u32) -> DateTime<FixedOffset> {
+    Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0)
+        .unwrap()
+        .fixed_offset()
+}
+
+#[tokio::test]
+async fn e2e_lib_server_state_archive_denies_access_to_normal_user() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(0, 0, 1)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.normals[0].user.clone();
+    let token = test_project.normals[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let archive = client.server_state.archive(2).await;
+
+    // assert
+    assert!(archive.is_err());
+    assert_eq!(
+        archive.unwrap_err().to_string(),
+        "Admin privileges required".to_string()
+    );
+}
+
+#[tokio::test]
+async fn e2e_lib_server_state_archive_keeps_costs_identical() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 0)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+    client
+        .flavor_price
+        .create(flavor.id, test_project.project.user_class)
+        .price(1000.0)
+        .start_time(date(2019, 1))
+        .send()
+        .await
+        .unwrap();
+    let instance_id = random_uuid();
+    let archived_instance_id = random_uuid();
+    let states = [
+        (&instance_id, date(2020, 3), Some(date(2020, 6)), "ACTIVE"),
+        (&instance_id, date(2020, 6), Some(date(2021, 2)), "SHUTOFF"),
+        (&instance_id, date(2021, 2), None, "ACTIVE"),
+        (
+            &archived_instance_id,
+            date(2020, 1),
+            Some(date(2020, 9)),
+            "ACTIVE",
+        ),
+    ];
+    for (instance_id, begin, end, status) in states {
+        let mut request = client.server_state.create(
+            begin,
+            instance_id.clone(),
+            random_alphanumeric_string(10),
+            flavor.id,
+            status.to_string(),
+            user.id,
+        );
+        if let Some(end) = end {
+            request.end(end);
+        }
+        request.send().await.unwrap();
+    }
+    let user_cost_before = client
+        .server_cost
+        .get()
+        .begin(date(2020, 1))
+        .end(date(2022, 1))
+        .user_detail(user.id)
+        .await
+        .unwrap();
+    let server_cost_before = client
+        .server_cost
+        .get()
+        .begin(date(2020, 1))
+        .end(date(2022, 1))
+        .server(&archived_instance_id)
+        .await
+        .unwrap();
+
+    // act
+    let years = (Utc::now().year() - 2021) as u32;
+    let archive = client.server_state.archive(years).await.unwrap();
+
+    // assert
+    assert_eq!(archive.before, date(2021, 1));
+    assert_eq!(archive.archived_state_count, 2);
+    let server_states = client
+        .server_state
+        .list()
+        .server(&instance_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(server_states.len(), 2);
+    let user_cost_after = client
+        .server_cost
+        .get()
+        .begin(date(2020, 1))
+        .end(date(2022, 1))
+        .user_detail(user.id)
+        .await
+        .unwrap();
+    assert_eq!(user_cost_before, user_cost_after);
+    let server_cost_after = client
+        .server_cost
+        .get()
+        .begin(date(2020, 1))
+        .end(date(2022, 1))
+        .server(&archived_instance_id)
+        .await
+        .unwrap();
+    assert_eq!(server_cost_before, server_cost_after);
+    assert!(server_cost_after.total > 0.0);
+}
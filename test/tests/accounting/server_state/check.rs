@@ -0,0 +1,226 @@
+use std::str::FromStr;
+
+use avina::{Api, Token};
+use avina_test::{random_alphanumeric_string, random_uuid, spawn_app};
+use avina_wire::accounting::ServerStateIssueKind;
+use chrono::{DateTime, FixedOffset, SubsecRound, TimeDelta, Utc};
+
+#[tokio::test]
+async fn e2e_lib_server_state_check_denies_access_to_normal_user() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(0, 0, 1)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.normals[0].user.clone();
+    let token = test_project.normals[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let check = client.server_state.check().send().await;
+
+    // assert
+    assert!(check.is_err());
+    assert_eq!(
+        check.unwrap_err().to_string(),
+        "Admin privileges required".to_string()
+    );
+}
+
+#[tokio::test]
+async fn e2e_lib_server_state_check_reports_and_repairs_issues() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 0)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+    let now = DateTime::<FixedOffset>::from(Utc::now()).trunc_subsecs(0);
+    let t = |hours| now - TimeDelta::hours(hours);
+    let instance_id = random_uuid();
+    let instance_name = random_alphanumeric_string(10);
+    let create = |begin, end: Option<DateTime<FixedOffset>>, status: &str| {
+        let mut request = client.server_state.create(
+            begin,
+            instance_id.clone(),
+            instance_name.clone(),
+            flavor.id,
+            status.to_string(),
+            user.id,
+        );
+        if let Some(end) = end {
+            request.end(end);
+        }
+        request
+    };
+    // two identical adjacent states, then two open states
+    let state_1 = create(t(4), Some(t(3)), "ACTIVE").send().await.unwrap();
+    let _state_2 = create(t(3), Some(t(2)), "ACTIVE").send().await.unwrap();
+    let state_3 = create(t(2), None, "SHUTOFF").send().await.unwrap();
+    let _state_4 = create(t(1), None, "SHUTOFF").send().await.unwrap();
+    // a state of another server with a broken end and status
+    let broken = client
+        .server_state
+        .create(
+            t(1),
+            random_uuid(),
+            random_alphanumeric_string(10),
+            flavor.id,
+            "BROKEN".to_string(),
+            user.id,
+        )
+        .end(t(2))
+        .send()
+        .await
+        .unwrap();
+
+    // act and assert 1 - report only
+    let check = client.server_state.check().send().await.unwrap();
+    assert!(!check.repair);
+    assert_eq!(check.checked_state_count, 5);
+    assert_eq!(check.overlap_count, 0);
+    assert_eq!(check.duplicate_open_count, 1);
+    assert_eq!(check.negative_duration_count, 1);
+    assert_eq!(check.unknown_status_count, 1);
+    assert_eq!(check.mergeable_count, 2);
+    assert_eq!(check.closed_state_count, 0);
+    assert_eq!(check.merged_state_count, 0);
+    assert!(check.issues.iter().all(|i| !i.repaired));
+    assert!(check.issues.iter().any(|i| {
+        i.kind == ServerStateIssueKind::NegativeDuration
+            && i.server_state == broken.id
+    }));
+    let server_states = client
+        .server_state
+        .list()
+        .server(&instance_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(server_states.len(), 4);
+
+    // act and assert 2 - repair
+    let repair = client.server_state.check().repair().send().await.unwrap();
+    assert!(repair.repair);
+    assert_eq!(repair.closed_state_count, 1);
+    assert_eq!(repair.merged_state_count, 2);
+    let mut server_states = client
+        .server_state
+        .list()
+        .server(&instance_id)
+        .send()
+        .await
+        .unwrap();
+    server_states.sort_by_key(|s| s.begin);
+    assert_eq!(server_states.len(), 2);
+    assert_eq!(server_states[0].id, state_1.id);
+    assert_eq!(server_states[0].begin, t(4));
+    assert_eq!(server_states[0].end, Some(t(2)));
+    assert_eq!(server_states[1].id, state_3.id);
+    assert_eq!(server_states[1].end, None);
+
+    // act and assert 3 - only the unrepairable issues remain
+    let check = client.server_state.check().send().await.unwrap();
+    assert_eq!(check.duplicate_open_count, 0);
+    assert_eq!(check.mergeable_count, 0);
+    assert_eq!(check.negative_duration_count, 1);
+    assert_eq!(check.unknown_status_count, 1);
+}
+
+#[tokio::test]
+async fn e2e_lib_server_state_check_reports_overlaps_of_long_states_and_gaps() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 0)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+    let now = DateTime::<FixedOffset>::from(Utc::now()).trunc_subsecs(0);
+    let t = |hours| now - TimeDelta::hours(hours);
+    let instance_id = random_uuid();
+    let instance_name = random_alphanumeric_string(10);
+    let create = |begin, end: Option<DateTime<FixedOffset>>, status: &str| {
+        let mut request = client.server_state.create(
+            begin,
+            instance_id.clone(),
+            instance_name.clone(),
+            flavor.id,
+            status.to_string(),
+            user.id,
+        );
+        if let Some(end) = end {
+            request.end(end);
+        }
+        request
+    };
+    // a long state covering two later ones, then a state after a gap
+    let long = create(t(6), Some(t(2)), "ACTIVE").send().await.unwrap();
+    let covered_1 = create(t(5), Some(t(4)), "SHUTOFF").send().await.unwrap();
+    let covered_2 = create(t(3), Some(t(2)), "SHUTOFF").send().await.unwrap();
+    let after_gap = create(t(1), None, "ACTIVE").send().await.unwrap();
+
+    // act
+    let check = client.server_state.check().send().await.unwrap();
+
+    // assert
+    assert_eq!(check.overlap_count, 2);
+    assert_eq!(check.gap_count, 1);
+    assert_eq!(check.duplicate_open_count, 0);
+    assert_eq!(check.mergeable_count, 0);
+    for (kind, other) in [
+        (ServerStateIssueKind::Overlap, covered_1.id),
+        (ServerStateIssueKind::Overlap, covered_2.id),
+        (ServerStateIssueKind::Gap, after_gap.id),
+    ] {
+        assert!(check.issues.iter().any(|i| {
+            i.kind == kind
+                && i.server_state == long.id
+                && i.other_server_state == Some(other)
+        }));
+    }
+}
@@ -0,0 +1,126 @@
+use std::str::FromStr;
+
+use avina::{Api, Token};
+use avina_test::{random_alphanumeric_string, random_uuid, spawn_app};
+use chrono::{DateTime, FixedOffset, SubsecRound, TimeDelta, Utc};
+
+#[tokio::test]
+async fn e2e_lib_server_state_compact_denies_access_to_normal_user() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(0, 0, 1)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.normals[0].user.clone();
+    let token = test_project.normals[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let compaction = client.server_state.compact().await;
+
+    // assert
+    assert!(compaction.is_err());
+    assert_eq!(
+        compaction.unwrap_err().to_string(),
+        "Admin privileges required".to_string()
+    );
+}
+
+#[tokio::test]
+async fn e2e_lib_server_state_compact_merges_identical_adjacent_states() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 0)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+    let now = DateTime::<FixedOffset>::from(Utc::now()).trunc_subsecs(0);
+    let t = |hours| now - TimeDelta::hours(hours);
+    let instance_id = random_uuid();
+    let instance_name = random_alphanumeric_string(10);
+    let mut ids = Vec::new();
+    for (begin, end, status) in [
+        (t(5), Some(t(4)), "ACTIVE"),
+        (t(4), Some(t(3)), "ACTIVE"),
+        (t(3), Some(t(2)), "ACTIVE"),
+        (t(2), None, "SHUTOFF"),
+    ] {
+        let mut request = client.server_state.create(
+            begin,
+            instance_id.clone(),
+            instance_name.clone(),
+            flavor.id,
+            status.to_string(),
+            user.id,
+        );
+        if let Some(end) = end {
+            request.end(end);
+        }
+        ids.push(request.send().await.unwrap().id);
+    }
+    let consumption_before = client
+        .server_consumption
+        .get()
+        .begin(t(6))
+        .end(now)
+        .server(&instance_id)
+        .await
+        .unwrap();
+
+    // act
+    let compaction = client.server_state.compact().await.unwrap();
+
+    // assert
+    assert_eq!(compaction.checked_state_count, 4);
+    assert_eq!(compaction.merged_state_count, 2);
+    let mut server_states = client
+        .server_state
+        .list()
+        .server(&instance_id)
+        .send()
+        .await
+        .unwrap();
+    server_states.sort_by_key(|s| s.begin);
+    assert_eq!(server_states.len(), 2);
+    assert_eq!(server_states[0].id, ids[0]);
+    assert_eq!(server_states[0].begin, t(5));
+    assert_eq!(server_states[0].end, Some(t(2)));
+    assert_eq!(server_states[1].id, ids[3]);
+    let consumption_after = client
+        .server_consumption
+        .get()
+        .begin(t(6))
+        .end(now)
+        .server(&instance_id)
+        .await
+        .unwrap();
+    assert_eq!(consumption_before, consumption_after);
+}
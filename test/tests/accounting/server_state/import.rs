@@ -0,0 +1,711 @@
+use std::str::FromStr;
+
+use avina::{Api, Token};
+use avina_test::{nova_server_json, spawn_app, spawn_app_with_configuration};
+use chrono::{TimeDelta, Utc};
+use serde_json::json;
+use uuid::Uuid;
+use wiremock::{
+    Mock, Request, ResponseTemplate,
+    matchers::{method, path},
+};
+
+fn has_changes_since(request: &Request) -> bool {
+    request
+        .url
+        .query_pairs()
+        .any(|(key, _)| key == "changes-since")
+}
+
+#[tokio::test]
+async fn e2e_lib_admin_can_import_server_states_and_get_status() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 1)
+        .await
+        .expect("Failed to setup test project");
+    let admin = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    let user = test_project.normals[0].user.clone();
+    server
+        .mock_keystone_auth(&token, &admin.openstack_id, &admin.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let server_id = Uuid::new_v4().to_string();
+    server
+        .mock_nova_servers(&[(&server_id, "test", "ACTIVE", &flavor, &user)])
+        .mount(&server.keystone_server)
+        .await;
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act and assert 1 - status before import
+    let status = client.server_state.import_status().await.unwrap();
+    assert!(!status.scheduled);
+    assert!(!status.running);
+    assert!(status.last_begin.is_none());
+
+    // act and assert 2 - import
+    let import = client.server_state.import().await.unwrap();
+    assert_eq!(import.new_state_count, 1);
+    assert_eq!(import.end_state_count, 0);
+
+    // act and assert 3 - status after import
+    let status = client.server_state.import_status().await.unwrap();
+    assert!(status.last_begin.is_some());
+    assert!(status.last_end.is_some());
+    assert_eq!(status.new_state_count, Some(1));
+    assert_eq!(status.end_state_count, Some(0));
+    assert_eq!(status.new_flavor_count, None);
+    assert!(status.error.is_none());
+}
+
+#[tokio::test]
+async fn e2e_lib_server_state_import_detects_resizes_and_renames() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 1)
+        .await
+        .expect("Failed to setup test project");
+    let admin = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    let user = test_project.normals[0].user.clone();
+    server
+        .mock_keystone_auth(&token, &admin.openstack_id, &admin.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor_1 = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let flavor_2 = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let unchanged = server
+        .setup_test_server_state_with_server_id(
+            &flavor_1,
+            &user,
+            &Uuid::new_v4().to_string(),
+        )
+        .await
+        .expect("Failed to setup test server state");
+    let resized = server
+        .setup_test_server_state_with_server_id(
+            &flavor_1,
+            &user,
+            &Uuid::new_v4().to_string(),
+        )
+        .await
+        .expect("Failed to setup test server state");
+    let renamed = server
+        .setup_test_server_state_with_server_id(
+            &flavor_1,
+            &user,
+            &Uuid::new_v4().to_string(),
+        )
+        .await
+        .expect("Failed to setup test server state");
+    server
+        .mock_nova_servers(&[
+            (
+                &unchanged.instance_id,
+                &unchanged.instance_name,
+                "ACTIVE",
+                &flavor_1,
+                &user,
+            ),
+            (
+                &resized.instance_id,
+                &resized.instance_name,
+                "ACTIVE",
+                &flavor_2,
+                &user,
+            ),
+            (&renamed.instance_id, "renamed", "ACTIVE", &flavor_1, &user),
+        ])
+        .mount(&server.keystone_server)
+        .await;
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let import = client.server_state.import().await.unwrap();
+
+    // assert
+    assert_eq!(import.new_state_count, 2);
+    assert_eq!(import.end_state_count, 2);
+    assert_eq!(import.resize_count, 1);
+    assert_eq!(import.rename_count, 1);
+    let states = client.server_state.list().all().send().await.unwrap();
+    let open_states = states
+        .iter()
+        .filter(|s| s.end.is_none())
+        .collect::<Vec<_>>();
+    assert_eq!(open_states.len(), 3);
+    assert!(open_states.iter().any(|s| s.id == unchanged.id));
+    assert!(open_states.iter().any(
+        |s| s.instance_id == resized.instance_id && s.flavor == flavor_2.id
+    ));
+    assert!(
+        open_states
+            .iter()
+            .any(|s| s.instance_id == renamed.instance_id
+                && s.instance_name == "renamed")
+    );
+}
+
+#[tokio::test]
+async fn e2e_lib_server_state_import_keeps_unreplaceable_states_open() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 2)
+        .await
+        .expect("Failed to setup test project");
+    let admin = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    let user = test_project.normals[0].user.clone();
+    let other_user = test_project.normals[1].user.clone();
+    server
+        .mock_keystone_auth(&token, &admin.openstack_id, &admin.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let mut unknown_flavor = flavor.clone();
+    unknown_flavor.openstack_id = Uuid::new_v4().to_string();
+    let resized = server
+        .setup_test_server_state_with_server_id(
+            &flavor,
+            &user,
+            &Uuid::new_v4().to_string(),
+        )
+        .await
+        .expect("Failed to setup test server state");
+    let transferred = server
+        .setup_test_server_state_with_server_id(
+            &flavor,
+            &user,
+            &Uuid::new_v4().to_string(),
+        )
+        .await
+        .expect("Failed to setup test server state");
+    server
+        .mock_nova_servers(&[
+            (
+                &resized.instance_id,
+                &resized.instance_name,
+                "ACTIVE",
+                &unknown_flavor,
+                &user,
+            ),
+            (
+                &transferred.instance_id,
+                &transferred.instance_name,
+                "ACTIVE",
+                &flavor,
+                &other_user,
+            ),
+        ])
+        .mount(&server.keystone_server)
+        .await;
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let import = client.server_state.import().await.unwrap();
+
+    // assert
+    assert_eq!(import.new_state_count, 1);
+    assert_eq!(import.end_state_count, 1);
+    assert_eq!(import.resize_count, 0);
+    assert_eq!(import.transfer_count, 1);
+    assert_eq!(import.skipped.len(), 1);
+    assert_eq!(import.skipped[0].instance_id, resized.instance_id);
+    let resized_state = client.server_state.get(resized.id).await.unwrap();
+    assert!(resized_state.end.is_none());
+    let states = client.server_state.list().all().send().await.unwrap();
+    assert!(
+        states
+            .iter()
+            .any(|s| s.instance_id == transferred.instance_id
+                && s.user == other_user.id
+                && s.end.is_none())
+    );
+}
+
+#[tokio::test]
+async fn e2e_lib_server_state_import_uses_nova_timestamps() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 1)
+        .await
+        .expect("Failed to setup test project");
+    let admin = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    let user = test_project.normals[0].user.clone();
+    server
+        .mock_keystone_auth(&token, &admin.openstack_id, &admin.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let terminated_at = Utc::now() - TimeDelta::days(1);
+    let deleted_id = Uuid::new_v4().to_string();
+    let new_id = Uuid::new_v4().to_string();
+    server
+        .mock_nova_servers(&[(&new_id, "new", "ACTIVE", &flavor, &user)])
+        .mount(&server.keystone_server)
+        .await;
+    server
+        .mock_nova_deleted_servers(&[(
+            &deleted_id,
+            "deleted",
+            &flavor,
+            &user,
+            terminated_at,
+        )])
+        .mount(&server.keystone_server)
+        .await;
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+    let deleted_state = client
+        .server_state
+        .create(
+            (Utc::now() - TimeDelta::days(2)).fixed_offset(),
+            deleted_id.clone(),
+            "deleted".to_string(),
+            flavor.id,
+            "ACTIVE".to_string(),
+            user.id,
+        )
+        .send()
+        .await
+        .unwrap();
+    let before_import = Utc::now();
+
+    // act
+    let import = client.server_state.import().await.unwrap();
+
+    // assert
+    assert_eq!(import.new_state_count, 1);
+    assert_eq!(import.end_state_count, 1);
+    let deleted_state =
+        client.server_state.get(deleted_state.id).await.unwrap();
+    let end = deleted_state.end.unwrap().to_utc();
+    assert!((end - terminated_at).abs() < TimeDelta::seconds(1));
+    let states = client.server_state.list().all().send().await.unwrap();
+    let new_state = states.iter().find(|s| s.instance_id == new_id).unwrap();
+    assert!(new_state.end.is_none());
+    assert!(new_state.begin.to_utc() <= before_import);
+}
+
+#[tokio::test]
+async fn e2e_lib_server_state_import_does_not_overlap_ended_states() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 1)
+        .await
+        .expect("Failed to setup test project");
+    let admin = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    let user = test_project.normals[0].user.clone();
+    server
+        .mock_keystone_auth(&token, &admin.openstack_id, &admin.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let server_id = Uuid::new_v4().to_string();
+    server
+        .mock_nova_servers(&[(&server_id, "test", "ACTIVE", &flavor, &user)])
+        .mount(&server.keystone_server)
+        .await;
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+    // ends after the server was launched according to nova
+    let ended_state = client
+        .server_state
+        .create(
+            (Utc::now() - TimeDelta::days(2)).fixed_offset(),
+            server_id.clone(),
+            "test".to_string(),
+            flavor.id,
+            "ACTIVE".to_string(),
+            user.id,
+        )
+        .end(Utc::now().fixed_offset())
+        .send()
+        .await
+        .unwrap();
+
+    // act
+    let import = client.server_state.import().await.unwrap();
+
+    // assert
+    assert_eq!(import.new_state_count, 1);
+    assert!(import.new_states[0].begin >= ended_state.end.unwrap());
+}
+
+#[tokio::test]
+async fn e2e_lib_scheduled_import_only_lists_changed_servers_in_between() {
+    // arrange
+    let server = spawn_app_with_configuration(|c| {
+        c.scheduler.enabled = true;
+        c.scheduler.interval_seconds = 1;
+        c.scheduler.jitter_seconds = 0;
+        c.scheduler.full_import_every = 100;
+    })
+    .await;
+    let test_project = server
+        .setup_test_project(1, 0, 1)
+        .await
+        .expect("Failed to setup test project");
+    let admin = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    let user = test_project.normals[0].user.clone();
+    server
+        .mock_keystone_auth(&token, &admin.openstack_id, &admin.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let kept_id = Uuid::new_v4().to_string();
+    let deleted_id = Uuid::new_v4().to_string();
+    // the first scheduled import lists all servers, the following ones
+    // only the deleted one
+    Mock::given(method("GET"))
+        .and(path("/v2.1/servers/detail"))
+        .and(|request: &Request| !has_changes_since(request))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "servers": [
+                nova_server_json(
+                    &kept_id, "kept", "ACTIVE", &flavor, &user, None
+                ),
+                nova_server_json(
+                    &deleted_id, "deleted", "ACTIVE", &flavor, &user, None
+                ),
+            ],
+        })))
+        .mount(&server.keystone_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v2.1/servers/detail"))
+        .and(has_changes_since)
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "servers": [nova_server_json(
+                &deleted_id,
+                "deleted",
+                "DELETED",
+                &flavor,
+                &user,
+                Some(Utc::now()),
+            )],
+        })))
+        .mount(&server.keystone_server)
+        .await;
+    // scheduled imports fail until the flavors can be listed
+    server
+        .mock_nova_flavors(&[])
+        .mount(&server.keystone_server)
+        .await;
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let mut states = vec![];
+    for _ in 0..30 {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        states = client.server_state.list().all().send().await.unwrap();
+        if states
+            .iter()
+            .any(|s| s.instance_id == deleted_id && s.end.is_some())
+        {
+            break;
+        }
+    }
+
+    // assert
+    let kept_states = states
+        .iter()
+        .filter(|s| s.instance_id == kept_id)
+        .collect::<Vec<_>>();
+    assert_eq!(kept_states.len(), 1);
+    assert!(kept_states[0].end.is_none());
+    let deleted_states = states
+        .iter()
+        .filter(|s| s.instance_id == deleted_id)
+        .collect::<Vec<_>>();
+    assert_eq!(deleted_states.len(), 1);
+    assert!(deleted_states[0].end.is_some());
+    let requests = server.keystone_server.received_requests().await.unwrap();
+    assert!(requests.iter().any(has_changes_since));
+}
+
+#[tokio::test]
+async fn e2e_lib_admin_can_get_server_state_import_history() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 1)
+        .await
+        .expect("Failed to setup test project");
+    let admin = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    let user = test_project.normals[0].user.clone();
+    server
+        .mock_keystone_auth(&token, &admin.openstack_id, &admin.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let mut unknown_flavor = flavor.clone();
+    unknown_flavor.openstack_id = Uuid::new_v4().to_string();
+    let server_id = Uuid::new_v4().to_string();
+    let unknown_server_id = Uuid::new_v4().to_string();
+    server
+        .mock_nova_servers(&[
+            (&server_id, "test", "ACTIVE", &flavor, &user),
+            (
+                &unknown_server_id,
+                "unknown",
+                "ACTIVE",
+                &unknown_flavor,
+                &user,
+            ),
+        ])
+        .mount(&server.keystone_server)
+        .await;
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+    client.server_state.import().await.unwrap();
+
+    // act and assert 1 - list
+    let import_runs = client.server_state.import_runs().send().await.unwrap();
+    assert_eq!(import_runs.len(), 1);
+    assert_eq!(import_runs[0].new_state_count, 1);
+    assert!(import_runs[0].duration >= 0.0);
+
+    // act and assert 2 - get
+    let import_run = client
+        .server_state
+        .import_run(import_runs[0].id)
+        .await
+        .unwrap();
+    assert_eq!(import_run, import_runs[0]);
+    assert_eq!(import_run.skipped.len(), 1);
+    assert_eq!(import_run.skipped[0].instance_id, unknown_server_id);
+    assert_eq!(
+        import_run.skipped[0].reason,
+        format!("Flavor {} not found", unknown_flavor.openstack_id)
+    );
+
+    // act and assert 3 - limit and since
+    client.server_state.import().await.unwrap();
+    let import_runs = client.server_state.import_runs().send().await.unwrap();
+    assert_eq!(import_runs.len(), 2);
+    let limited = client
+        .server_state
+        .import_runs()
+        .limit(1)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(limited, vec![import_runs[0].clone()]);
+    let since = client
+        .server_state
+        .import_runs()
+        .since(import_runs[0].begin)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(since, vec![import_runs[0].clone()]);
+}
+
+#[tokio::test]
+async fn e2e_lib_server_state_import_dry_run_changes_nothing() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 1)
+        .await
+        .expect("Failed to setup test project");
+    let admin = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    let user = test_project.normals[0].user.clone();
+    server
+        .mock_keystone_auth(&token, &admin.openstack_id, &admin.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let mut unknown_flavor = flavor.clone();
+    unknown_flavor.openstack_id = Uuid::new_v4().to_string();
+    let server_id = Uuid::new_v4().to_string();
+    let unknown_server_id = Uuid::new_v4().to_string();
+    server
+        .mock_nova_servers(&[
+            (&server_id, "test", "ACTIVE", &flavor, &user),
+            (
+                &unknown_server_id,
+                "unknown",
+                "ACTIVE",
+                &unknown_flavor,
+                &user,
+            ),
+        ])
+        .mount(&server.keystone_server)
+        .await;
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let dry_run = client.server_state.import_dry_run().await.unwrap();
+
+    // assert
+    assert!(dry_run.dry_run);
+    assert_eq!(dry_run.new_state_count, 1);
+    assert_eq!(dry_run.end_state_count, 0);
+    assert_eq!(dry_run.new_states.len(), 1);
+    assert_eq!(dry_run.new_states[0].instance_id, server_id);
+    assert_eq!(dry_run.new_states[0].flavor, flavor.id);
+    assert_eq!(dry_run.new_states[0].user, user.id);
+    assert!(dry_run.ended_states.is_empty());
+    assert_eq!(dry_run.skipped.len(), 1);
+    assert_eq!(dry_run.skipped[0].instance_id, unknown_server_id);
+    let server_states = client.server_state.list().all().send().await.unwrap();
+    assert!(server_states.is_empty());
+    assert!(
+        client
+            .server_state
+            .import_runs()
+            .send()
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    let status = client.server_state.import_status().await.unwrap();
+    assert!(status.last_begin.is_none());
+
+    // act and assert 2 - the real import applies the same changes
+    let import = client.server_state.import().await.unwrap();
+    assert!(!import.dry_run);
+    assert_eq!(import.new_states, dry_run.new_states);
+    assert_eq!(import.skipped, dry_run.skipped);
+}
+
+#[tokio::test]
+async fn e2e_lib_master_user_cannot_get_server_state_import_status() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(0, 1, 0)
+        .await
+        .expect("Failed to setup test project");
+    let master_user = test_project.masters[0].user.clone();
+    let token = test_project.masters[0].token.clone();
+    server
+        .mock_keystone_auth(
+            &token,
+            &master_user.openstack_id,
+            &master_user.name,
+        )
+        .mount(&server.keystone_server)
+        .await;
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let status = client.server_state.import_status().await;
+
+    // assert
+    assert!(status.is_err());
+    assert_eq!(
+        status.unwrap_err().to_string(),
+        "Admin privileges required".to_string()
+    );
+}
@@ -1,6 +1,10 @@
+mod archive;
+mod check;
+mod compact;
 mod create;
 mod delete;
 mod get;
+mod import;
 mod list;
 mod modify;
 
@@ -0,0 +1,218 @@
+use std::str::FromStr;
+
+use avina::{Api, Token};
+use avina_api::configuration::{SmtpSettings, SmtpTls, WebhookSettings};
+use avina_test::{
+    MockSmtpServer, TestApp, random_alphanumeric_string, random_uuid,
+    spawn_app_with_configuration,
+};
+use avina_wire::budgeting::{BudgetAlert, BudgetAlertKind};
+use chrono::{TimeDelta, Utc};
+use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{method, path},
+};
+
+// sets up a user with a budget of 150 and a server that cost about 100
+// during the last hour, then returns the client and the user
+async fn setup_user_with_spent_budget(server: &TestApp) -> (Api, u32) {
+    let test_project = server
+        .setup_test_project(1, 0, 0)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    server
+        .mock_nova_servers(&[])
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+    let now = Utc::now();
+    // about 100 per hour
+    client
+        .flavor_price
+        .create(flavor.id, test_project.project.user_class)
+        .price(365.0 * 24.0 * 100.0)
+        .start_time((now - TimeDelta::days(365 * 2)).fixed_offset())
+        .send()
+        .await
+        .unwrap();
+    client
+        .server_state
+        .create(
+            (now - TimeDelta::hours(1)).fixed_offset(),
+            random_uuid(),
+            random_alphanumeric_string(10),
+            flavor.id,
+            "ACTIVE".to_string(),
+            user.id,
+        )
+        .end(now.fixed_offset())
+        .send()
+        .await
+        .unwrap();
+    client
+        .user_budget
+        .create(user.id)
+        .amount(150)
+        .send()
+        .await
+        .unwrap();
+    (client, user.id)
+}
+
+#[tokio::test]
+async fn e2e_lib_budget_alert_is_sent_once_via_smtp_and_webhook() {
+    // arrange
+    let smtp_server = MockSmtpServer::start().await;
+    let webhook_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/hook"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&webhook_server)
+        .await;
+    let server = spawn_app_with_configuration(|c| {
+        c.alerts.thresholds = vec![50, 60, 100];
+        c.alerts.smtp = Some(SmtpSettings {
+            host: "127.0.0.1".to_string(),
+            port: smtp_server.port(),
+            tls: SmtpTls::None,
+            username: None,
+            password: None,
+            from: "avina@example.com".to_string(),
+            to: vec!["admins@example.com".to_string()],
+        });
+        c.alerts.webhook = Some(WebhookSettings {
+            url: format!("{}/hook", webhook_server.uri()),
+        });
+    })
+    .await;
+    let (client, user_id) = setup_user_with_spent_budget(&server).await;
+
+    // act
+    client.server_state.import().await.unwrap();
+    client.server_state.import().await.unwrap();
+
+    // assert
+    let alerts = webhook_server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|request| request.body_json::<BudgetAlert>().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].kind, BudgetAlertKind::User);
+    assert_eq!(alerts[0].owner_id, user_id);
+    assert_eq!(alerts[0].budget, 150);
+    // 50% is passed together with 60%, so only the higher one is alerted
+    assert_eq!(alerts[0].threshold, 60);
+    assert!(alerts[0].cost > 90.0 && alerts[0].cost < 150.0);
+    let messages = smtp_server.messages();
+    assert_eq!(messages.len(), 1);
+    assert!(messages[0].contains(&format!(
+        "Budget alert: user {} reached 60%",
+        alerts[0].owner_name
+    )));
+}
+
+#[tokio::test]
+async fn e2e_lib_budget_alert_is_retried_after_failed_delivery() {
+    // arrange
+    let webhook_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/hook"))
+        .respond_with(ResponseTemplate::new(500))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&webhook_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/hook"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&webhook_server)
+        .await;
+    let server = spawn_app_with_configuration(|c| {
+        c.alerts.thresholds = vec![50];
+        c.alerts.smtp = None;
+        c.alerts.webhook = Some(WebhookSettings {
+            url: format!("{}/hook", webhook_server.uri()),
+        });
+    })
+    .await;
+    let (client, _) = setup_user_with_spent_budget(&server).await;
+
+    // act
+    for _ in 0..3 {
+        client.server_state.import().await.unwrap();
+    }
+
+    // assert
+    let requests = webhook_server.received_requests().await.unwrap();
+    // the failed delivery and its retry, but nothing after that
+    assert_eq!(requests.len(), 2);
+    let alert = requests[1].body_json::<BudgetAlert>().unwrap();
+    assert_eq!(alert.threshold, 50);
+}
+
+#[tokio::test]
+async fn e2e_lib_budget_alert_is_retried_only_via_the_failed_channel() {
+    // arrange
+    let smtp_server = MockSmtpServer::start().await;
+    let webhook_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/hook"))
+        .respond_with(ResponseTemplate::new(500))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&webhook_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/hook"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&webhook_server)
+        .await;
+    let server = spawn_app_with_configuration(|c| {
+        c.alerts.thresholds = vec![50];
+        c.alerts.smtp = Some(SmtpSettings {
+            host: "127.0.0.1".to_string(),
+            port: smtp_server.port(),
+            tls: SmtpTls::None,
+            username: None,
+            password: None,
+            from: "avina@example.com".to_string(),
+            to: vec!["admins@example.com".to_string()],
+        });
+        c.alerts.webhook = Some(WebhookSettings {
+            url: format!("{}/hook", webhook_server.uri()),
+        });
+    })
+    .await;
+    let (client, _) = setup_user_with_spent_budget(&server).await;
+
+    // act
+    for _ in 0..3 {
+        client.server_state.import().await.unwrap();
+    }
+
+    // assert
+    let requests = webhook_server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 2);
+    let alert = requests[1].body_json::<BudgetAlert>().unwrap();
+    assert_eq!(alert.threshold, 50);
+    assert_eq!(smtp_server.messages().len(), 1);
+}
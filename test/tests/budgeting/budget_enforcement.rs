@@ -0,0 +1,336 @@
+use std::str::FromStr;
+
+use avina::{Api, Token};
+use avina_api::configuration::Settings;
+use avina_test::{
+    TestApp, random_alphanumeric_string, random_uuid,
+    spawn_app_with_configuration,
+};
+use avina_wire::{budgeting::EnforcementAction, resources::Flavor};
+use chrono::{TimeDelta, TimeZone, Utc};
+use serde_json::json;
+use wiremock::{
+    Mock, ResponseTemplate,
+    matchers::{body_json, method, path},
+};
+
+fn configure_enforcement(
+    c: &mut Settings,
+    action: EnforcementAction,
+    grace_period_hours: u64,
+    allow_list: Vec<String>,
+    dry_run: bool,
+) {
+    c.enforcement.enabled = true;
+    c.enforcement.action = action;
+    c.enforcement.grace_period_hours = grace_period_hours;
+    c.enforcement.allow_list = allow_list;
+    c.enforcement.dry_run = dry_run;
+}
+
+// the servers started an hour ago and cost about 10 per hour, so a budget
+// of 1 is over
+async fn create_user_with_servers(
+    client: &Api,
+    flavor: &Flavor,
+    user_id: u32,
+    amount: i64,
+    statuses: &[&str],
+) -> Vec<String> {
+    let now = Utc::now();
+    client
+        .user_budget
+        .create(user_id)
+        .amount(amount)
+        .send()
+        .await
+        .unwrap();
+    let mut server_ids = vec![];
+    for status in statuses {
+        let server_id = random_uuid();
+        client
+            .server_state
+            .create(
+                (now - TimeDelta::hours(1)).fixed_offset(),
+                server_id.clone(),
+                random_alphanumeric_string(10),
+                flavor.id,
+                status.to_string(),
+                user_id,
+            )
+            .send()
+            .await
+            .unwrap();
+        server_ids.push(server_id);
+    }
+    server_ids
+}
+
+async fn setup_admin_client(server: &TestApp) -> (Api, u32, u32, Flavor) {
+    let test_project = server
+        .setup_test_project(1, 0, 0)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+    client
+        .flavor_price
+        .create(flavor.id, test_project.project.user_class)
+        .price(10.0 * 24.0 * 366.0)
+        .start_time(Utc.with_ymd_and_hms(2019, 1, 1, 0, 0, 0).unwrap().into())
+        .send()
+        .await
+        .unwrap();
+    (client, user.id, test_project.project.id, flavor)
+}
+
+#[tokio::test]
+async fn e2e_lib_normal_user_cannot_run_budget_enforcement() {
+    // arrange
+    let server = spawn_app_with_configuration(|_| {}).await;
+    let test_project = server
+        .setup_test_project(0, 0, 1)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.normals[0].user.clone();
+    let token = test_project.normals[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let actions = client.budget_enforcement.dry_run().await;
+
+    // assert
+    assert!(actions.is_err());
+    assert_eq!(
+        actions.unwrap_err().to_string(),
+        "Admin privileges required".to_string()
+    );
+}
+
+#[tokio::test]
+async fn e2e_lib_budget_enforcement_dry_run_only_reports() {
+    // arrange
+    let server = spawn_app_with_configuration(|c| {
+        configure_enforcement(c, EnforcementAction::Stop, 0, vec![], true)
+    })
+    .await;
+    let (client, user_id, _, flavor) = setup_admin_client(&server).await;
+    let server_ids = create_user_with_servers(
+        &client,
+        &flavor,
+        user_id,
+        1,
+        &["ACTIVE", "SHUTOFF"],
+    )
+    .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(202))
+        .expect(0)
+        .named("nova server action")
+        .mount(&server.keystone_server)
+        .await;
+
+    // act
+    let actions = client.budget_enforcement.run().await.unwrap();
+
+    // assert
+    // the shut off server cannot be stopped
+    assert_eq!(actions.len(), 1);
+    assert_eq!(actions[0].server_id, server_ids[0]);
+    assert_eq!(actions[0].action, EnforcementAction::Stop);
+    assert!(actions[0].dry_run);
+    assert!(actions[0].error.is_none());
+    assert!(client.budget_enforcement.list().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn e2e_lib_budget_enforcement_locks_servers_once_and_skips_allow_list() {
+    // arrange
+    let exempt_name = random_alphanumeric_string(10);
+    let allow_list = vec![exempt_name.clone()];
+    let server = spawn_app_with_configuration(|c| {
+        configure_enforcement(c, EnforcementAction::Lock, 0, allow_list, false)
+    })
+    .await;
+    let (client, user_id, project_id, flavor) =
+        setup_admin_client(&server).await;
+    let exempt = client
+        .user
+        .create(exempt_name, random_uuid(), project_id)
+        .send()
+        .await
+        .unwrap();
+    let server_ids =
+        create_user_with_servers(&client, &flavor, user_id, 1, &["ACTIVE"])
+            .await;
+    let exempt_server_ids =
+        create_user_with_servers(&client, &flavor, exempt.id, 1, &["ACTIVE"])
+            .await;
+    Mock::given(method("POST"))
+        .and(path(format!("/v2.1/servers/{}/action", server_ids[0])))
+        .and(body_json(json!({ "lock": null })))
+        .respond_with(ResponseTemplate::new(202))
+        .expect(1)
+        .mount(&server.keystone_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(format!(
+            "/v2.1/servers/{}/action",
+            exempt_server_ids[0]
+        )))
+        .respond_with(ResponseTemplate::new(202))
+        .expect(0)
+        .mount(&server.keystone_server)
+        .await;
+
+    // act
+    let first = client.budget_enforcement.run().await.unwrap();
+    let second = client.budget_enforcement.run().await.unwrap();
+
+    // assert
+    assert_eq!(first.len(), 1);
+    assert_eq!(first[0].server_id, server_ids[0]);
+    assert_eq!(first[0].user_id, user_id);
+    assert!(!first[0].dry_run);
+    assert!(first[0].error.is_none());
+    assert!(second.is_empty());
+    let recorded = client.budget_enforcement.list().await.unwrap();
+    assert_eq!(recorded.len(), 1);
+    assert_eq!(recorded[0].server_id, server_ids[0]);
+    assert_eq!(recorded[0].action, EnforcementAction::Lock);
+}
+
+#[tokio::test]
+async fn e2e_lib_budget_enforcement_records_and_retries_failed_actions() {
+    // arrange
+    let server = spawn_app_with_configuration(|c| {
+        configure_enforcement(c, EnforcementAction::Shelve, 0, vec![], false)
+    })
+    .await;
+    let (client, user_id, _, flavor) = setup_admin_client(&server).await;
+    let server_ids =
+        create_user_with_servers(&client, &flavor, user_id, 1, &["ACTIVE"])
+            .await;
+    let action_path = format!("/v2.1/servers/{}/action", server_ids[0]);
+    Mock::given(method("POST"))
+        .and(path(action_path.clone()))
+        .respond_with(ResponseTemplate::new(409))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&server.keystone_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(action_path))
+        .and(body_json(json!({ "shelve": null })))
+        .respond_with(ResponseTemplate::new(202))
+        .mount(&server.keystone_server)
+        .await;
+
+    // act
+    let first = client.budget_enforcement.run().await.unwrap();
+    let second = client.budget_enforcement.run().await.unwrap();
+
+    // assert
+    assert_eq!(first.len(), 1);
+    assert!(first[0].error.is_some());
+    assert_eq!(second.len(), 1);
+    assert!(second[0].error.is_none());
+    let recorded = client.budget_enforcement.list().await.unwrap();
+    assert_eq!(recorded.len(), 2);
+}
+
+#[tokio::test]
+async fn e2e_lib_budget_enforcement_waits_for_grace_period() {
+    // arrange
+    let server = spawn_app_with_configuration(|c| {
+        configure_enforcement(c, EnforcementAction::Lock, 24, vec![], false)
+    })
+    .await;
+    let (client, user_id, _, flavor) = setup_admin_client(&server).await;
+    create_user_with_servers(&client, &flavor, user_id, 1, &["ACTIVE"]).await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(202))
+        .expect(0)
+        .named("nova server action")
+        .mount(&server.keystone_server)
+        .await;
+
+    // act
+    let first = client.budget_enforcement.run().await.unwrap();
+    let second = client.budget_enforcement.dry_run().await.unwrap();
+
+    // assert
+    assert!(first.is_empty());
+    assert!(second.is_empty());
+}
+
+#[tokio::test]
+async fn e2e_lib_budget_enforcement_requires_enabled_configuration() {
+    // arrange
+    let server = spawn_app_with_configuration(|c| {
+        c.enforcement.enabled = false;
+    })
+    .await;
+    let (client, _, _, _) = setup_admin_client(&server).await;
+
+    // act
+    let forced = client.budget_enforcement.force_run().await;
+    let dry_run = client.budget_enforcement.dry_run().await;
+
+    // assert
+    assert!(forced.is_err());
+    assert_eq!(
+        forced.unwrap_err().to_string(),
+        "Budget enforcement is disabled".to_string()
+    );
+    assert!(dry_run.is_ok());
+}
+
+#[tokio::test]
+async fn e2e_lib_budget_enforcement_ignores_empty_budgets() {
+    // arrange
+    let server = spawn_app_with_configuration(|c| {
+        configure_enforcement(c, EnforcementAction::Lock, 0, vec![], false)
+    })
+    .await;
+    let (client, user_id, _, flavor) = setup_admin_client(&server).await;
+    create_user_with_servers(&client, &flavor, user_id, 0, &["ACTIVE"]).await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(202))
+        .expect(0)
+        .named("nova server action")
+        .mount(&server.keystone_server)
+        .await;
+
+    // act
+    let actions = client.budget_enforcement.run().await.unwrap();
+
+    // assert
+    assert!(actions.is_empty());
+    assert!(client.budget_enforcement.list().await.unwrap().is_empty());
+}
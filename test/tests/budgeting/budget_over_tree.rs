@@ -0,0 +1,230 @@
+use std::str::FromStr;
+
+use avina::{Api, Token};
+use avina_test::spawn_app;
+
+#[tokio::test]
+async fn e2e_lib_normal_user_can_get_own_budget_over_tree() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(0, 0, 1)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.normals[0].user.clone();
+    let token = test_project.normals[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let project_budget = server
+        .setup_test_project_budget(&test_project.project)
+        .await
+        .expect("Failed to setup test project budget");
+    let user_budget = server
+        .setup_test_user_budget(&user)
+        .await
+        .expect("Failed to setup test user budget");
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let tree = client.budget_over_tree.get().send().await.unwrap();
+
+    // assert
+    assert!(tree.cost.is_none());
+    assert_eq!(tree.projects.len(), 1);
+    let project = &tree.projects[&test_project.project.name];
+    assert_eq!(project.budget_id, project_budget.id);
+    assert!(project.over);
+    assert!(project.flavors.is_none());
+    assert_eq!(project.users.len(), 1);
+    let user_node = &project.users[&user.name];
+    assert_eq!(user_node.budget_id, user_budget.id);
+    assert!(user_node.over);
+}
+
+#[tokio::test]
+async fn e2e_lib_normal_user_cannot_get_budget_over_tree_of_other_user() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(0, 0, 2)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.normals[0].user.clone();
+    let token = test_project.normals[0].token.clone();
+    let other_user = test_project.normals[1].user.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let tree = client
+        .budget_over_tree
+        .get()
+        .user(other_user.id)
+        .send()
+        .await;
+
+    // assert
+    assert!(tree.is_err());
+    assert_eq!(
+        tree.unwrap_err().to_string(),
+        "Resource not found".to_string()
+    );
+}
+
+#[tokio::test]
+async fn e2e_lib_master_user_can_get_budget_over_tree_of_own_project() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(0, 1, 1)
+        .await
+        .expect("Failed to setup test project");
+    let master_user = test_project.masters[0].user.clone();
+    let token = test_project.masters[0].token.clone();
+    let normal_user = test_project.normals[0].user.clone();
+    server
+        .mock_keystone_auth(
+            &token,
+            &master_user.openstack_id,
+            &master_user.name,
+        )
+        .mount(&server.keystone_server)
+        .await;
+    server
+        .setup_test_project_budget(&test_project.project)
+        .await
+        .expect("Failed to setup test project budget");
+    server
+        .setup_test_user_budget(&master_user)
+        .await
+        .expect("Failed to setup test user budget");
+    server
+        .setup_test_user_budget(&normal_user)
+        .await
+        .expect("Failed to setup test user budget");
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let tree = client
+        .budget_over_tree
+        .get()
+        .project(test_project.project.id)
+        .send()
+        .await
+        .unwrap();
+
+    // assert
+    assert!(tree.cost.is_none());
+    let project = &tree.projects[&test_project.project.name];
+    assert!(project.flavors.is_some());
+    assert_eq!(project.users.len(), 2);
+    assert!(project.users.contains_key(&master_user.name));
+    assert!(project.users.contains_key(&normal_user.name));
+}
+
+#[tokio::test]
+async fn e2e_lib_master_user_cannot_get_budget_over_tree_for_all() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(0, 1, 0)
+        .await
+        .expect("Failed to setup test project");
+    let master_user = test_project.masters[0].user.clone();
+    let token = test_project.masters[0].token.clone();
+    server
+        .mock_keystone_auth(
+            &token,
+            &master_user.openstack_id,
+            &master_user.name,
+        )
+        .mount(&server.keystone_server)
+        .await;
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let tree = client.budget_over_tree.get().all().send().await;
+
+    // assert
+    assert!(tree.is_err());
+    assert_eq!(
+        tree.unwrap_err().to_string(),
+        "Admin privileges required".to_string()
+    );
+}
+
+#[tokio::test]
+async fn e2e_lib_admin_can_get_budget_over_tree_for_all() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 0)
+        .await
+        .expect("Failed to setup test project");
+    let admin = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &admin.openstack_id, &admin.name)
+        .mount(&server.keystone_server)
+        .await;
+    server
+        .setup_test_project_budget(&test_project.project)
+        .await
+        .expect("Failed to setup test project budget");
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let tree = client.budget_over_tree.get().all().send().await.unwrap();
+
+    // assert
+    assert_eq!(tree.cost, Some(0.0));
+    assert!(tree.flavors.is_some());
+    let project = &tree.projects[&test_project.project.name];
+    assert_eq!(project.cost, 0.0);
+    assert!(project.users.is_empty());
+}
@@ -1,2 +1,5 @@
+mod budget_alert;
+mod budget_enforcement;
+mod budget_over_tree;
 mod project_budget;
 mod user_budget;
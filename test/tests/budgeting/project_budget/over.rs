@@ -1,7 +1,19 @@
 use std::str::FromStr;
 
 use avina::{Api, Token};
-use avina_test::spawn_app;
+use avina_test::{
+    assert_cost_eq, random_alphanumeric_string, random_uuid, spawn_app,
+};
+use chrono::{DateTime, FixedOffset, TimeZone};
+
+// midnight in Europe/Berlin during winter time, which the billing calendar
+// of the base configuration uses
+fn midnight(year: i32, month: u32, day: u32) -> DateTime<FixedOffset> {
+    FixedOffset::east_opt(3600)
+        .unwrap()
+        .with_ymd_and_hms(year, month, day, 0, 0, 0)
+        .unwrap()
+}
 
 #[tokio::test]
 async fn e2e_lib_admin_can_get_project_budget_over_for_all() {
@@ -249,3 +261,81 @@ async fn e2e_lib_master_user_cannot_get_other_project_budget_over_by_budget() {
         "Resource not found".to_string()
     );
 }
+
+#[tokio::test]
+async fn e2e_lib_project_budget_over_forecast_predicts_exhaustion() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 0)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+    // one unit per day in 2021
+    client
+        .flavor_price
+        .create(flavor.id, test_project.project.user_class)
+        .price(365.0)
+        .start_time(midnight(2020, 1, 1))
+        .send()
+        .await
+        .unwrap();
+    // the server keeps running, so it costs one unit per day from now on
+    client
+        .server_state
+        .create(
+            midnight(2021, 1, 1),
+            random_uuid(),
+            random_alphanumeric_string(10),
+            flavor.id,
+            "ACTIVE".to_string(),
+            user.id,
+        )
+        .send()
+        .await
+        .unwrap();
+    client
+        .project_budget
+        .create(test_project.project.id)
+        .year(2021)
+        .amount(100)
+        .send()
+        .await
+        .unwrap();
+
+    // act
+    let forecasts = client
+        .project_budget
+        .over()
+        .project(test_project.project.id)
+        .end(midnight(2021, 3, 1))
+        .forecast()
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(forecasts.len(), 1);
+    let forecast = &forecasts[0];
+    assert!(!forecast.over);
+    assert_cost_eq(forecast.cost, 59.0);
+    assert_cost_eq(forecast.rate, 1.0);
+    assert_cost_eq(forecast.projected_cost, 365.0);
+    // the remaining 41 units are used up 41 days later
+    assert_eq!(forecast.exhausted_at, Some(midnight(2021, 4, 11)));
+}
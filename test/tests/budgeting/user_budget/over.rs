@@ -1,7 +1,19 @@
 use std::str::FromStr;
 
 use avina::{Api, Token};
-use avina_test::spawn_app;
+use avina_test::{
+    assert_cost_eq, random_alphanumeric_string, random_uuid, spawn_app,
+};
+use chrono::{DateTime, FixedOffset, TimeZone};
+
+// midnight in Europe/Berlin during winter time, which the billing calendar
+// of the base configuration uses
+fn midnight(year: i32, month: u32, day: u32) -> DateTime<FixedOffset> {
+    FixedOffset::east_opt(3600)
+        .unwrap()
+        .with_ymd_and_hms(year, month, day, 0, 0, 0)
+        .unwrap()
+}
 
 #[tokio::test]
 async fn e2e_lib_admin_can_get_user_budget_over_for_all() {
@@ -277,3 +289,79 @@ async fn e2e_lib_user_can_get_own_user_budget_over_by_user() {
     let request = client.user_budget.over().user(normal_user.id).send().await;
     assert!(request.is_ok());
 }
+
+#[tokio::test]
+async fn e2e_lib_user_budget_over_forecast_without_exhaustion_this_year() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 0)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+    // one unit per day in 2021
+    client
+        .flavor_price
+        .create(flavor.id, test_project.project.user_class)
+        .price(365.0)
+        .start_time(midnight(2020, 1, 1))
+        .send()
+        .await
+        .unwrap();
+    // the server keeps running, so it costs one unit per day from now on
+    client
+        .server_state
+        .create(
+            midnight(2021, 1, 1),
+            random_uuid(),
+            random_alphanumeric_string(10),
+            flavor.id,
+            "ACTIVE".to_string(),
+            user.id,
+        )
+        .send()
+        .await
+        .unwrap();
+    client
+        .user_budget
+        .create(user.id)
+        .year(2021)
+        .amount(1000)
+        .send()
+        .await
+        .unwrap();
+
+    // act
+    let forecasts = client
+        .user_budget
+        .over()
+        .user(user.id)
+        .end(midnight(2021, 3, 1))
+        .forecast()
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(forecasts.len(), 1);
+    let forecast = &forecasts[0];
+    assert!(!forecast.over);
+    assert_cost_eq(forecast.cost, 59.0);
+    assert_cost_eq(forecast.projected_cost, 365.0);
+    assert_eq!(forecast.exhausted_at, None);
+}
@@ -0,0 +1,103 @@
+use std::str::FromStr;
+
+use avina::{Api, Token};
+use avina_test::spawn_app;
+
+#[tokio::test]
+async fn e2e_lib_admin_can_initialize_flavor_prices() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 0)
+        .await
+        .expect("Failed to setup test project");
+    let admin = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &admin.openstack_id, &admin.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let flavor_price = server
+        .setup_test_flavor_price(&flavor)
+        .await
+        .expect("Failed to setup test flavor price");
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+    client
+        .flavor
+        .modify(flavor.id)
+        .weight(2)
+        .send()
+        .await
+        .unwrap();
+
+    // act and assert 1 - initialize missing prices
+    let initialize = client.flavor_price.initialize().await.unwrap();
+    assert_eq!(initialize.new_flavor_price_count, 5);
+    let flavor_prices = client
+        .flavor_price
+        .list()
+        .send()
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|p| p.flavor == flavor.id)
+        .collect::<Vec<_>>();
+    assert_eq!(flavor_prices.len(), 6);
+    for flavor_price_new in flavor_prices
+        .iter()
+        .filter(|p| p.user_class != flavor_price.user_class)
+    {
+        assert_eq!(flavor_price_new.unit_price, 200.0);
+    }
+
+    // act and assert 2 - nothing left to initialize
+    let initialize = client.flavor_price.initialize().await.unwrap();
+    assert_eq!(initialize.new_flavor_price_count, 0);
+}
+
+#[tokio::test]
+async fn e2e_lib_normal_user_cannot_initialize_flavor_prices() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(0, 0, 1)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.normals[0].user.clone();
+    let token = test_project.normals[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let initialize = client.flavor_price.initialize().await;
+
+    // assert
+    assert!(initialize.is_err());
+    assert_eq!(
+        initialize.unwrap_err().to_string(),
+        "Admin privileges required".to_string()
+    );
+}
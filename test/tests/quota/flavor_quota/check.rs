@@ -0,0 +1,243 @@
+use std::str::FromStr;
+
+use avina::{Api, Token};
+use avina_test::spawn_app;
+
+#[tokio::test]
+async fn e2e_lib_flavor_quota_check_compares_usage_with_quota() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 1)
+        .await
+        .expect("Failed to setup test project");
+    let admin = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    let normal_user = test_project.normals[0].user.clone();
+    server
+        .mock_keystone_auth(&token, &admin.openstack_id, &admin.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor_group = server
+        .setup_test_flavor_group(test_project.project.id)
+        .await
+        .expect("Failed to setup test flavor group");
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let flavor_quota = server
+        .setup_test_flavor_quota(&flavor_group, &normal_user)
+        .await
+        .expect("Failed to setup test flavor quota");
+    let _server_state = server
+        .setup_test_server_state(&flavor, &normal_user)
+        .await
+        .expect("Failed to setup test server state");
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+    client
+        .flavor
+        .modify(flavor.id)
+        .group(flavor_group.id)
+        .weight(2)
+        .send()
+        .await
+        .unwrap();
+    client
+        .flavor_quota
+        .modify(flavor_quota.id)
+        .quota(5)
+        .send()
+        .await
+        .unwrap();
+
+    // act and assert 1 - under quota
+    let check = client
+        .flavor_quota
+        .check(normal_user.id, flavor.id)
+        .count(1)
+        .send()
+        .await
+        .unwrap();
+    assert!(check.underquota);
+
+    // act and assert 2 - over quota
+    let check = client
+        .flavor_quota
+        .check(normal_user.id, flavor.id)
+        .count(2)
+        .send()
+        .await
+        .unwrap();
+    assert!(!check.underquota);
+
+    // act and assert 3 - count times weight exceeds the integer range
+    let check = client
+        .flavor_quota
+        .check(normal_user.id, flavor.id)
+        .count(u32::MAX)
+        .send()
+        .await
+        .unwrap();
+    assert!(!check.underquota);
+
+    // act and assert 4 - unlimited quota
+    client
+        .flavor_quota
+        .modify(flavor_quota.id)
+        .quota(-1)
+        .send()
+        .await
+        .unwrap();
+    let check = client
+        .flavor_quota
+        .check(normal_user.id, flavor.id)
+        .count(100)
+        .send()
+        .await
+        .unwrap();
+    assert!(check.underquota);
+}
+
+#[tokio::test]
+async fn e2e_lib_flavor_quota_check_allows_flavor_without_group() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(0, 0, 1)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.normals[0].user.clone();
+    let token = test_project.normals[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let check = client
+        .flavor_quota
+        .check(user.id, flavor.id)
+        .send()
+        .await
+        .unwrap();
+
+    // assert
+    assert!(check.underquota);
+}
+
+#[tokio::test]
+async fn e2e_lib_flavor_quota_check_denies_group_without_quota() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 0)
+        .await
+        .expect("Failed to setup test project");
+    let admin = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &admin.openstack_id, &admin.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor_group = server
+        .setup_test_flavor_group(test_project.project.id)
+        .await
+        .expect("Failed to setup test flavor group");
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+    client
+        .flavor
+        .modify(flavor.id)
+        .group(flavor_group.id)
+        .send()
+        .await
+        .unwrap();
+
+    // act
+    let check = client
+        .flavor_quota
+        .check(admin.id, flavor.id)
+        .send()
+        .await
+        .unwrap();
+
+    // assert
+    assert!(!check.underquota);
+}
+
+#[tokio::test]
+async fn e2e_lib_normal_user_cannot_check_flavor_quota_of_other_user() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(0, 0, 2)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.normals[0].user.clone();
+    let token = test_project.normals[0].token.clone();
+    let other_user = test_project.normals[1].user.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let check = client
+        .flavor_quota
+        .check(other_user.id, flavor.id)
+        .send()
+        .await;
+
+    // assert
+    assert!(check.is_err());
+    assert_eq!(
+        check.unwrap_err().to_string(),
+        "Resource not found".to_string()
+    );
+}
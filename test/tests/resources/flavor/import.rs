@@ -0,0 +1,81 @@
+use std::str::FromStr;
+
+use avina::{Api, Token};
+use avina_test::{random_alphanumeric_string, random_uuid, spawn_app};
+
+#[tokio::test]
+async fn e2e_lib_flavor_import_dry_run_changes_nothing() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 0)
+        .await
+        .expect("Failed to setup test project");
+    let admin = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &admin.openstack_id, &admin.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let mut new_flavor = flavor.clone();
+    new_flavor.name = random_alphanumeric_string(10);
+    new_flavor.openstack_id = random_uuid();
+    server
+        .mock_nova_flavors(&[(&flavor, 1, 4096), (&new_flavor, 2, 8192)])
+        .mount(&server.keystone_server)
+        .await;
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let dry_run = client.flavor.import_dry_run().await.unwrap();
+
+    // assert
+    assert!(dry_run.dry_run);
+    assert_eq!(dry_run.new_flavor_count, 1);
+    assert_eq!(dry_run.new_flavors[0].name, new_flavor.name);
+    let flavors = client.flavor.list().all().send().await.unwrap();
+    assert_eq!(flavors, vec![flavor.clone()]);
+}
+
+#[tokio::test]
+async fn e2e_lib_flavor_import_dry_run_denies_access_to_normal_user() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(0, 0, 1)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.normals[0].user.clone();
+    let token = test_project.normals[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let import = client.flavor.import_dry_run().await;
+
+    // assert
+    assert!(import.is_err());
+    assert_eq!(
+        import.unwrap_err().to_string(),
+        "Admin privileges required".to_string()
+    );
+}
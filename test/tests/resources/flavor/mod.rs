@@ -1,2 +1,4 @@
 mod delete;
+mod import;
 mod modify;
+mod usage;
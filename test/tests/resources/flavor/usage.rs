@@ -0,0 +1,230 @@
+use std::str::FromStr;
+
+use avina::{Api, Token};
+use avina_test::spawn_app;
+
+#[tokio::test]
+async fn e2e_lib_normal_user_can_get_own_flavor_usage() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(0, 0, 1)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.normals[0].user.clone();
+    let token = test_project.normals[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let _server_state_1 = server
+        .setup_test_server_state(&flavor, &user)
+        .await
+        .expect("Failed to setup test server state");
+    let _server_state_2 = server
+        .setup_test_server_state(&flavor, &user)
+        .await
+        .expect("Failed to setup test server state");
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let usage = client.flavor.usage().mine().await.unwrap();
+
+    // assert
+    assert_eq!(usage.len(), 1);
+    assert_eq!(usage[0].user_id, user.id);
+    assert_eq!(usage[0].flavor_id, flavor.id);
+    assert_eq!(usage[0].count, 2);
+    assert_eq!(usage[0].usage, 2 * flavor.weight);
+}
+
+#[tokio::test]
+async fn e2e_lib_normal_user_cannot_get_flavor_usage_of_other_user() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(0, 0, 2)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.normals[0].user.clone();
+    let token = test_project.normals[0].token.clone();
+    let other_user = test_project.normals[1].user.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let usage = client.flavor.usage().user(other_user.id).await;
+
+    // assert
+    assert!(usage.is_err());
+    assert_eq!(
+        usage.unwrap_err().to_string(),
+        "Resource not found".to_string()
+    );
+}
+
+#[tokio::test]
+async fn e2e_lib_master_user_can_get_flavor_usage_of_own_project() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(0, 1, 1)
+        .await
+        .expect("Failed to setup test project");
+    let master_user = test_project.masters[0].user.clone();
+    let token = test_project.masters[0].token.clone();
+    let normal_user = test_project.normals[0].user.clone();
+    server
+        .mock_keystone_auth(
+            &token,
+            &master_user.openstack_id,
+            &master_user.name,
+        )
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let _server_state_1 = server
+        .setup_test_server_state(&flavor, &master_user)
+        .await
+        .expect("Failed to setup test server state");
+    let _server_state_2 = server
+        .setup_test_server_state(&flavor, &normal_user)
+        .await
+        .expect("Failed to setup test server state");
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act and assert 1 - project
+    let usage = client
+        .flavor
+        .usage()
+        .project(test_project.project.id)
+        .await
+        .unwrap();
+    assert_eq!(usage.len(), 2);
+    assert!(usage.iter().all(|u| u.count == 1));
+
+    // act and assert 2 - project aggregate
+    let aggregate = client
+        .flavor
+        .usage()
+        .project_aggregate(test_project.project.id)
+        .await
+        .unwrap();
+    assert_eq!(aggregate.len(), 1);
+    assert_eq!(aggregate[0].flavor_id, flavor.id);
+    assert_eq!(aggregate[0].count, 2);
+}
+
+#[tokio::test]
+async fn e2e_lib_master_user_cannot_get_flavor_usage_for_all() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(0, 1, 0)
+        .await
+        .expect("Failed to setup test project");
+    let master_user = test_project.masters[0].user.clone();
+    let token = test_project.masters[0].token.clone();
+    server
+        .mock_keystone_auth(
+            &token,
+            &master_user.openstack_id,
+            &master_user.name,
+        )
+        .mount(&server.keystone_server)
+        .await;
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let usage = client.flavor.usage().all().await;
+
+    // assert
+    assert!(usage.is_err());
+    assert_eq!(
+        usage.unwrap_err().to_string(),
+        "Admin privileges required".to_string()
+    );
+}
+
+#[tokio::test]
+async fn e2e_lib_admin_can_get_flavor_usage_for_all() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 0)
+        .await
+        .expect("Failed to setup test project");
+    let admin = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &admin.openstack_id, &admin.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let _server_state = server
+        .setup_test_server_state(&flavor, &admin)
+        .await
+        .expect("Failed to setup test server state");
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let aggregate = client.flavor.usage().all_aggregate().await.unwrap();
+
+    // assert
+    assert_eq!(aggregate.len(), 1);
+    assert_eq!(aggregate[0].flavor_id, flavor.id);
+    assert_eq!(aggregate[0].count, 1);
+}
@@ -0,0 +1,95 @@
+use std::str::FromStr;
+
+use avina::{Api, Token};
+use avina_test::spawn_app;
+
+#[tokio::test]
+async fn e2e_lib_admin_can_initialize_flavor_groups() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 0)
+        .await
+        .expect("Failed to setup test project");
+    let admin = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &admin.openstack_id, &admin.name)
+        .mount(&server.keystone_server)
+        .await;
+    let mut flavor_1 = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let flavor_2 = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+    flavor_1 = client
+        .flavor
+        .modify(flavor_1.id)
+        .name("lrz.medium".to_string())
+        .send()
+        .await
+        .unwrap();
+    server
+        .mock_nova_flavors(&[(&flavor_1, 4, 18432), (&flavor_2, 1, 4096)])
+        .mount(&server.keystone_server)
+        .await;
+
+    // act
+    let initialize = client.flavor_group.initialize().await.unwrap();
+
+    // assert
+    assert_eq!(initialize.new_flavor_group_count, 1);
+    assert_eq!(initialize.new_flavor_count, 1);
+    let flavor_1_detail = client.flavor.get(flavor_1.id).await.unwrap();
+    assert_eq!(flavor_1_detail.group_name, Some("lrz.".to_string()));
+    assert_eq!(flavor_1_detail.weight, 5);
+    let flavor_2_detail = client.flavor.get(flavor_2.id).await.unwrap();
+    assert!(flavor_2_detail.group.is_none());
+}
+
+#[tokio::test]
+async fn e2e_lib_normal_user_cannot_initialize_flavor_groups() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(0, 0, 1)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.normals[0].user.clone();
+    let token = test_project.normals[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let initialize = client.flavor_group.initialize().await;
+
+    // assert
+    assert!(initialize.is_err());
+    assert_eq!(
+        initialize.unwrap_err().to_string(),
+        "Admin privileges required".to_string()
+    );
+}
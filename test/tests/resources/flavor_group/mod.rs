@@ -1,2 +1,4 @@
 mod delete;
+mod initialize;
 mod modify;
+mod usage;
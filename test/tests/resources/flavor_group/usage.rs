@@ -0,0 +1,141 @@
+use std::str::FromStr;
+
+use avina::{Api, Token};
+use avina_test::spawn_app;
+
+#[tokio::test]
+async fn e2e_lib_admin_can_get_flavor_group_usage_for_all() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 1)
+        .await
+        .expect("Failed to setup test project");
+    let admin = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    let normal_user = test_project.normals[0].user.clone();
+    server
+        .mock_keystone_auth(&token, &admin.openstack_id, &admin.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor_group = server
+        .setup_test_flavor_group(test_project.project.id)
+        .await
+        .expect("Failed to setup test flavor group");
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let _server_state_1 = server
+        .setup_test_server_state(&flavor, &admin)
+        .await
+        .expect("Failed to setup test server state");
+    let _server_state_2 = server
+        .setup_test_server_state(&flavor, &normal_user)
+        .await
+        .expect("Failed to setup test server state");
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+    client
+        .flavor
+        .modify(flavor.id)
+        .group(flavor_group.id)
+        .weight(3)
+        .send()
+        .await
+        .unwrap();
+
+    // act and assert 1 - all
+    let usage = client.flavor_group.usage().all().await.unwrap();
+    assert_eq!(usage.len(), 2);
+    assert!(usage.iter().all(|u| u.flavorgroup_id == flavor_group.id));
+    assert!(usage.iter().all(|u| u.usage == 3));
+
+    // act and assert 2 - all aggregate
+    let aggregate = client.flavor_group.usage().all_aggregate().await.unwrap();
+    assert_eq!(aggregate.len(), 1);
+    assert_eq!(aggregate[0].flavorgroup_id, flavor_group.id);
+    assert_eq!(aggregate[0].flavorgroup_name, flavor_group.name);
+    assert_eq!(aggregate[0].usage, 6);
+}
+
+#[tokio::test]
+async fn e2e_lib_flavor_group_usage_ignores_flavors_without_group() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(0, 0, 1)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.normals[0].user.clone();
+    let token = test_project.normals[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+    let flavor = server
+        .setup_test_flavor()
+        .await
+        .expect("Failed to setup test flavor");
+    let _server_state = server
+        .setup_test_server_state(&flavor, &user)
+        .await
+        .expect("Failed to setup test server state");
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let usage = client.flavor_group.usage().mine().await.unwrap();
+
+    // assert
+    assert!(usage.is_empty());
+}
+
+#[tokio::test]
+async fn e2e_lib_normal_user_cannot_get_flavor_group_usage_for_all() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(0, 0, 1)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.normals[0].user.clone();
+    let token = test_project.normals[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let usage = client.flavor_group.usage().all_aggregate().await;
+
+    // assert
+    assert!(usage.is_err());
+    assert_eq!(
+        usage.unwrap_err().to_string(),
+        "Admin privileges required".to_string()
+    );
+}
@@ -0,0 +1,125 @@
+use std::str::FromStr;
+
+use avina::{Api, Token};
+use avina_test::{random_alphanumeric_string, random_uuid, spawn_app};
+use avina_wire::user::ProjectRetrieved;
+
+#[tokio::test]
+async fn e2e_lib_admin_can_import_users() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 2)
+        .await
+        .expect("Failed to setup test project");
+    let admin = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    let renamed_user = test_project.normals[0].user.clone();
+    let removed_user = test_project.normals[1].user.clone();
+    server
+        .mock_keystone_auth(&token, &admin.openstack_id, &admin.name)
+        .mount(&server.keystone_server)
+        .await;
+    let mut domain = test_project.project.clone();
+    domain.name = random_alphanumeric_string(10);
+    let new_user_name = random_alphanumeric_string(10);
+    let new_user_openstack_id = random_uuid();
+    let renamed_user_name = random_alphanumeric_string(10);
+    server
+        .mock_keystone_domain(&domain)
+        .mount(&server.keystone_server)
+        .await;
+    server
+        .mock_keystone_projects(
+            &domain,
+            &[
+                (&admin.openstack_id, &admin.name),
+                (&renamed_user.openstack_id, &renamed_user_name),
+                (&new_user_openstack_id, &new_user_name),
+            ],
+        )
+        .mount(&server.keystone_server)
+        .await;
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let import = client.user.import().await.unwrap();
+
+    // assert
+    assert_eq!(import.new_project_count, 0);
+    assert_eq!(import.updated_project_count, 1);
+    assert_eq!(import.new_user_count, 1);
+    assert_eq!(import.updated_user_count, 1);
+    assert_eq!(import.deactivated_user_count, 1);
+    let ProjectRetrieved::Detailed(project) =
+        client.project.get(test_project.project.id).await.unwrap()
+    else {
+        panic!("Expected ProjectDetailed")
+    };
+    assert_eq!(project.name, domain.name);
+    let renamed = client.user.get(renamed_user.id).await.unwrap();
+    assert_eq!(renamed.name, renamed_user_name);
+    let removed = client.user.get(removed_user.id).await.unwrap();
+    assert!(!removed.is_active);
+    let users = client
+        .user
+        .list()
+        .project(test_project.project.id)
+        .send()
+        .await
+        .unwrap();
+    assert!(users.iter().any(|u| u.openstack_id == new_user_openstack_id
+        && u.name == new_user_name
+        && u.is_active));
+
+    // act and assert 2 - a second import changes nothing
+    let import = client.user.import().await.unwrap();
+    assert_eq!(import.new_project_count, 0);
+    assert_eq!(import.updated_project_count, 0);
+    assert_eq!(import.new_user_count, 0);
+    assert_eq!(import.updated_user_count, 0);
+    assert_eq!(import.deactivated_user_count, 0);
+}
+
+#[tokio::test]
+async fn e2e_lib_normal_user_cannot_import_users() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(0, 0, 1)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.normals[0].user.clone();
+    let token = test_project.normals[0].token.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // act
+    let import = client.user.import().await;
+
+    // assert
+    assert!(import.is_err());
+    assert_eq!(
+        import.unwrap_err().to_string(),
+        "Admin privileges required".to_string()
+    );
+}
@@ -39,3 +39,74 @@ async fn e2e_lib_user_me_works() {
     assert_eq!(me.project.name, project.name);
     assert_eq!(me.project_name, project.name);
 }
+
+#[tokio::test]
+async fn e2e_lib_admin_can_impersonate_user() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(1, 0, 1)
+        .await
+        .expect("Failed to setup test project");
+    let admin = test_project.admins[0].user.clone();
+    let token = test_project.admins[0].token.clone();
+    let normal_user = test_project.normals[0].user.clone();
+    server
+        .mock_keystone_auth(&token, &admin.openstack_id, &admin.name)
+        .mount(&server.keystone_server)
+        .await;
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        Some(normal_user.id),
+        None,
+    )
+    .unwrap();
+
+    // act
+    let me = client.user.me().await.unwrap();
+
+    // assert
+    assert_eq!(me.id, normal_user.id);
+    assert_eq!(me.name, normal_user.name);
+    assert!(!me.is_staff);
+    assert_eq!(me.project.id, test_project.project.id);
+}
+
+#[tokio::test]
+async fn e2e_lib_normal_user_cannot_impersonate_user() {
+    // arrange
+    let server = spawn_app().await;
+    let test_project = server
+        .setup_test_project(0, 0, 2)
+        .await
+        .expect("Failed to setup test project");
+    let user = test_project.normals[0].user.clone();
+    let token = test_project.normals[0].token.clone();
+    let other_user = test_project.normals[1].user.clone();
+    server
+        .mock_keystone_auth(&token, &user.openstack_id, &user.name)
+        .mount(&server.keystone_server)
+        .await;
+
+    // arrange
+    let client = Api::new(
+        format!("{}/api", server.address),
+        Token::from_str(&token).unwrap(),
+        Some(other_user.id),
+        None,
+    )
+    .unwrap();
+
+    // act
+    let me = client.user.me().await;
+
+    // assert
+    assert!(me.is_err());
+    assert_eq!(
+        me.unwrap_err().to_string(),
+        "Admin privileges required for impersonation".to_string()
+    );
+}
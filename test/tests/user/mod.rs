@@ -1,4 +1,4 @@
+mod import;
 mod me;
 mod project;
 mod user;
-// TODO: test that non-existent ids return 404
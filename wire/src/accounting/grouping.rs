@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+// calendar unit by which accounting results are grouped into buckets
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeGrouping {
+    Year,
+    Month,
+    Day,
+}
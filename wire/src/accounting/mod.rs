@@ -1,7 +1,9 @@
+mod grouping;
 mod server_consumption;
 mod server_cost;
 mod server_state;
 
+pub use grouping::*;
 pub use server_consumption::*;
 pub use server_cost::*;
 pub use server_state::*;
@@ -3,6 +3,8 @@ use std::collections::HashMap;
 use chrono::{DateTime, FixedOffset};
 use serde::{Deserialize, Serialize};
 
+use crate::accounting::TimeGrouping;
+
 pub type ServerConsumptionFlavors = HashMap<String, f64>;
 
 pub type ServerConsumptionServer = ServerConsumptionFlavors;
@@ -34,4 +36,13 @@ pub struct ServerConsumptionParams {
     pub project: Option<u32>,
     pub all: Option<bool>,
     pub detail: Option<bool>,
+    pub by: Option<TimeGrouping>,
+}
+
+// consumption of one bucket [begin, end) when grouped by a time unit
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ServerConsumptionBucket<T> {
+    pub begin: DateTime<FixedOffset>,
+    pub end: DateTime<FixedOffset>,
+    pub consumption: T,
 }
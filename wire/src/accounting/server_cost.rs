@@ -5,6 +5,8 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "tabled")]
 use tabled::Tabled;
 
+use crate::accounting::TimeGrouping;
+
 #[cfg_attr(feature = "tabled", derive(Tabled))]
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct ServerCostSimple {
@@ -47,4 +49,95 @@ pub struct ServerCostParams {
     pub project: Option<u32>,
     pub all: Option<bool>,
     pub detail: Option<bool>,
+    pub by: Option<TimeGrouping>,
+}
+
+// cost of one bucket [begin, end) when grouped by a time unit
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ServerCostBucket<T> {
+    pub begin: DateTime<FixedOffset>,
+    pub end: DateTime<FixedOffset>,
+    pub cost: T,
+}
+
+#[cfg_attr(feature = "tabled", derive(Tabled))]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ServerCostPoint {
+    pub begin: DateTime<FixedOffset>,
+    pub end: DateTime<FixedOffset>,
+    pub cost: f64,
+    // cost of this and all previous points of the series
+    pub cumulative: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ServerCostSeriesParams {
+    pub begin: Option<DateTime<FixedOffset>>,
+    pub end: Option<DateTime<FixedOffset>>,
+    pub server: Option<String>,
+    pub user: Option<u32>,
+    pub project: Option<u32>,
+    pub all: Option<bool>,
+    // defaults to month
+    pub by: Option<TimeGrouping>,
+}
+
+// cost rate of a running server priced at the current flavor price
+#[cfg_attr(feature = "tabled", derive(Tabled))]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ServerCostRateServer {
+    pub server_id: String,
+    pub server_name: String,
+    pub flavor: String,
+    pub user_id: u32,
+    pub user_name: String,
+    pub hourly: f64,
+    pub daily: f64,
+    pub monthly: f64,
+}
+
+#[cfg_attr(feature = "tabled", derive(Tabled))]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ServerCostRateFlavor {
+    pub flavor: String,
+    pub server_count: u32,
+    pub hourly: f64,
+    pub daily: f64,
+    pub monthly: f64,
+}
+
+// a month is a twelfth of the running billing year
+#[cfg_attr(feature = "tabled", derive(Tabled))]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ServerCostRate {
+    pub hourly: f64,
+    pub daily: f64,
+    pub monthly: f64,
+    #[cfg_attr(feature = "tabled", tabled(skip))]
+    pub flavors: Vec<ServerCostRateFlavor>,
+    #[cfg_attr(feature = "tabled", tabled(skip))]
+    pub servers: Vec<ServerCostRateServer>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ServerCostRateParams {
+    pub server: Option<String>,
+    pub user: Option<u32>,
+    pub project: Option<u32>,
+    pub all: Option<bool>,
+}
+
+#[cfg_attr(feature = "tabled", derive(Tabled))]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ServerCostRollup {
+    // the rolled up days are the ones in [begin, end)
+    pub begin: DateTime<FixedOffset>,
+    pub end: DateTime<FixedOffset>,
+    pub day_count: u32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ServerCostRollupParams {
+    pub begin: Option<DateTime<FixedOffset>>,
+    pub end: Option<DateTime<FixedOffset>>,
 }
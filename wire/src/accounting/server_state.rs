@@ -6,7 +6,7 @@ use serde::{Deserialize, Serialize};
 use tabled::Tabled;
 
 #[cfg(feature = "tabled")]
-use crate::common::display_option;
+use crate::common::{display_len, display_option};
 
 #[cfg_attr(feature = "tabled", derive(Tabled))]
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
@@ -35,6 +35,194 @@ impl Display for ServerState {
 pub struct ServerStateImport {
     pub new_state_count: u32,
     pub end_state_count: u32,
+    // transitions caused by a flavor change, included in the counts above
+    #[serde(default)]
+    pub resize_count: u32,
+    // transitions caused by a name change, included in the counts above
+    #[serde(default)]
+    pub rename_count: u32,
+    // transitions caused by an owner change, included in the counts above
+    #[serde(default)]
+    pub transfer_count: u32,
+    // whether the changes below were rolled back instead of committed
+    #[serde(default)]
+    pub dry_run: bool,
+    #[serde(default)]
+    #[cfg_attr(feature = "tabled", tabled(display = "display_len"))]
+    pub new_states: Vec<ServerStateImportNewState>,
+    #[serde(default)]
+    #[cfg_attr(feature = "tabled", tabled(display = "display_len"))]
+    pub ended_states: Vec<ServerStateImportEndedState>,
+    #[serde(default)]
+    #[cfg_attr(feature = "tabled", tabled(display = "display_len"))]
+    pub skipped: Vec<ServerStateImportSkip>,
+}
+
+#[cfg_attr(feature = "tabled", derive(Tabled))]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ServerStateImportNewState {
+    pub begin: DateTime<FixedOffset>,
+    pub instance_id: String, // UUIDv4
+    pub instance_name: String,
+    pub flavor: u32,
+    pub status: String,
+    pub user: u32,
+}
+
+#[cfg_attr(feature = "tabled", derive(Tabled))]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ServerStateImportEndedState {
+    pub id: u32,
+    pub end: DateTime<FixedOffset>,
+    pub instance_id: String, // UUIDv4
+    pub instance_name: String,
+    pub status: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ServerStateImportParams {
+    pub dry_run: Option<bool>,
+}
+
+#[cfg_attr(feature = "tabled", derive(Tabled))]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ServerStateImportStatus {
+    pub scheduled: bool,
+    pub running: bool,
+    #[cfg_attr(feature = "tabled", tabled(display = "display_option"))]
+    pub last_begin: Option<DateTime<FixedOffset>>,
+    #[cfg_attr(feature = "tabled", tabled(display = "display_option"))]
+    pub last_end: Option<DateTime<FixedOffset>>,
+    #[cfg_attr(feature = "tabled", tabled(display = "display_option"))]
+    pub new_state_count: Option<u32>,
+    #[cfg_attr(feature = "tabled", tabled(display = "display_option"))]
+    pub end_state_count: Option<u32>,
+    #[cfg_attr(feature = "tabled", tabled(display = "display_option"))]
+    pub new_flavor_count: Option<u32>,
+    #[cfg_attr(feature = "tabled", tabled(display = "display_option"))]
+    pub error: Option<String>,
+}
+
+#[cfg_attr(feature = "tabled", derive(Tabled))]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ServerStateImportSkip {
+    pub instance_id: String,
+    pub instance_name: String,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ServerStateImportRunListParams {
+    // most recent runs to return, defaults to 100
+    pub limit: Option<u32>,
+    pub since: Option<DateTime<FixedOffset>>,
+}
+
+#[cfg_attr(feature = "tabled", derive(Tabled))]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ServerStateImportRun {
+    pub id: u32,
+    pub begin: DateTime<FixedOffset>,
+    pub end: DateTime<FixedOffset>,
+    // duration of the run in seconds
+    pub duration: f64,
+    pub new_state_count: u32,
+    pub end_state_count: u32,
+    #[cfg_attr(feature = "tabled", tabled(display = "display_option"))]
+    pub new_flavor_count: Option<u32>,
+    #[cfg_attr(feature = "tabled", tabled(display = "display_option"))]
+    pub error: Option<String>,
+    #[cfg_attr(feature = "tabled", tabled(display = "display_len"))]
+    pub skipped: Vec<ServerStateImportSkip>,
+}
+
+impl Display for ServerStateImportRun {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format!("ServerStateImportRun(id={})", self.id))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerStateIssueKind {
+    // the state ends after a later state of the same server begins
+    Overlap,
+    // no state of the server covers the time between the state and the next
+    Gap,
+    // the state is still open while a later state of the server is open
+    DuplicateOpen,
+    NegativeDuration,
+    UnknownStatus,
+    // the state ends exactly when an identical next state begins
+    Mergeable,
+}
+
+impl Display for ServerStateIssueKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ServerStateIssueKind::Overlap => "overlap",
+            ServerStateIssueKind::Gap => "gap",
+            ServerStateIssueKind::DuplicateOpen => "duplicate_open",
+            ServerStateIssueKind::NegativeDuration => "negative_duration",
+            ServerStateIssueKind::UnknownStatus => "unknown_status",
+            ServerStateIssueKind::Mergeable => "mergeable",
+        })
+    }
+}
+
+#[cfg_attr(feature = "tabled", derive(Tabled))]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ServerStateIssue {
+    pub kind: ServerStateIssueKind,
+    pub instance_id: String, // UUIDv4
+    pub server_state: u32,
+    // the later state of the same server for overlaps, gaps and merges
+    #[cfg_attr(feature = "tabled", tabled(display = "display_option"))]
+    pub other_server_state: Option<u32>,
+    pub repaired: bool,
+}
+
+#[cfg_attr(feature = "tabled", derive(Tabled))]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ServerStateCheck {
+    pub checked_state_count: u32,
+    pub overlap_count: u32,
+    pub gap_count: u32,
+    pub duplicate_open_count: u32,
+    pub negative_duration_count: u32,
+    pub unknown_status_count: u32,
+    pub mergeable_count: u32,
+    pub repair: bool,
+    pub closed_state_count: u32,
+    pub merged_state_count: u32,
+    #[cfg_attr(feature = "tabled", tabled(display = "display_len"))]
+    pub issues: Vec<ServerStateIssue>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ServerStateCheckParams {
+    pub repair: Option<bool>,
+}
+
+#[cfg_attr(feature = "tabled", derive(Tabled))]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ServerStateCompaction {
+    pub checked_state_count: u32,
+    pub merged_state_count: u32,
+}
+
+#[cfg_attr(feature = "tabled", derive(Tabled))]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ServerStateArchive {
+    // states that ended before this time were archived
+    pub before: DateTime<FixedOffset>,
+    pub archived_state_count: u32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ServerStateArchiveParams {
+    // number of past years to keep besides the current one
+    pub years: u32,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
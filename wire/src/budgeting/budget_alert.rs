@@ -0,0 +1,46 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetAlertKind {
+    User,
+    Project,
+}
+
+impl Display for BudgetAlertKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            BudgetAlertKind::User => "user",
+            BudgetAlertKind::Project => "project",
+        })
+    }
+}
+
+// sent through the notification channels, e.g. as webhook payload, when
+// the cost of a user or project reaches a threshold of its budget
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct BudgetAlert {
+    pub kind: BudgetAlertKind,
+    pub budget_id: u32,
+    // ID and name of the user or project, depending on the kind
+    pub owner_id: u32,
+    pub owner_name: String,
+    pub year: u32,
+    // percentage of the budget amount
+    pub threshold: u32,
+    pub cost: f64,
+    pub budget: u32,
+    pub created: DateTime<FixedOffset>,
+}
+
+impl Display for BudgetAlert {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format!(
+            "BudgetAlert(kind={}, budget_id={}, threshold={})",
+            self.kind, self.budget_id, self.threshold
+        ))
+    }
+}
@@ -0,0 +1,49 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "tabled")]
+use tabled::Tabled;
+
+#[cfg(feature = "tabled")]
+use crate::common::display_option;
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EnforcementAction {
+    Lock,
+    Shelve,
+    Stop,
+}
+
+impl Display for EnforcementAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            EnforcementAction::Lock => "lock",
+            EnforcementAction::Shelve => "shelve",
+            EnforcementAction::Stop => "stop",
+        })
+    }
+}
+
+// Nova action on a server of a user whose combined budget check is over,
+// either taken and recorded or, in a dry run, only reported.
+#[cfg_attr(feature = "tabled", derive(Tabled))]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct BudgetEnforcementAction {
+    pub user_id: u32,
+    pub user_name: String,
+    pub server_id: String,
+    pub server_name: String,
+    pub action: EnforcementAction,
+    pub dry_run: bool,
+    #[cfg_attr(feature = "tabled", tabled(display = "display_option"))]
+    pub error: Option<String>,
+    pub created: DateTime<FixedOffset>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct BudgetEnforcementData {
+    // defaults to the dry run setting of the enforcement configuration
+    pub dry_run: Option<bool>,
+}
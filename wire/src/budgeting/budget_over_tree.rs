@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use chrono::{DateTime, FixedOffset};
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
@@ -37,3 +38,11 @@ pub struct BudgetOverTree {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub flavors: Option<HashMap<String, f64>>,
 }
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BudgetOverTreeParams {
+    pub all: Option<bool>,
+    pub project: Option<u32>,
+    pub user: Option<u32>,
+    pub end: Option<DateTime<FixedOffset>>,
+}
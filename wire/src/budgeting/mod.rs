@@ -1,9 +1,13 @@
+mod budget_alert;
 mod budget_bulk_create;
+mod budget_enforcement;
 mod budget_over_tree;
 mod project_budget;
 mod user_budget;
 
+pub use budget_alert::*;
 pub use budget_bulk_create::*;
+pub use budget_enforcement::*;
 pub use budget_over_tree::*;
 pub use project_budget::*;
 pub use user_budget::*;
@@ -7,6 +7,8 @@ use sqlx::FromRow;
 #[cfg(feature = "tabled")]
 use tabled::Tabled;
 
+#[cfg(feature = "tabled")]
+use crate::common::display_option;
 use crate::common::is_false;
 
 #[cfg_attr(feature = "sqlx", derive(FromRow))]
@@ -43,6 +45,8 @@ pub struct ProjectBudgetOverParams {
     pub project: Option<u32>,
     pub all: Option<bool>,
     pub detail: Option<bool>,
+    // takes precedence over detail
+    pub forecast: Option<bool>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -103,3 +107,22 @@ pub struct ProjectBudgetOverDetail {
     pub cost: f64,
     pub budget: u32,
 }
+
+#[cfg_attr(feature = "tabled", derive(Tabled))]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ProjectBudgetOverForecast {
+    pub budget_id: u32,
+    pub project_id: u32,
+    pub project_name: String,
+    pub over: bool,
+    pub cost: f64,
+    pub budget: u32,
+    // cost per day of the servers running at the end of the period
+    pub rate: f64,
+    // cost at the end of the budget year if the rate stays the same
+    pub projected_cost: f64,
+    // when the projected cost reaches the budget, unless it is already
+    // reached or will not be reached within the budget year
+    #[cfg_attr(feature = "tabled", tabled(display = "display_option"))]
+    pub exhausted_at: Option<DateTime<FixedOffset>>,
+}
@@ -47,6 +47,8 @@ pub struct UserBudgetOverParams {
     pub all: Option<bool>,
     pub combined: Option<bool>,
     pub detail: Option<bool>,
+    // takes precedence over combined and detail
+    pub forecast: Option<bool>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -144,3 +146,22 @@ pub struct UserBudgetOverCombinedDetail {
 pub struct UserBudgetSync {
     pub updated_budget_count: u32,
 }
+
+#[cfg_attr(feature = "tabled", derive(Tabled))]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct UserBudgetOverForecast {
+    pub budget_id: u32,
+    pub user_id: u32,
+    pub user_name: String,
+    pub over: bool,
+    pub cost: f64,
+    pub budget: u32,
+    // cost per day of the servers running at the end of the period
+    pub rate: f64,
+    // cost at the end of the budget year if the rate stays the same
+    pub projected_cost: f64,
+    // when the projected cost reaches the budget, unless it is already
+    // reached or will not be reached within the budget year
+    #[cfg_attr(feature = "tabled", tabled(display = "display_option"))]
+    pub exhausted_at: Option<DateTime<FixedOffset>>,
+}
@@ -1,5 +1,10 @@
 use std::{borrow::Borrow, fmt::Display};
 
+use serde::{
+    Deserialize, Deserializer,
+    de::{Error, Unexpected},
+};
+
 pub fn display_option<T: Display>(option: &Option<T>) -> String {
     match option {
         Some(value) => value.to_string(),
@@ -7,6 +12,10 @@ pub fn display_option<T: Display>(option: &Option<T>) -> String {
     }
 }
 
+pub fn display_len<T>(values: &[T]) -> String {
+    values.len().to_string()
+}
+
 #[allow(dead_code)]
 pub fn is_true(b: impl Borrow<bool>) -> bool {
     *b.borrow()
@@ -15,3 +24,22 @@ pub fn is_true(b: impl Borrow<bool>) -> bool {
 pub fn is_false(b: impl Borrow<bool>) -> bool {
     !b.borrow()
 }
+
+// query flags like all=1, which the lib sends as the Python API expects them
+pub fn deserialize_optional_flag<'de, D>(
+    deserializer: D,
+) -> Result<Option<bool>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|value| match value.as_str() {
+            "1" | "true" => Ok(true),
+            "0" | "false" => Ok(false),
+            _ => Err(D::Error::invalid_value(
+                Unexpected::Str(&value),
+                &"1, 0, true or false",
+            )),
+        })
+        .transpose()
+}
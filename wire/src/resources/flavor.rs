@@ -7,7 +7,7 @@ use sqlx::{FromRow, Row, mysql::MySqlRow};
 use tabled::Tabled;
 
 #[cfg(feature = "tabled")]
-use crate::common::display_option;
+use crate::common::{display_len, display_option};
 use crate::resources::FlavorGroupMinimal;
 
 #[cfg_attr(feature = "tabled", derive(Tabled))]
@@ -96,6 +96,24 @@ pub struct FlavorListParams {
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct FlavorImport {
     pub new_flavor_count: u32,
+    // whether the changes below were rolled back instead of committed
+    #[serde(default)]
+    pub dry_run: bool,
+    #[serde(default)]
+    #[cfg_attr(feature = "tabled", tabled(display = "display_len"))]
+    pub new_flavors: Vec<FlavorImportChange>,
+}
+
+#[cfg_attr(feature = "tabled", derive(Tabled))]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct FlavorImportChange {
+    pub name: String,
+    pub openstack_id: String, // UUIDv4
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FlavorImportParams {
+    pub dry_run: Option<bool>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -145,6 +163,22 @@ impl FlavorModifyData {
     }
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FlavorUsageParams {
+    pub user: Option<u32>,
+    pub project: Option<u32>,
+    #[serde(
+        default,
+        deserialize_with = "crate::common::deserialize_optional_flag"
+    )]
+    pub all: Option<bool>,
+    #[serde(
+        default,
+        deserialize_with = "crate::common::deserialize_optional_flag"
+    )]
+    pub aggregate: Option<bool>,
+}
+
 #[cfg_attr(feature = "tabled", derive(Tabled))]
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct FlavorUsage {
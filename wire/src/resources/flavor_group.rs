@@ -134,6 +134,22 @@ impl FlavorGroupModifyData {
     }
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FlavorGroupUsageParams {
+    pub user: Option<u32>,
+    pub project: Option<u32>,
+    #[serde(
+        default,
+        deserialize_with = "crate::common::deserialize_optional_flag"
+    )]
+    pub all: Option<bool>,
+    #[serde(
+        default,
+        deserialize_with = "crate::common::deserialize_optional_flag"
+    )]
+    pub aggregate: Option<bool>,
+}
+
 #[cfg_attr(feature = "tabled", derive(Tabled))]
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct FlavorGroupUsage {
@@ -118,6 +118,12 @@ impl PartialEq<User> for UserDetailed {
 pub struct UserImport {
     pub new_project_count: u32,
     pub new_user_count: u32,
+    #[serde(default)]
+    pub updated_project_count: u32,
+    #[serde(default)]
+    pub updated_user_count: u32,
+    #[serde(default)]
+    pub deactivated_user_count: u32,
 }
 
 #[derive(Debug, Serialize, Deserialize)]